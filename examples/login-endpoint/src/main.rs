@@ -2,7 +2,8 @@ use poem_auth::api::types::LoginRequest;
 use poem_auth::prelude::*;
 use poem_auth::db::sqlite::SqliteUserDb;
 use poem_auth::providers::LocalAuthProvider;
-use poem_auth::jwt::JwtValidator;
+use poem_auth::poem_integration::{login, LoginOutcome, PoemAppState};
+use std::sync::Arc;
 use poem_auth::db::UserRecord;
 use poem_auth::password::hash_password;
 
@@ -44,8 +45,9 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     // Step 3: Create auth components
     println!("Step 3: Create authentication components");
-    let provider = LocalAuthProvider::new(db);
-    let jwt = JwtValidator::new("my-super-secret-key-should-be-at-least-16-chars")?;
+    let provider = Arc::new(LocalAuthProvider::new(db));
+    let state = PoemAppState::with_provider(provider, "my-super-secret-key-should-be-at-least-16-chars")?;
+    let jwt = state.jwt();
     println!("✓ LocalAuthProvider created");
     println!("✓ JwtValidator created\n");
 
@@ -59,55 +61,55 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== POST /login ===");
     println!("Request Body: {{\"username\": \"alice\", \"password\": \"password123\"}}");
 
-    match provider.authenticate("alice", "password123").await {
-        Ok(claims) => {
-            match jwt.generate_token(&claims) {
-                Ok(token_data) => {
+    match login(&state, &login_request("alice", "password123")).await {
+        LoginOutcome::Success { claims, token } => {
+            println!("Response: 200 OK");
+            println!("  {{");
+            println!("    \"token\": \"{}\",", &token.token[..50]);
+            println!("    \"token_type\": \"Bearer\",");
+            println!("    \"expires_in\": {},", claims.exp - claims.iat);
+            println!("    \"claims\": {{");
+            println!("      \"sub\": \"{}\",", claims.sub);
+            println!("      \"groups\": {:?}", claims.groups);
+            println!("    }}");
+            println!("  }}\n");
+
+            // Step 5: Demonstrate token validation
+            println!("=== GET /profile (Protected Route) ===");
+            println!("Authorization: Bearer {}", &token.token[..50]);
+
+            match jwt.verify_token(&token.token) {
+                Ok(decoded_claims) => {
                     println!("Response: 200 OK");
                     println!("  {{");
-                    println!("    \"token\": \"{}\",", &token_data.token[..50]);
-                    println!("    \"token_type\": \"Bearer\",");
-                    println!("    \"expires_in\": {},", claims.exp - claims.iat);
-                    println!("    \"claims\": {{");
-                    println!("      \"sub\": \"{}\",", claims.sub);
-                    println!("      \"groups\": {:?}", claims.groups);
-                    println!("    }}");
+                    println!("    \"username\": \"{}\",", decoded_claims.sub);
+                    println!("    \"groups\": {:?},", decoded_claims.groups);
+                    println!("    \"message\": \"Access granted\"");
                     println!("  }}\n");
-
-                    // Step 5: Demonstrate token validation
-                    println!("=== GET /profile (Protected Route) ===");
-                    println!("Authorization: Bearer {}", &token_data.token[..50]);
-
-                    match jwt.verify_token(&token_data.token) {
-                        Ok(decoded_claims) => {
-                            println!("Response: 200 OK");
-                            println!("  {{");
-                            println!("    \"username\": \"{}\",", decoded_claims.sub);
-                            println!("    \"groups\": {:?},", decoded_claims.groups);
-                            println!("    \"message\": \"Access granted\"");
-                            println!("  }}\n");
-                        }
-                        Err(e) => println!("Response: 401 Unauthorized - {}\n", e),
-                    }
-                }
-                Err(e) => {
-                    println!("Response: 500 Internal Server Error");
-                    println!("  {{\"error\": \"Failed to generate token: {}\"}}\n", e);
                 }
+                Err(e) => println!("Response: 401 Unauthorized - {}\n", e),
             }
         }
-        Err(e) => {
+        LoginOutcome::Unavailable(e) => {
+            println!("Response: 503 Service Unavailable");
+            println!("  {{\"error\": \"service_unavailable\"}} ({})\n", e);
+        }
+        LoginOutcome::TokenError(e) => {
+            println!("Response: 500 Internal Server Error");
+            println!("  {{\"error\": \"Failed to generate token: {}\"}}\n", e);
+        }
+        LoginOutcome::InvalidCredentials | LoginOutcome::Disabled => {
             println!("Response: 401 Unauthorized");
-            println!("  {{\"error\": \"invalid_credentials\", \"message\": \"{}\"}}\n", e);
+            println!("  {{\"error\": \"invalid_credentials\"}}\n");
         }
     }
 
     // Step 6: Test invalid credentials
     println!("=== POST /login (Invalid Credentials) ===");
     println!("Request Body: {{\"username\": \"alice\", \"password\": \"wrongpassword\"}}");
-    match provider.authenticate("alice", "wrongpassword").await {
-        Ok(_) => println!("ERROR: Should have failed!"),
-        Err(_) => {
+    match login(&state, &login_request("alice", "wrongpassword")).await {
+        LoginOutcome::Success { .. } => println!("ERROR: Should have failed!"),
+        _ => {
             println!("Response: 401 Unauthorized");
             println!("  {{\"error\": \"invalid_credentials\", \"message\": \"Invalid username or password\"}}\n");
         }
@@ -115,8 +117,7 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("=== Example Complete ===");
     println!("\nKey Concepts:");
-    println!("  - LocalAuthProvider.authenticate() validates credentials");
-    println!("  - JwtValidator.generate_token() creates signed JWT tokens");
+    println!("  - login() validates credentials and signs a JWT token");
     println!("  - JwtValidator.verify_token() validates token signatures");
     println!("  - Tokens can be used to authenticate subsequent requests");
 
@@ -130,3 +131,12 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Build the JSON body a client would POST to /login.
+fn login_request(username: &str, password: &str) -> LoginRequest {
+    LoginRequest {
+        username: username.to_string(),
+        password: password.to_string(),
+        provider: None,
+    }
+}
@@ -5,6 +5,7 @@ use poem::{
 use poem_auth::{
     initialize_from_config, PoemAppState, AuthProvider, UserClaims,
     api::types::LoginRequest,
+    poem_integration::{self, LoginOutcome},
     poem_integration::guards::{AuthGuard, HasGroup, HasAnyGroup},
     LoginResponseBuilder,
     require_group, require_any_groups, require_all_groups,
@@ -28,15 +29,12 @@ fn health() -> String {
 /// Simplified using LoginResponseBuilder for minimal boilerplate.
 #[handler]
 async fn login(Json(req): Json<LoginRequest>) -> Response {
-    let state = PoemAppState::get();
-    match state.provider.authenticate(&req.username, &req.password).await {
-        Ok(claims) => {
-            match state.jwt.generate_token(&claims) {
-                Ok(token_data) => LoginResponseBuilder::success(&claims, &token_data),
-                Err(_) => LoginResponseBuilder::token_generation_failed(),
-            }
-        }
-        Err(_) => LoginResponseBuilder::invalid_credentials(),
+    match poem_integration::login(PoemAppState::get(), &req).await {
+        LoginOutcome::Success { claims, token } => LoginResponseBuilder::success(&claims, &token),
+        LoginOutcome::InvalidCredentials => LoginResponseBuilder::invalid_credentials(),
+        LoginOutcome::Disabled => LoginResponseBuilder::user_disabled(&req.username),
+        LoginOutcome::Unavailable(_) => LoginResponseBuilder::service_unavailable(),
+        LoginOutcome::TokenError(_) => LoginResponseBuilder::token_generation_failed(),
     }
 }
 
@@ -144,7 +142,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== poem + poem_auth Example ===\n");
 
     // Initialize everything from config file in ONE LINE!
-    initialize_from_config("auth.toml").await?;
+    let summary = initialize_from_config("auth.toml").await?;
+    println!("{}\n", summary);
 
     // Build app routes
     println!("Step 4: Building Poem app with routes\n");
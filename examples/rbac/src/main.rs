@@ -1,11 +1,11 @@
-use poem::{listener::TcpListener, App, get, post, web::Json, http::StatusCode};
-use poem_auth::api::types::{LoginRequest, LoginResponse, ErrorResponse, UserClaimsResponse};
+use poem::{get, handler, http::StatusCode, Error, listener::TcpListener, post, web::Json, Response, Route, Server};
+use poem_auth::api::types::LoginRequest;
 use poem_auth::prelude::*;
 use poem_auth::db::sqlite::SqliteUserDb;
 use poem_auth::providers::LocalAuthProvider;
-use poem_auth::jwt::JwtValidator;
 use poem_auth::db::UserRecord;
 use poem_auth::password::hash_password;
+use poem_auth::poem_integration::{login, LoginOutcome, PoemAppState};
 use std::sync::Arc;
 
 #[tokio::main]
@@ -18,19 +18,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create test users with different roles
     create_test_users(&db).await?;
 
-    let provider = LocalAuthProvider::new(db.clone());
-    let jwt = JwtValidator::new("my-super-secret-key-should-be-at-least-16-chars")?;
+    let provider = Arc::new(LocalAuthProvider::new(db));
+    PoemAppState::with_provider(provider, "my-super-secret-key-should-be-at-least-16-chars")?
+        .init()
+        .map_err(|_| "app state already initialized")?;
 
-    let provider = Arc::new(provider);
-    let jwt = Arc::new(jwt);
-
-    let app = App::new()
+    let app = Route::new()
         .at("/health", get(health_check))
-        .at("/login", post({
-            let provider = provider.clone();
-            let jwt = jwt.clone();
-            move |req| login(req, provider.clone(), jwt.clone())
-        }))
+        .at("/login", post(login_handler))
         .at("/user", get(user_handler))
         .at("/admin", get(admin_handler))
         .at("/developer", get(developer_handler));
@@ -42,7 +37,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  bob (password: password123) - groups: users, admins");
     println!("\nLogin and get token, then access /user, /admin, or /developer");
 
-    app.run(TcpListener::bind(addr)).await?;
+    Server::new(TcpListener::bind(addr)).run(app).await?;
     Ok(())
 }
 
@@ -68,49 +63,44 @@ async fn create_test_users(db: &SqliteUserDb) -> Result<(), Box<dyn std::error::
     Ok(())
 }
 
-async fn health_check() -> &'static str {
+#[handler]
+fn health_check() -> &'static str {
     "OK"
 }
 
-async fn login(
-    req: Json<LoginRequest>,
-    provider: Arc<LocalAuthProvider>,
-    jwt: Arc<JwtValidator>,
-) -> Result<Json<LoginResponse>, Json<ErrorResponse>> {
-    let claims = provider.authenticate(&req.username, &req.password)
-        .await
-        .map_err(|_| Json(ErrorResponse::invalid_credentials()))?;
-
-    let token = jwt.encode(&claims)
-        .map_err(|_| Json(ErrorResponse::new("error", "Failed to generate token")))?;
-
-    Ok(Json(LoginResponse {
-        token,
-        token_type: "Bearer".to_string(),
-        expires_in: claims.exp - claims.iat,
-        claims: UserClaimsResponse::from_claims(claims),
-    }))
+#[handler]
+async fn login_handler(Json(req): Json<LoginRequest>) -> Response {
+    match login(PoemAppState::get(), &req).await {
+        LoginOutcome::Success { claims, token } => LoginResponseBuilder::success(&claims, &token),
+        LoginOutcome::InvalidCredentials => LoginResponseBuilder::invalid_credentials(),
+        LoginOutcome::Disabled => LoginResponseBuilder::user_disabled(&req.username),
+        LoginOutcome::Unavailable(_) => LoginResponseBuilder::service_unavailable(),
+        LoginOutcome::TokenError(_) => LoginResponseBuilder::token_generation_failed(),
+    }
 }
 
-async fn user_handler(claims: UserClaims) -> Result<String, (StatusCode, String)> {
+#[handler]
+fn user_handler(claims: UserClaims) -> String {
     // All authenticated users can access this
-    Ok(format!("Hello {}! You are authenticated.", claims.sub))
+    format!("Hello {}! You are authenticated.", claims.sub)
 }
 
-async fn admin_handler(claims: UserClaims) -> Result<String, (StatusCode, String)> {
+#[handler]
+fn admin_handler(claims: UserClaims) -> poem::Result<String> {
     // Only admins can access this
     if claims.has_group("admins") {
         Ok(format!("Welcome admin {}! You have full access.", claims.sub))
     } else {
-        Err((StatusCode::FORBIDDEN, "Admin access required".to_string()))
+        Err(Error::from_string("Admin access required", StatusCode::FORBIDDEN))
     }
 }
 
-async fn developer_handler(claims: UserClaims) -> Result<String, (StatusCode, String)> {
+#[handler]
+fn developer_handler(claims: UserClaims) -> poem::Result<String> {
     // Only developers can access this
     if claims.has_group("developers") {
         Ok(format!("Welcome developer {}! Access to developer tools.", claims.sub))
     } else {
-        Err((StatusCode::FORBIDDEN, "Developer access required".to_string()))
+        Err(Error::from_string("Developer access required", StatusCode::FORBIDDEN))
     }
 }
@@ -0,0 +1,35 @@
+-- Normalized group membership used for group queries.
+--
+-- The JSON `users.groups` column stays the source of truth; triggers keep
+-- user_groups in sync with it on every write, so no write path has to
+-- maintain both.
+
+CREATE TABLE IF NOT EXISTS user_groups (
+    user_id INTEGER NOT NULL,
+    group_name TEXT NOT NULL,
+    PRIMARY KEY (user_id, group_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_groups_group ON user_groups(group_name, user_id);
+
+CREATE TRIGGER IF NOT EXISTS users_groups_after_insert AFTER INSERT ON users
+BEGIN
+    INSERT OR IGNORE INTO user_groups (user_id, group_name)
+        SELECT NEW.id, value FROM json_each(NEW.groups);
+END;
+
+CREATE TRIGGER IF NOT EXISTS users_groups_after_update AFTER UPDATE OF groups ON users
+BEGIN
+    DELETE FROM user_groups WHERE user_id = NEW.id;
+    INSERT OR IGNORE INTO user_groups (user_id, group_name)
+        SELECT NEW.id, value FROM json_each(NEW.groups);
+END;
+
+CREATE TRIGGER IF NOT EXISTS users_groups_after_delete AFTER DELETE ON users
+BEGIN
+    DELETE FROM user_groups WHERE user_id = OLD.id;
+END;
+
+-- Backfill databases created before user_groups existed
+INSERT OR IGNORE INTO user_groups (user_id, group_name)
+    SELECT users.id, json_each.value FROM users, json_each(users.groups);
@@ -18,31 +18,43 @@
 //!     "Admin area".into()
 //! }
 //! ```
+//!
+//! The macros can be stacked; every requirement must pass (AND), and they
+//! are checked in the order they are written. Put them above `#[handler]`.
+//!
+//! ```ignore
+//! #[require_group("admins")]
+//! #[require_provider("ldap")]
+//! #[handler]
+//! async fn rotate_keys(claims: UserClaims) -> Response {
+//!     "Keys rotated".into()
+//! }
+//! ```
 
 use proc_macro::TokenStream;
-use quote::{quote, format_ident};
+use quote::quote;
 use syn::{
-    parse_macro_input, ItemFn, LitStr, Token, parse::{Parse, ParseStream},
-    FnArg, Pat, PatType,
+    parse_macro_input, Attribute, ItemFn, LitStr, Meta, Token, parse::{Parse, ParseStream},
+    FnArg, Pat, PatType, ReturnType,
 };
 
-/// Arguments parsed from macro attributes
-struct GroupArgs {
-    groups: Vec<String>,
+/// String literal arguments parsed from macro attributes (group or scope names)
+struct NameArgs {
+    names: Vec<String>,
 }
 
-impl Parse for GroupArgs {
+impl Parse for NameArgs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let mut groups = Vec::new();
+        let mut names = Vec::new();
 
         // Handle empty case (for single group macros with no parens)
         if input.is_empty() {
-            return Ok(GroupArgs { groups });
+            return Ok(NameArgs { names });
         }
 
         loop {
             let lit: LitStr = input.parse()?;
-            groups.push(lit.value());
+            names.push(lit.value());
 
             if input.is_empty() {
                 break;
@@ -56,99 +68,365 @@ impl Parse for GroupArgs {
             }
         }
 
-        Ok(GroupArgs { groups })
+        Ok(NameArgs { names })
     }
 }
 
-/// Check if a function parameter is `claims: UserClaims`
-fn has_claims_parameter(input: &ItemFn) -> bool {
-    input
-        .sig
-        .inputs
-        .iter()
-        .any(|arg| {
-            if let FnArg::Typed(PatType { pat, ty, .. }) = arg {
-                if let Pat::Ident(pat_ident) = &**pat {
-                    if pat_ident.ident == "claims" {
-                        // Check if type is UserClaims
-                        if let syn::Type::Path(type_path) = &**ty {
-                            if let Some(last_segment) = type_path.path.segments.last() {
-                                return last_segment.ident == "UserClaims";
-                            }
-                        }
-                    }
-                }
+/// How the handler receives its `claims` parameter
+#[derive(Clone, Copy)]
+enum ClaimsParam {
+    /// `claims: UserClaims`
+    Owned,
+    /// `claims: &UserClaims`
+    Borrowed,
+}
+
+/// Check if the type names `UserClaims` (any path ending in it, e.g.
+/// `poem_auth::UserClaims`)
+fn is_user_claims(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "UserClaims"),
+        syn::Type::Paren(paren) => is_user_claims(&paren.elem),
+        syn::Type::Group(group) => is_user_claims(&group.elem),
+        _ => false,
+    }
+}
+
+/// Find the `claims: UserClaims` or `claims: &UserClaims` parameter
+fn claims_parameter(input: &ItemFn) -> Option<ClaimsParam> {
+    input.sig.inputs.iter().find_map(|arg| {
+        let FnArg::Typed(PatType { pat, ty, .. }) = arg else {
+            return None;
+        };
+        let Pat::Ident(pat_ident) = &**pat else {
+            return None;
+        };
+        if pat_ident.ident != "claims" {
+            return None;
+        }
+        match &**ty {
+            syn::Type::Reference(reference) if is_user_claims(&reference.elem) => {
+                Some(ClaimsParam::Borrowed)
             }
-            false
-        })
+            ty if is_user_claims(ty) => Some(ClaimsParam::Owned),
+            _ => None,
+        }
+    })
 }
 
-/// Require a single group membership
+/// Build the expression that evaluates `__guard` against `claims`.
 ///
-/// Returns 403 Forbidden if the user doesn't have the specified group.
+/// Async handlers go through `AsyncAuthGuard` so guards needing lookups can be
+/// awaited; sync handlers fall back to `AuthGuard`.
+fn guard_check_expr(input: &ItemFn, param: ClaimsParam) -> proc_macro2::TokenStream {
+    let claims = match param {
+        ClaimsParam::Owned => quote! { &claims },
+        ClaimsParam::Borrowed => quote! { claims },
+    };
+    if input.sig.asyncness.is_some() {
+        quote! { ::poem_auth::AsyncAuthGuard::check(&__guard, #claims).await }
+    } else {
+        quote! { ::poem_auth::AuthGuard::check(&__guard, #claims) }
+    }
+}
+
+/// Check if the handler returns a `Result` (including aliases like `poem::Result<T>`)
+fn returns_result(input: &ItemFn) -> bool {
+    match &input.sig.output {
+        ReturnType::Type(_, ty) => match &**ty {
+            syn::Type::Path(type_path) => type_path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+/// Name of the request parameter added to `#[handler]` functions
+const REQUEST_PARAM: &str = "__poem_auth_request";
+
+/// Check if the function is also annotated with Poem's `#[handler]`
+fn is_handler(input: &ItemFn) -> bool {
+    input
+        .attrs
+        .iter()
+        .any(|attr| attr.path().segments.last().is_some_and(|segment| segment.ident == "handler"))
+}
+
+/// Build the expression that picks the `DenialMode` for a denial.
 ///
-/// # Example
+/// `#[handler]` functions get an extra `&poem::Request` parameter so the
+/// mode comes from the same state the `UserClaims` extractor used (one
+/// attached with `.data(state)`, or the global one). Plain functions have
+/// no request and use the global state's mode.
+fn denial_mode_expr(input: &ItemFn) -> proc_macro2::TokenStream {
+    if is_handler(input) {
+        let request = syn::Ident::new(REQUEST_PARAM, proc_macro2::Span::call_site());
+        quote! { ::poem_auth::DenialMode::for_request(#request) }
+    } else {
+        quote! { ::poem_auth::DenialMode::current() }
+    }
+}
+
+/// Build the early return taken when the guard denies the request.
 ///
-/// ```ignore
-/// #[require_group("admins")]
-/// #[handler]
-/// async fn admin_panel(claims: UserClaims) -> Response {
-///     "Welcome to admin panel".into()
-/// }
-/// ```
+/// The denial body lists the `required` names and the match `mode`
+/// (`single`, `any` or `all`) next to the human-readable message.
 ///
-/// # Requirements
+/// `Result`-returning handlers get `Err(..)` built from a `poem::Error`, so
+/// their error type must implement `From<poem::Error>` (as `poem::Error`
+/// itself does); other handlers return the denial response directly.
+fn denial_return(
+    input: &ItemFn,
+    error_msg: &str,
+    mode: &str,
+    required: &[String],
+) -> proc_macro2::TokenStream {
+    let required = quote! { &[#(#required),*] };
+    let denial_mode = denial_mode_expr(input);
+    if returns_result(input) {
+        quote! {
+            return ::std::result::Result::Err(::std::convert::From::from(
+                #denial_mode.deny_requirement_error(#error_msg, #mode, #required),
+            ));
+        }
+    } else {
+        quote! {
+            return #denial_mode.deny_requirement(#error_msg, #mode, #required);
+        }
+    }
+}
+
+/// One of the `require_*` attribute macros
+#[derive(Clone, Copy)]
+enum RequireMacro {
+    Group,
+    AnyGroups,
+    AllGroups,
+    Provider,
+    AnyScope,
+    AllScopes,
+}
+
+impl RequireMacro {
+    /// Recognize a stacked attribute by the last segment of its path.
+    fn from_attr(attr: &Attribute) -> Option<Self> {
+        let name = attr.path().segments.last()?.ident.to_string();
+        match name.as_str() {
+            "require_group" => Some(Self::Group),
+            "require_any_groups" => Some(Self::AnyGroups),
+            "require_all_groups" => Some(Self::AllGroups),
+            "require_provider" => Some(Self::Provider),
+            "require_any_scope" => Some(Self::AnyScope),
+            "require_all_scopes" => Some(Self::AllScopes),
+            _ => None,
+        }
+    }
+
+    /// Build the guard and denial details for `names`.
+    fn requirement(self, names: Vec<String>) -> Requirement {
+        let first = names.first().cloned().unwrap_or_default();
+        let (kind, mode, guard, error_msg) = match self {
+            Self::Group => (
+                "group",
+                "single",
+                quote! { ::poem_auth::HasGroup(#first.to_string()) },
+                format!("Forbidden: requires '{}' group", first),
+            ),
+            Self::AnyGroups => (
+                "group",
+                "any",
+                quote! { ::poem_auth::HasAnyGroup(vec![#(#names.to_string()),*]) },
+                if names.len() == 1 {
+                    format!("Forbidden: requires '{}' group", first)
+                } else {
+                    format!("Forbidden: requires one of groups: {}", names.join(", "))
+                },
+            ),
+            Self::AllGroups => (
+                "group",
+                "all",
+                quote! { ::poem_auth::HasAllGroups(vec![#(#names.to_string()),*]) },
+                format!("Forbidden: requires all groups: {}", names.join(", ")),
+            ),
+            Self::Provider => (
+                "provider",
+                "single",
+                quote! { ::poem_auth::HasProvider(#first.to_string()) },
+                format!("Forbidden: requires authentication via '{}'", first),
+            ),
+            Self::AnyScope => (
+                "scope",
+                "any",
+                quote! { ::poem_auth::HasAnyScope(vec![#(#names.to_string()),*]) },
+                if names.len() == 1 {
+                    format!("Forbidden: requires '{}' scope", first)
+                } else {
+                    format!("Forbidden: requires one of scopes: {}", names.join(", "))
+                },
+            ),
+            Self::AllScopes => (
+                "scope",
+                "all",
+                quote! { ::poem_auth::HasAllScopes(vec![#(#names.to_string()),*]) },
+                format!("Forbidden: requires all scopes: {}", names.join(", ")),
+            ),
+        };
+        Requirement {
+            names,
+            kind,
+            mode,
+            guard,
+            error_msg,
+        }
+    }
+}
+
+/// A guard to check and what to report when it denies the request
+struct Requirement {
+    names: Vec<String>,
+    /// "group", "scope" or "provider", only used in compile errors
+    kind: &'static str,
+    /// Reported in the denial body: "single", "any" or "all"
+    mode: &'static str,
+    guard: proc_macro2::TokenStream,
+    error_msg: String,
+}
+
+/// Parse the arguments of the macro being expanded and apply it.
+fn expand(which: RequireMacro, args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as NameArgs);
+    let item_fn = parse_macro_input!(input as ItemFn);
+
+    authorize(which.requirement(args.names), item_fn)
+}
+
+/// Validate the handler and prepend the checks to its body.
 ///
-/// The handler must have a `claims: UserClaims` parameter. The handler
-/// function must return a type that implements `IntoResponse`.
-#[proc_macro_attribute]
-pub fn require_group(args: TokenStream, input: TokenStream) -> TokenStream {
-    let args = parse_macro_input!(args as GroupArgs);
-    let mut item_fn = parse_macro_input!(input as ItemFn);
+/// `require_*` attributes stacked below the one being expanded are taken off
+/// the handler and checked here too, in the order they are written, so
+/// stacking reads as AND and the topmost failing requirement is the one
+/// reported. On denial the handler returns early with the denial (see
+/// `denial_return`).
+fn authorize(requirement: Requirement, mut item_fn: ItemFn) -> TokenStream {
+    let mut requirements = vec![requirement];
+    let mut attrs = Vec::new();
+    for attr in std::mem::take(&mut item_fn.attrs) {
+        let Some(which) = RequireMacro::from_attr(&attr) else {
+            attrs.push(attr);
+            continue;
+        };
+        let names = match &attr.meta {
+            Meta::Path(_) => Vec::new(),
+            _ => match attr.parse_args::<NameArgs>() {
+                Ok(args) => args.names,
+                Err(err) => return err.to_compile_error().into(),
+            },
+        };
+        requirements.push(which.requirement(names));
+    }
+    item_fn.attrs = attrs;
 
-    if args.groups.is_empty() {
-        return syn::Error::new_spanned(&item_fn.sig, "At least one group must be specified")
-            .to_compile_error()
-            .into();
+    if let Some(empty) = requirements.iter().find(|r| r.names.is_empty()) {
+        return syn::Error::new_spanned(
+            &item_fn.sig,
+            format!("At least one {} must be specified", empty.kind),
+        )
+        .to_compile_error()
+        .into();
     }
 
-    if !has_claims_parameter(&item_fn) {
+    // `require_group` and `require_provider` check a single name
+    if let Some(multiple) = requirements.iter().find(|r| r.mode == "single" && r.names.len() > 1) {
         return syn::Error::new_spanned(
             &item_fn.sig,
-            "Handler must have a `claims: UserClaims` parameter to use authorization macros",
+            format!("Only one {} can be specified, got {}", multiple.kind, multiple.names.join(", ")),
         )
         .to_compile_error()
         .into();
     }
 
-    let group = &args.groups[0];
-    let error_msg = format!("Forbidden: requires '{}' group", group);
+    let Some(param) = claims_parameter(&item_fn) else {
+        return syn::Error::new_spanned(
+            &item_fn.sig,
+            "Handler must have a `claims: UserClaims` (or `claims: &UserClaims`) parameter to use authorization macros",
+        )
+        .to_compile_error()
+        .into();
+    };
 
-    // Insert guard check at start of function body
-    let original_block = item_fn.block.clone();
-    let guard_check = quote! {
-        let __guard = ::poem_auth::HasGroup(#group.to_string());
-        if !__guard.check(&claims) {
-            return (
-                ::poem::http::StatusCode::FORBIDDEN,
-                ::poem::web::Json(::serde_json::json!({
-                    "error": #error_msg
-                }))
-            ).into_response();
+    // Insert guard checks at start of function body
+    let guard_passes = guard_check_expr(&item_fn, param);
+    let guard_checks: Vec<_> = requirements.iter().map(|requirement| {
+        let guard = &requirement.guard;
+        let deny = denial_return(
+            &item_fn,
+            &requirement.error_msg,
+            requirement.mode,
+            &requirement.names,
+        );
+        quote! {
+            {
+                let __guard = #guard;
+                if !#guard_passes {
+                    #deny
+                }
+            }
         }
-    };
+    }).collect();
+    let original_block = item_fn.block.clone();
 
-    item_fn.block = Box::new(syn::parse_quote!({
-        #guard_check
+    if is_handler(&item_fn) {
+        let request = syn::Ident::new(REQUEST_PARAM, proc_macro2::Span::call_site());
+        item_fn.sig.inputs.push(syn::parse_quote!(#request: &::poem::Request));
+    }
+
+    *item_fn.block = syn::parse_quote!({
+        #(#guard_checks)*
         #original_block
-    }));
+    });
 
     quote!(#item_fn).into()
 }
 
+/// Require a single group membership
+///
+/// Returns 403 Forbidden if the user doesn't have the specified group
+/// (or 404, depending on the configured `DenialMode`). The 403 body is
+/// `{"error": "...", "required": ["admins"], "mode": "single"}`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[require_group("admins")]
+/// #[handler]
+/// async fn admin_panel(claims: UserClaims) -> Response {
+///     "Welcome to admin panel".into()
+/// }
+/// ```
+///
+/// # Requirements
+///
+/// The handler must have a `claims: UserClaims` (or `&UserClaims`)
+/// parameter; any path ending in `UserClaims` is accepted. The handler
+/// function must return a type that implements `IntoResponse`, or a
+/// `Result<T, E>` whose `E` implements `From<poem::Error>`.
+#[proc_macro_attribute]
+pub fn require_group(args: TokenStream, input: TokenStream) -> TokenStream {
+    expand(RequireMacro::Group, args, input)
+}
+
 /// Require membership in ANY of the specified groups (OR logic)
 ///
-/// Returns 403 Forbidden if the user doesn't have at least one of the groups.
+/// Returns 403 Forbidden if the user doesn't have at least one of the groups
+/// (or 404, depending on the configured `DenialMode`).
 ///
 /// # Example
 ///
@@ -162,60 +440,19 @@ pub fn require_group(args: TokenStream, input: TokenStream) -> TokenStream {
 ///
 /// # Requirements
 ///
-/// The handler must have a `claims: UserClaims` parameter. The handler
-/// function must return a type that implements `IntoResponse`.
+/// The handler must have a `claims: UserClaims` (or `&UserClaims`)
+/// parameter; any path ending in `UserClaims` is accepted. The handler
+/// function must return a type that implements `IntoResponse`, or a
+/// `Result<T, E>` whose `E` implements `From<poem::Error>`.
 #[proc_macro_attribute]
 pub fn require_any_groups(args: TokenStream, input: TokenStream) -> TokenStream {
-    let args = parse_macro_input!(args as GroupArgs);
-    let mut item_fn = parse_macro_input!(input as ItemFn);
-
-    if args.groups.is_empty() {
-        return syn::Error::new_spanned(&item_fn.sig, "At least one group must be specified")
-            .to_compile_error()
-            .into();
-    }
-
-    if !has_claims_parameter(&item_fn) {
-        return syn::Error::new_spanned(
-            &item_fn.sig,
-            "Handler must have a `claims: UserClaims` parameter to use authorization macros",
-        )
-        .to_compile_error()
-        .into();
-    }
-
-    let groups_vec = args.groups.clone();
-    let error_msg = if args.groups.len() == 1 {
-        format!("Forbidden: requires '{}' group", args.groups[0])
-    } else {
-        format!("Forbidden: requires one of groups: {}", args.groups.join(", "))
-    };
-
-    // Insert guard check at start of function body
-    let original_block = item_fn.block.clone();
-    let guard_check = quote! {
-        let __guard = ::poem_auth::HasAnyGroup(vec![#(#groups_vec.to_string()),*]);
-        if !__guard.check(&claims) {
-            return (
-                ::poem::http::StatusCode::FORBIDDEN,
-                ::poem::web::Json(::serde_json::json!({
-                    "error": #error_msg
-                }))
-            ).into_response();
-        }
-    };
-
-    item_fn.block = Box::new(syn::parse_quote!({
-        #guard_check
-        #original_block
-    }));
-
-    quote!(#item_fn).into()
+    expand(RequireMacro::AnyGroups, args, input)
 }
 
 /// Require membership in ALL of the specified groups (AND logic)
 ///
-/// Returns 403 Forbidden if the user doesn't have all of the groups.
+/// Returns 403 Forbidden if the user doesn't have all of the groups
+/// (or 404, depending on the configured `DenialMode`).
 ///
 /// # Example
 ///
@@ -229,49 +466,92 @@ pub fn require_any_groups(args: TokenStream, input: TokenStream) -> TokenStream
 ///
 /// # Requirements
 ///
-/// The handler must have a `claims: UserClaims` parameter. The handler
-/// function must return a type that implements `IntoResponse`.
+/// The handler must have a `claims: UserClaims` (or `&UserClaims`)
+/// parameter; any path ending in `UserClaims` is accepted. The handler
+/// function must return a type that implements `IntoResponse`, or a
+/// `Result<T, E>` whose `E` implements `From<poem::Error>`.
 #[proc_macro_attribute]
 pub fn require_all_groups(args: TokenStream, input: TokenStream) -> TokenStream {
-    let args = parse_macro_input!(args as GroupArgs);
-    let mut item_fn = parse_macro_input!(input as ItemFn);
-
-    if args.groups.is_empty() {
-        return syn::Error::new_spanned(&item_fn.sig, "At least one group must be specified")
-            .to_compile_error()
-            .into();
-    }
-
-    if !has_claims_parameter(&item_fn) {
-        return syn::Error::new_spanned(
-            &item_fn.sig,
-            "Handler must have a `claims: UserClaims` parameter to use authorization macros",
-        )
-        .to_compile_error()
-        .into();
-    }
-
-    let groups_vec = args.groups.clone();
-    let error_msg = format!("Forbidden: requires all groups: {}", args.groups.join(", "));
+    expand(RequireMacro::AllGroups, args, input)
+}
 
-    // Insert guard check at start of function body
-    let original_block = item_fn.block.clone();
-    let guard_check = quote! {
-        let __guard = ::poem_auth::HasAllGroups(vec![#(#groups_vec.to_string()),*]);
-        if !__guard.check(&claims) {
-            return (
-                ::poem::http::StatusCode::FORBIDDEN,
-                ::poem::web::Json(::serde_json::json!({
-                    "error": #error_msg
-                }))
-            ).into_response();
-        }
-    };
+/// Require authentication through a specific provider
+///
+/// Compares against `claims.provider`, so a route can demand a particular
+/// authentication method. Returns 403 Forbidden if the user authenticated
+/// through another provider (or 404, depending on the configured
+/// `DenialMode`). Naming more than one provider is a compile error.
+///
+/// # Example
+///
+/// ```ignore
+/// #[require_provider("ldap")]
+/// #[handler]
+/// async fn rotate_keys(claims: UserClaims) -> Response {
+///     "Keys rotated".into()
+/// }
+/// ```
+///
+/// # Requirements
+///
+/// The handler must have a `claims: UserClaims` (or `&UserClaims`)
+/// parameter; any path ending in `UserClaims` is accepted. The handler
+/// function must return a type that implements `IntoResponse`, or a
+/// `Result<T, E>` whose `E` implements `From<poem::Error>`.
+#[proc_macro_attribute]
+pub fn require_provider(args: TokenStream, input: TokenStream) -> TokenStream {
+    expand(RequireMacro::Provider, args, input)
+}
 
-    item_fn.block = Box::new(syn::parse_quote!({
-        #guard_check
-        #original_block
-    }));
+/// Require ANY of the specified OAuth scopes (OR logic)
+///
+/// Scopes are read from the token's space-delimited `scope` claim. Returns
+/// 403 Forbidden if none of the scopes are granted (or 404, depending on the
+/// configured `DenialMode`).
+///
+/// # Example
+///
+/// ```ignore
+/// #[require_any_scope("read:users", "admin")]
+/// #[handler]
+/// async fn list_users(claims: UserClaims) -> Response {
+///     "User list".into()
+/// }
+/// ```
+///
+/// # Requirements
+///
+/// The handler must have a `claims: UserClaims` (or `&UserClaims`)
+/// parameter; any path ending in `UserClaims` is accepted. The handler
+/// function must return a type that implements `IntoResponse`, or a
+/// `Result<T, E>` whose `E` implements `From<poem::Error>`.
+#[proc_macro_attribute]
+pub fn require_any_scope(args: TokenStream, input: TokenStream) -> TokenStream {
+    expand(RequireMacro::AnyScope, args, input)
+}
 
-    quote!(#item_fn).into()
+/// Require ALL of the specified OAuth scopes (AND logic)
+///
+/// Returns 403 Forbidden if any of the scopes is missing (or 404, depending
+/// on the configured `DenialMode`).
+///
+/// # Example
+///
+/// ```ignore
+/// #[require_all_scopes("read:users", "write:users")]
+/// #[handler]
+/// async fn edit_user(claims: UserClaims) -> Response {
+///     "Edit user".into()
+/// }
+/// ```
+///
+/// # Requirements
+///
+/// The handler must have a `claims: UserClaims` (or `&UserClaims`)
+/// parameter; any path ending in `UserClaims` is accepted. The handler
+/// function must return a type that implements `IntoResponse`, or a
+/// `Result<T, E>` whose `E` implements `From<poem::Error>`.
+#[proc_macro_attribute]
+pub fn require_all_scopes(args: TokenStream, input: TokenStream) -> TokenStream {
+    expand(RequireMacro::AllScopes, args, input)
 }
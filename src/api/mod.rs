@@ -5,4 +5,12 @@
 
 pub mod types;
 
-pub use types::{LoginRequest, LoginResponse, CreateUserRequest, UpdatePasswordRequest};
+/// OpenAPI service for the login and user management endpoints.
+/// Available when the `openapi` feature is enabled.
+#[cfg(feature = "openapi")]
+pub mod openapi;
+
+pub use types::{
+    LoginRequest, LoginResponse, CreateUserRequest, UpdatePasswordRequest, IntrospectionRequest,
+    IntrospectionResponse,
+};
@@ -0,0 +1,373 @@
+//! OpenAPI service for the login and user management endpoints.
+//!
+//! [`AuthApi`] is a `poem-openapi` API: it serves `POST /login` and the
+//! master-authenticated `/users` and `/password` operations, and the spec is
+//! derived from the same operations and the [`types`](super::types) schemas,
+//! so it only documents routes that exist. Wrap it in an [`OpenApiService`] to
+//! mount the routes and publish Swagger UI.
+//!
+//! Handlers use [`PoemAppState::for_request`], so the state must be attached
+//! with `.data(state)` or initialized globally. User management needs
+//! [`PoemAppState::user_db`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! use poem::{EndpointExt, Route};
+//! use poem_auth::api::openapi::AuthApi;
+//! use poem_auth::MasterAuth;
+//! use poem_openapi::OpenApiService;
+//!
+//! let api = AuthApi::new(MasterAuth::new("admin", &master_hash));
+//! let service = OpenApiService::new(api, "My Service Auth", env!("CARGO_PKG_VERSION")).server("/auth");
+//! let app = Route::new()
+//!     .nest("/docs", service.swagger_ui())
+//!     .at("/openapi.json", service.spec_endpoint())
+//!     .nest("/auth", service)
+//!     .data(state);
+//! ```
+//!
+//! [`OpenApiService`]: poem_openapi::OpenApiService
+
+use poem::error::ResponseError;
+use poem::Request;
+use poem_openapi::auth::Basic;
+use poem_openapi::param::Path;
+use poem_openapi::payload::Json;
+use poem_openapi::{ApiResponse, OpenApi, SecurityScheme};
+
+use crate::api::types::{
+    CreateUserRequest, ErrorResponse, LoginRequest, LoginResponse, UpdatePasswordRequest, UserResponse,
+};
+use crate::db::{UserDatabase, UserRecord};
+use crate::error::AuthError;
+use crate::middleware::MasterAuth;
+use crate::poem_integration::extractors::bearer_challenge;
+use crate::poem_integration::{login, LoginOutcome, LoginResponseBuilder, PoemAppState};
+
+/// Login and user management operations.
+///
+/// User management operations require the master credentials over HTTP Basic
+/// auth; attempts are recorded when `master` has an audit log.
+#[derive(Debug, Clone)]
+pub struct AuthApi {
+    master: MasterAuth,
+}
+
+impl AuthApi {
+    /// Create the API, checking user management calls against `master`.
+    pub fn new(master: MasterAuth) -> Self {
+        Self { master }
+    }
+
+    /// Validate the Basic credentials against the master credentials.
+    async fn authorize(&self, req: &Request, auth: &MasterBasicAuth) -> Result<(), AuthApiError> {
+        let ip = crate::net::client_ip(req, false);
+        self.master.validate_audited(&auth.0.username, &auth.0.password, ip).await?;
+        Ok(())
+    }
+}
+
+/// Master credentials sent with HTTP Basic auth.
+#[derive(SecurityScheme)]
+#[oai(ty = "basic")]
+pub struct MasterBasicAuth(Basic);
+
+impl std::fmt::Debug for MasterBasicAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MasterBasicAuth").field("username", &self.0.username).finish_non_exhaustive()
+    }
+}
+
+/// Successful login.
+#[derive(Debug, ApiResponse)]
+pub enum LoginApiResponse {
+    /// Token issued
+    #[oai(status = 200)]
+    Ok(Json<LoginResponse>),
+}
+
+/// Successful user lookup or creation.
+#[derive(Debug, ApiResponse)]
+pub enum UserApiResponse {
+    /// The user
+    #[oai(status = 200)]
+    Ok(Json<UserResponse>),
+    /// The user was created
+    #[oai(status = 201)]
+    Created(Json<UserResponse>),
+}
+
+/// Successful user listing.
+#[derive(Debug, ApiResponse)]
+pub enum UserListApiResponse {
+    /// All users
+    #[oai(status = 200)]
+    Ok(Json<Vec<UserResponse>>),
+}
+
+/// Successful change with nothing to return.
+#[derive(Debug, ApiResponse)]
+pub enum NoContentApiResponse {
+    /// Done
+    #[oai(status = 204)]
+    NoContent,
+}
+
+/// Error responses, with the same status and body as [`AuthError`]'s
+/// `ResponseError` implementation.
+#[derive(Debug, ApiResponse)]
+pub enum AuthApiError {
+    /// Invalid request, e.g. a rejected username or password
+    #[oai(status = 400)]
+    BadRequest(Json<ErrorResponse>),
+    /// Invalid credentials or token
+    #[oai(status = 401)]
+    Unauthorized(Json<ErrorResponse>, #[oai(header = "WWW-Authenticate")] Option<String>),
+    /// User disabled
+    #[oai(status = 403)]
+    Forbidden(Json<ErrorResponse>),
+    /// User not found
+    #[oai(status = 404)]
+    NotFound(Json<ErrorResponse>),
+    /// Too many requests
+    #[oai(status = 429)]
+    TooManyRequests(Json<ErrorResponse>, #[oai(header = "Retry-After")] Option<u64>),
+    /// Server-side failure
+    #[oai(status = 500)]
+    InternalServerError(Json<ErrorResponse>),
+    /// Authentication backend temporarily unavailable
+    #[oai(status = 503)]
+    ServiceUnavailable(Json<ErrorResponse>),
+}
+
+impl From<AuthError> for AuthApiError {
+    fn from(err: AuthError) -> Self {
+        let body = Json(err.response_body());
+        match err.status().as_u16() {
+            400 => AuthApiError::BadRequest(body),
+            401 => {
+                let challenge = err.is_token_error().then(|| bearer_challenge(Some(&err)));
+                AuthApiError::Unauthorized(body, challenge)
+            }
+            403 => AuthApiError::Forbidden(body),
+            404 => AuthApiError::NotFound(body),
+            429 => match err {
+                AuthError::RateLimitExceeded(secs) => AuthApiError::TooManyRequests(body, Some(secs)),
+                _ => AuthApiError::TooManyRequests(body, None),
+            },
+            _ => AuthApiError::InternalServerError(body),
+        }
+    }
+}
+
+fn app_state(req: &Request) -> Result<&PoemAppState, AuthApiError> {
+    Ok(PoemAppState::for_request(req).ok_or_else(|| AuthError::config("PoemAppState is not initialized"))?)
+}
+
+fn user_db(req: &Request) -> Result<&dyn UserDatabase, AuthApiError> {
+    Ok(app_state(req)?
+        .user_db
+        .as_deref()
+        .ok_or_else(|| AuthError::config("No user database configured"))?)
+}
+
+fn user_response(user: UserRecord) -> UserResponse {
+    UserResponse {
+        enabled: user.is_enabled(),
+        username: user.username,
+        groups: user.groups,
+        created_at: user.created_at,
+        updated_at: user.updated_at,
+    }
+}
+
+#[OpenApi]
+impl AuthApi {
+    /// Authenticate with username and password
+    #[oai(path = "/login", method = "post", operation_id = "login")]
+    async fn login(&self, req: &Request, body: Json<LoginRequest>) -> Result<LoginApiResponse, AuthApiError> {
+        match login(app_state(req)?, &body).await {
+            LoginOutcome::Success { claims, token } => Ok(LoginApiResponse::Ok(Json(
+                LoginResponseBuilder::body(&claims, &token, None),
+            ))),
+            LoginOutcome::InvalidCredentials => Err(AuthError::InvalidCredentials.into()),
+            LoginOutcome::Disabled => Err(AuthApiError::Forbidden(Json(ErrorResponse::user_disabled(
+                &body.username,
+            )))),
+            LoginOutcome::Unavailable(_) => Err(AuthApiError::ServiceUnavailable(Json(ErrorResponse::new(
+                "service_unavailable",
+                "Authentication service is temporarily unavailable",
+            )))),
+            LoginOutcome::TokenError(_) => Err(AuthApiError::InternalServerError(Json(ErrorResponse::new(
+                "token_generation_failed",
+                "Failed to generate authentication token",
+            )))),
+        }
+    }
+
+    /// List users
+    #[oai(path = "/users", method = "get", operation_id = "listUsers")]
+    async fn list_users(&self, req: &Request, auth: MasterBasicAuth) -> Result<UserListApiResponse, AuthApiError> {
+        self.authorize(req, &auth).await?;
+        let users = user_db(req)?.list_users().await?;
+        Ok(UserListApiResponse::Ok(Json(users.into_iter().map(user_response).collect())))
+    }
+
+    /// Create a local user
+    #[oai(path = "/users", method = "post", operation_id = "createUser")]
+    async fn create_user(
+        &self,
+        req: &Request,
+        auth: MasterBasicAuth,
+        body: Json<CreateUserRequest>,
+    ) -> Result<UserApiResponse, AuthApiError> {
+        self.authorize(req, &auth).await?;
+        let db = user_db(req)?;
+        let hash = crate::password::hash_password(&body.password)?;
+        let user = UserRecord::new(&body.username, &hash)
+            .with_groups(body.groups.clone())
+            .with_enabled(body.enabled);
+        user.validate()?;
+        db.create_user(user).await?;
+        Ok(UserApiResponse::Created(Json(user_response(db.get_user(&body.username).await?))))
+    }
+
+    /// Get a user
+    #[oai(path = "/users/:username", method = "get", operation_id = "getUser")]
+    async fn get_user(
+        &self,
+        req: &Request,
+        auth: MasterBasicAuth,
+        username: Path<String>,
+    ) -> Result<UserApiResponse, AuthApiError> {
+        self.authorize(req, &auth).await?;
+        let user = user_db(req)?.get_user(&username).await?;
+        Ok(UserApiResponse::Ok(Json(user_response(user))))
+    }
+
+    /// Delete a user
+    #[oai(path = "/users/:username", method = "delete", operation_id = "deleteUser")]
+    async fn delete_user(
+        &self,
+        req: &Request,
+        auth: MasterBasicAuth,
+        username: Path<String>,
+    ) -> Result<NoContentApiResponse, AuthApiError> {
+        self.authorize(req, &auth).await?;
+        user_db(req)?.delete_user(&username).await?;
+        Ok(NoContentApiResponse::NoContent)
+    }
+
+    /// Set a user's password
+    #[oai(path = "/password", method = "put", operation_id = "updatePassword")]
+    async fn update_password(
+        &self,
+        req: &Request,
+        auth: MasterBasicAuth,
+        body: Json<UpdatePasswordRequest>,
+    ) -> Result<NoContentApiResponse, AuthApiError> {
+        self.authorize(req, &auth).await?;
+        let hash = crate::password::hash_password(&body.new_password)?;
+        user_db(req)?.update_password(&body.username, hash).await?;
+        Ok(NoContentApiResponse::NoContent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use base64::Engine;
+    use poem::http::{header, Method, StatusCode};
+    use poem::{Endpoint, EndpointExt};
+    use poem_openapi::OpenApiService;
+    use serde_json::{json, Value};
+    use crate::db::MemoryUserDb;
+    use crate::password::hash_password;
+    use crate::providers::LocalAuthProvider;
+
+    const SECRET: &str = "openapi-test-secret-32-bytes-long!!";
+
+    fn api() -> AuthApi {
+        AuthApi::new(MasterAuth::new("admin", &hash_password("master-pass").unwrap()))
+    }
+
+    fn spec() -> Value {
+        serde_json::from_str(&OpenApiService::new(api(), "Auth", "1.0").spec()).unwrap()
+    }
+
+    #[test]
+    fn test_spec_contains_login_and_error_schema() {
+        let spec = spec();
+
+        let login = &spec["paths"]["/login"]["post"];
+        assert_eq!(login["operationId"], "login");
+        assert_eq!(
+            login["requestBody"]["content"]["application/json; charset=utf-8"]["schema"]["$ref"],
+            "#/components/schemas/LoginRequest"
+        );
+        assert_eq!(
+            login["responses"]["401"]["content"]["application/json; charset=utf-8"]["schema"]["$ref"],
+            "#/components/schemas/ErrorResponse"
+        );
+
+        let error = &spec["components"]["schemas"]["ErrorResponse"];
+        assert_eq!(error["required"], serde_json::json!(["error", "message"]));
+        assert!(error["properties"]["details"].is_object());
+    }
+
+    #[test]
+    fn test_spec_documents_only_served_routes() {
+        let spec = spec();
+        let mut paths: Vec<_> = spec["paths"].as_object().unwrap().keys().cloned().collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/login", "/password", "/users", "/users/{username}"]);
+        assert!(spec["components"]["securitySchemes"]["MasterBasicAuth"].is_object());
+    }
+
+    async fn call(app: &impl Endpoint, req: Request) -> (StatusCode, Value) {
+        let response = app.get_response(req).await;
+        let status = response.status();
+        let body = response.into_body().into_string().await.unwrap();
+        (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+    }
+
+    fn post(path: &str, body: Value, authorization: Option<&str>) -> Request {
+        let mut builder = Request::builder().method(Method::POST).uri_str(path).content_type("application/json");
+        if let Some(authorization) = authorization {
+            builder = builder.header(header::AUTHORIZATION, authorization);
+        }
+        builder.body(body.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_login_and_user_management_are_served() {
+        let db = Arc::new(MemoryUserDb::new());
+        let provider = Arc::new(LocalAuthProvider::with_db(db.clone()));
+        let state = PoemAppState::with_provider(provider, SECRET).unwrap().with_user_db(db.clone());
+        let app = OpenApiService::new(api(), "Auth", "1.0").data(state);
+
+        let create = json!({ "username": "alice", "password": "s3cret", "groups": ["users"] });
+        let (status, _) = call(&app, post("/users", create.clone(), None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let basic = format!("Basic {}", BASE64.encode("admin:master-pass"));
+        let (status, body) = call(&app, post("/users", create, Some(&basic))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["username"], "alice");
+        assert_eq!(body["enabled"], true);
+        assert!(db.get_user("alice").await.unwrap().has_group("users"));
+
+        let (status, body) = call(&app, post("/login", json!({ "username": "alice", "password": "s3cret" }), None)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["token_type"], "Bearer");
+        assert_eq!(body["claims"]["sub"], "alice");
+        assert!(body.get("refresh_token").is_none());
+
+        let (status, body) = call(&app, post("/login", json!({ "username": "alice", "password": "wrong" }), None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_credentials");
+    }
+}
@@ -18,6 +18,7 @@ use crate::auth::UserClaims;
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(poem_openapi::Object))]
 pub struct LoginRequest {
     /// The username to authenticate
     pub username: String,
@@ -41,9 +42,11 @@ pub struct LoginRequest {
 ///     token_type: "Bearer".to_string(),
 ///     expires_in: 86400,
 ///     claims: claims,
+///     refresh_token: None,
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(poem_openapi::Object))]
 pub struct LoginResponse {
     /// The JWT token string
     pub token: String,
@@ -53,12 +56,17 @@ pub struct LoginResponse {
     pub expires_in: i64,
     /// The decoded claims (user info)
     pub claims: UserClaimsResponse,
+    /// Long-lived token for obtaining new access tokens, if one was issued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", oai(skip_serializing_if_is_none))]
+    pub refresh_token: Option<String>,
 }
 
 /// Simplified user claims for API responses.
 ///
 /// Exposes user information without sensitive fields.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(poem_openapi::Object))]
 pub struct UserClaimsResponse {
     /// The authenticated username
     pub sub: String,
@@ -75,13 +83,20 @@ pub struct UserClaimsResponse {
 impl UserClaimsResponse {
     /// Create from UserClaims struct.
     pub fn from_claims(claims: UserClaims) -> Self {
-        Self {
-            sub: claims.sub,
-            provider: claims.provider,
-            groups: claims.groups,
-            exp: claims.exp,
-            iat: claims.iat,
-        }
+        Self::from(claims)
+    }
+}
+
+impl From<UserClaims> for UserClaimsResponse {
+    fn from(claims: UserClaims) -> Self {
+        claims.to_response()
+    }
+}
+
+/// Borrowing conversion for handlers that still need the claims afterwards.
+impl From<&UserClaims> for UserClaimsResponse {
+    fn from(claims: &UserClaims) -> Self {
+        claims.to_response()
     }
 }
 
@@ -101,6 +116,7 @@ impl UserClaimsResponse {
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(poem_openapi::Object))]
 pub struct CreateUserRequest {
     /// The new username
     pub username: String,
@@ -108,9 +124,11 @@ pub struct CreateUserRequest {
     pub password: String,
     /// Initial groups/roles for the user
     #[serde(default)]
+    #[cfg_attr(feature = "openapi", oai(default))]
     pub groups: Vec<String>,
     /// Whether the user account is initially enabled
     #[serde(default = "default_true")]
+    #[cfg_attr(feature = "openapi", oai(default = "default_true"))]
     pub enabled: bool,
 }
 
@@ -127,6 +145,7 @@ pub struct CreateUserRequest {
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(poem_openapi::Object))]
 pub struct UpdatePasswordRequest {
     /// The username whose password to update
     pub username: String,
@@ -138,6 +157,7 @@ pub struct UpdatePasswordRequest {
 ///
 /// Public user information for list/get operations.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(poem_openapi::Object))]
 pub struct UserResponse {
     /// The username
     pub username: String,
@@ -151,10 +171,82 @@ pub struct UserResponse {
     pub updated_at: i64,
 }
 
+/// Token introspection request payload.
+///
+/// Submitted by resource servers that can't verify tokens themselves.
+/// Requires master authentication.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(poem_openapi::Object))]
+pub struct IntrospectionRequest {
+    /// The token to introspect
+    pub token: String,
+}
+
+/// Token introspection response (RFC 7662-style).
+///
+/// Inactive tokens carry no other fields, so nothing is disclosed about
+/// tokens that failed verification.
+///
+/// # Example
+///
+/// ```ignore
+/// // {"active":true,"sub":"alice","groups":["users"],"exp":...,"iat":...,"provider":"local"}
+/// let response = IntrospectionResponse::active(&claims);
+///
+/// // {"active":false}
+/// let response = IntrospectionResponse::inactive();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(poem_openapi::Object))]
+pub struct IntrospectionResponse {
+    /// Whether the token is currently valid
+    pub active: bool,
+    /// The authenticated username
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", oai(skip_serializing_if_is_none))]
+    pub sub: Option<String>,
+    /// User's groups/roles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", oai(skip_serializing_if_is_none))]
+    pub groups: Option<Vec<String>>,
+    /// Token expiration time (Unix timestamp)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", oai(skip_serializing_if_is_none))]
+    pub exp: Option<i64>,
+    /// Token issued at time (Unix timestamp)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", oai(skip_serializing_if_is_none))]
+    pub iat: Option<i64>,
+    /// The authentication provider used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", oai(skip_serializing_if_is_none))]
+    pub provider: Option<String>,
+}
+
+impl IntrospectionResponse {
+    /// Response for a valid token.
+    pub fn active(claims: &UserClaims) -> Self {
+        Self {
+            active: true,
+            sub: Some(claims.sub.clone()),
+            groups: Some(claims.groups.clone()),
+            exp: Some(claims.exp),
+            iat: Some(claims.iat),
+            provider: Some(claims.provider.clone()),
+        }
+    }
+
+    /// Response for an invalid, expired or revoked token.
+    pub fn inactive() -> Self {
+        Self::default()
+    }
+}
+
 /// API error response.
 ///
 /// Standardized error response format for all API endpoints.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(poem_openapi::Object))]
 pub struct ErrorResponse {
     /// Machine-readable error code
     pub error: String,
@@ -162,6 +254,7 @@ pub struct ErrorResponse {
     pub message: String,
     /// Optional additional details
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", oai(skip_serializing_if_is_none))]
     pub details: Option<String>,
 }
 
@@ -208,6 +301,14 @@ impl ErrorResponse {
     pub fn forbidden(reason: &str) -> Self {
         Self::new("forbidden", reason)
     }
+
+    /// Rate limit exceeded error.
+    pub fn rate_limit_exceeded(retry_after_secs: u64) -> Self {
+        Self::new(
+            "rate_limit_exceeded",
+            &format!("Too many requests, retry in {} seconds", retry_after_secs),
+        )
+    }
 }
 
 /// Helper function for default_true in serde.
@@ -219,6 +320,20 @@ fn default_true() -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_introspection_response_serialization() {
+        let inactive = serde_json::to_value(IntrospectionResponse::inactive()).unwrap();
+        assert_eq!(inactive, serde_json::json!({ "active": false }));
+
+        let claims = UserClaims::new("alice", "local", 2000, 1000).with_groups(vec!["users"]);
+        let active = serde_json::to_value(IntrospectionResponse::active(&claims)).unwrap();
+        assert_eq!(active["active"], true);
+        assert_eq!(active["sub"], "alice");
+        assert_eq!(active["groups"], serde_json::json!(["users"]));
+        assert_eq!(active["exp"], 2000);
+        assert_eq!(active["provider"], "local");
+    }
+
     #[test]
     fn test_login_request_serialization() {
         let req = LoginRequest {
@@ -249,6 +364,23 @@ mod tests {
         assert_eq!(response.iat, 1000);
     }
 
+    #[test]
+    fn test_user_claims_response_from_borrowed_claims() {
+        let claims = UserClaims::new("bob", "ldap", 2000, 1000)
+            .with_groups(vec!["admins", "users"]);
+
+        let response = UserClaimsResponse::from(&claims);
+
+        assert_eq!(response.sub, "bob");
+        assert_eq!(response.provider, "ldap");
+        assert_eq!(response.groups, vec!["admins", "users"]);
+        assert_eq!(response.exp, 2000);
+        assert_eq!(response.iat, 1000);
+
+        // The claims are still usable after conversion
+        assert_eq!(claims.sub, "bob");
+    }
+
     #[test]
     fn test_create_user_request_default_enabled() {
         let json = r#"{"username":"alice","password":"pass123"}"#;
@@ -257,7 +389,7 @@ mod tests {
         assert_eq!(req.username, "alice");
         assert_eq!(req.password, "pass123");
         assert_eq!(req.groups, Vec::<String>::new());
-        assert_eq!(req.enabled, true);  // Should default to true
+        assert!(req.enabled);  // Should default to true
     }
 
     #[test]
@@ -289,6 +421,10 @@ mod tests {
 
         let err4 = ErrorResponse::forbidden("Admin only");
         assert_eq!(err4.error, "forbidden");
+
+        let err5 = ErrorResponse::rate_limit_exceeded(30);
+        assert_eq!(err5.error, "rate_limit_exceeded");
+        assert!(err5.message.contains("30"));
     }
 
     #[test]
@@ -299,6 +435,7 @@ mod tests {
             token_type: "Bearer".to_string(),
             expires_in: 1000,
             claims: UserClaimsResponse::from_claims(claims),
+            refresh_token: None,
         };
 
         let json = serde_json::to_string(&response).unwrap();
@@ -0,0 +1,184 @@
+//! Audit logging of security-relevant events.
+//!
+//! Components that guard sensitive operations (such as [`MasterAuth`]) record
+//! an [`AuditEvent`] into an [`AuditLog`] sink when one is configured.
+//! `SqliteUserDb` implements `AuditLog` by writing to its `audit_log` table,
+//! and can stream matching rows back out with `export_events` for SIEM
+//! ingestion (see [`write_ndjson`]).
+//!
+//! [`MasterAuth`]: crate::middleware::MasterAuth
+
+use async_trait::async_trait;
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+use std::net::IpAddr;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::error::AuthError;
+
+/// A single audit log entry.
+///
+/// Fields mirror the columns of the SQLite `audit_log` table.
+///
+/// # Example
+///
+/// ```ignore
+/// let event = AuditEvent::new("master_auth_failure", "master")
+///     .with_username("admin")
+///     .with_ip_address(client_ip);
+/// audit_log.record(event).await?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditEvent {
+    /// Unix timestamp when the event happened
+    pub timestamp: i64,
+    /// What happened, including the outcome (e.g. `master_auth_failure`)
+    pub event_type: String,
+    /// Username the event is about, if any
+    pub username: Option<String>,
+    /// Provider that handled the request (e.g. `local`, `master`)
+    pub provider: String,
+    /// Client IP address, if known
+    pub ip_address: Option<IpAddr>,
+    /// Free-form details
+    pub details: Option<String>,
+}
+
+impl AuditEvent {
+    /// Create an event timestamped now.
+    pub fn new<E: Into<String>, P: Into<String>>(event_type: E, provider: P) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().timestamp(),
+            event_type: event_type.into(),
+            username: None,
+            provider: provider.into(),
+            ip_address: None,
+            details: None,
+        }
+    }
+
+    /// Set the username.
+    pub fn with_username<S: Into<String>>(mut self, username: S) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    /// Set the client IP address.
+    pub fn with_ip_address(mut self, ip_address: Option<IpAddr>) -> Self {
+        self.ip_address = ip_address;
+        self
+    }
+
+    /// Set free-form details.
+    pub fn with_details<S: Into<String>>(mut self, details: S) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+}
+
+/// Criteria for selecting audit events to export.
+///
+/// Unset fields match every event. `since` is inclusive and `until`
+/// exclusive, so consecutive windows don't overlap.
+///
+/// # Example
+///
+/// ```ignore
+/// let filter = AuditFilter::new()
+///     .with_event_type("master_auth_failure")
+///     .with_since(yesterday);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditFilter {
+    /// Only events about this username
+    pub username: Option<String>,
+    /// Only events of this type
+    pub event_type: Option<String>,
+    /// Only events at or after this Unix timestamp
+    pub since: Option<i64>,
+    /// Only events before this Unix timestamp
+    pub until: Option<i64>,
+}
+
+impl AuditFilter {
+    /// Create a filter matching every event.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match events about `username`.
+    pub fn with_username<S: Into<String>>(mut self, username: S) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    /// Only match events of `event_type`.
+    pub fn with_event_type<S: Into<String>>(mut self, event_type: S) -> Self {
+        self.event_type = Some(event_type.into());
+        self
+    }
+
+    /// Only match events at or after `since`.
+    pub fn with_since(mut self, since: i64) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only match events before `until`.
+    pub fn with_until(mut self, until: i64) -> Self {
+        self.until = Some(until);
+        self
+    }
+}
+
+/// Write a stream of audit events as newline-delimited JSON.
+///
+/// Events are written one line at a time as they arrive, so memory use
+/// doesn't grow with the number of events. Returns how many were written.
+///
+/// # Errors
+///
+/// Returns the first error yielded by `events`, or `AuthError::DatabaseError`
+/// if an event can't be serialized or written, so handlers report export
+/// failures as server errors.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut file = tokio::fs::File::create("audit.ndjson").await?;
+/// let written = write_ndjson(db.export_events(&AuditFilter::new()), &mut file).await?;
+/// ```
+pub async fn write_ndjson<S, W>(events: S, writer: &mut W) -> Result<u64, AuthError>
+where
+    S: Stream<Item = Result<AuditEvent, AuthError>>,
+    W: AsyncWrite + Unpin,
+{
+    let write_error = |e: std::io::Error| AuthError::database(format!("Failed to write audit export: {}", e));
+    let mut events = std::pin::pin!(events);
+    let mut written = 0;
+
+    while let Some(event) = events.next().await {
+        let mut line = serde_json::to_vec(&event?)
+            .map_err(|e| AuthError::database(format!("Failed to serialize audit event: {}", e)))?;
+        line.push(b'\n');
+        writer.write_all(&line).await.map_err(write_error)?;
+        written += 1;
+    }
+    writer.flush().await.map_err(write_error)?;
+
+    Ok(written)
+}
+
+/// Sink for audit events.
+///
+/// Implement this to forward events to your own store (SIEM, log pipeline,
+/// database table).
+#[async_trait]
+pub trait AuditLog: Send + Sync + std::fmt::Debug {
+    /// Persist an event.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `AuthError` if the event couldn't be stored.
+    async fn record(&self, event: AuditEvent) -> Result<(), AuthError>;
+}
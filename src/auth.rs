@@ -4,6 +4,8 @@
 
 pub mod provider;
 pub mod claims;
+pub mod diagnostics;
 
 pub use provider::AuthProvider;
-pub use claims::UserClaims;
+pub use diagnostics::{DiagnosticCheck, ProviderDiagnostics};
+pub use claims::{GroupIndex, UserClaims, UserClaimsBuilder, RESERVED_CLAIMS};
@@ -3,7 +3,32 @@
 //! `UserClaims` represents the authenticated user's information that gets
 //! encoded into JWT tokens and injected into request handlers.
 
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+
+use crate::api::types::UserClaimsResponse;
+
+/// Claim names owned by the standard `UserClaims` fields.
+///
+/// Because `extra` is flattened into the token, a custom claim with one of
+/// these names would collide with the real field, so they are dropped from
+/// `extra` by [`UserClaims::with_extra`] and [`UserClaimsBuilder::extra`].
+/// `zgroups` carries compressed groups (see
+/// [`JwtValidator::with_compressed_groups`](crate::jwt::JwtValidator::with_compressed_groups)).
+pub const RESERVED_CLAIMS: &[&str] =
+    &["sub", "groups", "provider", "exp", "iat", "jti", "nbf", "zgroups"];
+
+/// Remove reserved claim names from custom claims, warning about each one.
+fn strip_reserved(mut extra: serde_json::Value) -> serde_json::Value {
+    if let Some(map) = extra.as_object_mut() {
+        for key in RESERVED_CLAIMS {
+            if map.remove(*key).is_some() {
+                tracing::warn!("Ignoring custom claim '{}': the name is reserved", key);
+            }
+        }
+    }
+    extra
+}
 
 /// Claims about an authenticated user.
 ///
@@ -25,6 +50,7 @@ use serde::{Deserialize, Serialize};
 ///     exp: 1704067200,
 ///     iat: 1703980800,
 ///     jti: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+///     nbf: None,
 ///     extra: None,
 /// };
 /// ```
@@ -43,6 +69,9 @@ pub struct UserClaims {
     /// Can be used for authorization decisions.
     ///
     /// Example: `["Domain Admins", "API Users", "Sales"]`
+    ///
+    /// Omitted from the token when empty, and empty when a token omits it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub groups: Vec<String>,
 
     /// Which authentication provider authenticated this user.
@@ -65,8 +94,19 @@ pub struct UserClaims {
     ///
     /// A unique identifier for this specific token instance.
     /// Can be used for token revocation or tracking.
+    ///
+    /// Empty when jti issuance is disabled (see `JwtValidator::with_jti`),
+    /// in which case it is omitted from the token.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub jti: String,
 
+    /// Not-before time (Unix timestamp), if any.
+    ///
+    /// Tokens are rejected until this time is reached. Omitted from the
+    /// token when `None`, so tokens without it are unaffected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<i64>,
+
     /// Additional custom claims.
     ///
     /// Use this field to store provider-specific or application-specific claims
@@ -108,10 +148,29 @@ impl UserClaims {
             exp,
             iat,
             jti: uuid::Uuid::new_v4().to_string(),
+            nbf: None,
             extra: None,
         }
     }
 
+    /// Start building claims with a fluent builder.
+    ///
+    /// Unlike `new`, the builder can set every field, including `jti`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let claims = UserClaims::builder()
+    ///     .subject("alice")
+    ///     .provider("local")
+    ///     .groups(vec!["admins"])
+    ///     .jti("fixed-jti")
+    ///     .build();
+    /// ```
+    pub fn builder() -> UserClaimsBuilder {
+        UserClaimsBuilder::default()
+    }
+
     /// Add groups to the claims.
     ///
     /// # Example
@@ -141,6 +200,9 @@ impl UserClaims {
 
     /// Add custom claims.
     ///
+    /// Keys listed in [`RESERVED_CLAIMS`] are dropped with a warning so they
+    /// can't overwrite the standard claims when the token is serialized.
+    ///
     /// # Example
     ///
     /// ```ignore
@@ -150,10 +212,124 @@ impl UserClaims {
     ///     .with_extra(json!({"department": "Engineering"}));
     /// ```
     pub fn with_extra(mut self, extra: serde_json::Value) -> Self {
+        self.extra = Some(strip_reserved(extra));
+        self
+    }
+
+    /// Project the claims into the [`UserClaimsResponse`] returned to clients.
+    ///
+    /// Only `sub`, `provider`, `groups`, `exp` and `iat` are copied; `jti`,
+    /// `nbf` and custom claims in `extra` are left out.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// Json(LoginResponse { claims: claims.to_response(), .. })
+    /// ```
+    pub fn to_response(&self) -> UserClaimsResponse {
+        UserClaimsResponse {
+            sub: self.sub.clone(),
+            provider: self.provider.clone(),
+            groups: self.groups.clone(),
+            exp: self.exp,
+            iat: self.iat,
+        }
+    }
+
+    /// Deserialize a single custom claim from `extra`.
+    ///
+    /// Returns `None` if there are no custom claims, the key is missing, or
+    /// the value doesn't match `T`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let clearance: Option<u8> = claims.get_extra("clearance_level");
+    /// ```
+    pub fn get_extra<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.extra.as_ref()?.get(key)?;
+        T::deserialize(value).ok()
+    }
+
+    /// Deserialize all custom claims in `extra` into `T`.
+    ///
+    /// Returns `None` if there are no custom claims or they don't match `T`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// #[derive(Deserialize)]
+    /// struct Custom {
+    ///     department: String,
+    ///     clearance_level: u8,
+    /// }
+    ///
+    /// let custom: Option<Custom> = claims.deserialize_extra();
+    /// ```
+    pub fn deserialize_extra<T: DeserializeOwned>(&self) -> Option<T> {
+        T::deserialize(self.extra.as_ref()?).ok()
+    }
+
+    /// Set the OAuth-style `scope` claim (space-delimited, per RFC 9068).
+    ///
+    /// Stored in `extra`, alongside any other custom claims.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let claims = UserClaims::new("alice", "local", exp, iat)
+    ///     .with_scopes(vec!["read:users", "write:users"]);
+    /// ```
+    pub fn with_scopes<S: Into<String>>(mut self, scopes: Vec<S>) -> Self {
+        let scope = scopes.into_iter().map(Into::into).collect::<Vec<String>>().join(" ");
+        let mut extra = self.extra.take().unwrap_or_else(|| serde_json::json!({}));
+        if let Some(map) = extra.as_object_mut() {
+            map.insert("scope".to_string(), serde_json::Value::String(scope));
+        }
         self.extra = Some(extra);
         self
     }
 
+    /// Scopes granted by the `scope` claim, if any.
+    pub fn scopes(&self) -> Vec<&str> {
+        self.extra
+            .as_ref()
+            .and_then(|extra| extra.get("scope"))
+            .and_then(|scope| scope.as_str())
+            .map(|scope| scope.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    /// Check if the token grants a specific scope.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes().contains(&scope)
+    }
+
+    /// Check if the token grants any of the specified scopes.
+    pub fn has_any_scope(&self, scopes: &[&str]) -> bool {
+        self.scopes().iter().any(|s| scopes.contains(s))
+    }
+
+    /// Check if the token grants all of the specified scopes.
+    pub fn has_all_scopes(&self, scopes: &[&str]) -> bool {
+        let granted = self.scopes();
+        scopes.iter().all(|s| granted.contains(s))
+    }
+
+    /// Set the not-before (`nbf`) time.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Token only becomes valid an hour from now
+    /// let claims = UserClaims::new("alice", "local", now + 7200, now)
+    ///     .with_not_before(now + 3600);
+    /// ```
+    pub fn with_not_before(mut self, nbf: i64) -> Self {
+        self.nbf = Some(nbf);
+        self
+    }
+
     /// Check if user has a specific group.
     ///
     /// # Example
@@ -181,10 +357,52 @@ impl UserClaims {
     }
 
     /// Check if user has all of the specified groups.
+    ///
+    /// An empty `groups` list is always satisfied, while `has_any_group`
+    /// with an empty list never is.
     pub fn has_all_groups(&self, groups: &[&str]) -> bool {
         groups.iter().all(|g| self.groups.iter().any(|ug| ug == *g))
     }
 
+    /// Index the groups for constant-time membership checks.
+    ///
+    /// The `has_*` methods scan `groups` linearly, which is fine for a
+    /// handful of groups. For users in hundreds of groups checked against
+    /// many required groups, build the index once and query it instead.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let groups = claims.index_groups();
+    /// let visible: Vec<_> = projects.iter().filter(|p| groups.has_group(&p.group)).collect();
+    /// ```
+    pub fn index_groups(&self) -> GroupIndex<'_> {
+        GroupIndex {
+            groups: self.groups.iter().map(String::as_str).collect(),
+        }
+    }
+
+    /// Check if both claims describe the same user with the same groups.
+    ///
+    /// Compares `sub`, `provider` and the set of `groups` (ignoring order
+    /// and duplicates). Per-token fields such as `jti`, `iat` and `exp` are
+    /// ignored, so two logins of the same user compare equal here while
+    /// `==` still requires an exact match.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let first = UserClaims::new("alice", "local", exp, iat);
+    /// let second = UserClaims::new("alice", "local", exp, iat);
+    /// assert!(first.same_principal(&second));
+    /// assert_ne!(first, second); // different jti
+    /// ```
+    pub fn same_principal(&self, other: &UserClaims) -> bool {
+        let mine: BTreeSet<&str> = self.groups.iter().map(String::as_str).collect();
+        let theirs: BTreeSet<&str> = other.groups.iter().map(String::as_str).collect();
+        self.sub == other.sub && self.provider == other.provider && mine == theirs
+    }
+
     /// Check if the token is expired (based on provided current time).
     ///
     /// # Arguments
@@ -222,10 +440,166 @@ impl UserClaims {
     }
 }
 
+/// Hashed view of a [`UserClaims`]'s groups, from [`UserClaims::index_groups`].
+///
+/// Answers the same questions as the `has_*` methods on `UserClaims`, with
+/// the same results, in O(1) per group checked.
+#[derive(Debug, Clone)]
+pub struct GroupIndex<'a> {
+    groups: HashSet<&'a str>,
+}
+
+impl GroupIndex<'_> {
+    /// Check if the user has `group`.
+    pub fn has_group(&self, group: &str) -> bool {
+        self.groups.contains(group)
+    }
+
+    /// Check if the user has any of `groups`.
+    pub fn has_any_group(&self, groups: &[&str]) -> bool {
+        groups.iter().any(|group| self.groups.contains(group))
+    }
+
+    /// Check if the user has all of `groups`.
+    pub fn has_all_groups(&self, groups: &[&str]) -> bool {
+        groups.iter().all(|group| self.groups.contains(group))
+    }
+
+    /// Number of distinct groups.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Check if the user has no groups.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+/// Fluent builder for [`UserClaims`].
+///
+/// Created with [`UserClaims::builder`]. Fields that are never set fall back
+/// to defaults in `build()`:
+/// - `jti` - a fresh UUID v4
+/// - `iat` - the current time
+/// - `exp` - 24 hours after `iat`
+/// - `sub`, `provider` - empty strings
+/// - `groups` - empty
+/// - `nbf`, `extra` - unset
+#[derive(Debug, Clone, Default)]
+pub struct UserClaimsBuilder {
+    sub: String,
+    groups: Vec<String>,
+    provider: String,
+    exp: Option<i64>,
+    iat: Option<i64>,
+    jti: Option<String>,
+    nbf: Option<i64>,
+    extra: Option<serde_json::Value>,
+}
+
+impl UserClaimsBuilder {
+    /// Set the subject (`sub`), i.e. the username.
+    pub fn subject<S: Into<String>>(mut self, sub: S) -> Self {
+        self.sub = sub.into();
+        self
+    }
+
+    /// Set the authentication provider name.
+    pub fn provider<S: Into<String>>(mut self, provider: S) -> Self {
+        self.provider = provider.into();
+        self
+    }
+
+    /// Replace the groups.
+    pub fn groups<S: Into<String>>(mut self, groups: Vec<S>) -> Self {
+        self.groups = groups.into_iter().map(|s| s.into()).collect();
+        self
+    }
+
+    /// Add a single group.
+    pub fn group<S: Into<String>>(mut self, group: S) -> Self {
+        self.groups.push(group.into());
+        self
+    }
+
+    /// Set the expiration timestamp.
+    pub fn exp(mut self, exp: i64) -> Self {
+        self.exp = Some(exp);
+        self
+    }
+
+    /// Set the issued-at timestamp.
+    pub fn iat(mut self, iat: i64) -> Self {
+        self.iat = Some(iat);
+        self
+    }
+
+    /// Override the JWT ID instead of generating a random one.
+    pub fn jti<S: Into<String>>(mut self, jti: S) -> Self {
+        self.jti = Some(jti.into());
+        self
+    }
+
+    /// Leave the JWT ID empty so it's omitted from the token.
+    pub fn without_jti(mut self) -> Self {
+        self.jti = Some(String::new());
+        self
+    }
+
+    /// Set the not-before timestamp.
+    pub fn nbf(mut self, nbf: i64) -> Self {
+        self.nbf = Some(nbf);
+        self
+    }
+
+    /// Set custom claims, dropping any [`RESERVED_CLAIMS`] keys.
+    pub fn extra(mut self, extra: serde_json::Value) -> Self {
+        self.extra = Some(strip_reserved(extra));
+        self
+    }
+
+    /// Build the claims, filling in defaults for unset fields.
+    pub fn build(self) -> UserClaims {
+        let iat = self.iat.unwrap_or_else(|| chrono::Utc::now().timestamp());
+        let exp = self.exp.unwrap_or(iat + 24 * 60 * 60);
+
+        UserClaims {
+            sub: self.sub,
+            groups: self.groups,
+            provider: self.provider,
+            exp,
+            iat,
+            jti: self.jti.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            nbf: self.nbf,
+            extra: self.extra,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_scopes() {
+        let claims = UserClaims::new("alice", "local", 1000, 500)
+            .with_extra(serde_json::json!({"department": "Engineering"}))
+            .with_scopes(vec!["read:users", "admin"]);
+
+        assert_eq!(claims.scopes(), vec!["read:users", "admin"]);
+        assert!(claims.has_scope("admin"));
+        assert!(!claims.has_scope("read"));
+        assert!(claims.has_any_scope(&["write:users", "read:users"]));
+        assert!(!claims.has_any_scope(&["write:users"]));
+        assert!(claims.has_all_scopes(&["admin", "read:users"]));
+        assert!(!claims.has_all_scopes(&["admin", "write:users"]));
+        // Other custom claims are kept
+        assert_eq!(claims.get_extra::<String>("department").as_deref(), Some("Engineering"));
+
+        assert!(UserClaims::new("bob", "local", 1000, 500).scopes().is_empty());
+    }
+
     #[test]
     fn test_user_claims_new() {
         let claims = UserClaims::new("alice", "local", 1000, 500);
@@ -270,6 +644,37 @@ mod tests {
         assert!(!claims.has_all_groups(&["users", "developers", "admins"]));
     }
 
+    #[test]
+    fn test_group_index_matches_linear_checks() {
+        let claims = UserClaims::new("alice", "ldap", 1000, 500)
+            .with_groups((0..1000).map(|i| format!("group-{}", i)).collect());
+        let index = claims.index_groups();
+        assert_eq!(index.len(), 1000);
+
+        let required: Vec<String> = (990..1010).map(|i| format!("group-{}", i)).collect();
+        let required: Vec<&str> = required.iter().map(String::as_str).collect();
+        for group in &required {
+            assert_eq!(index.has_group(group), claims.has_group(group), "{}", group);
+        }
+        for window in [&required[..5], &required[10..], &required[5..15], &[][..]] {
+            assert_eq!(index.has_any_group(window), claims.has_any_group(window));
+            assert_eq!(index.has_all_groups(window), claims.has_all_groups(window));
+        }
+        assert!(index.has_all_groups(&required[..10]));
+        assert!(!index.has_any_group(&required[10..]));
+    }
+
+    #[test]
+    fn test_group_index_without_groups() {
+        let claims = UserClaims::new("svc", "local", 1000, 500);
+        let index = claims.index_groups();
+        assert!(index.is_empty());
+        assert!(!index.has_group("admins"));
+        assert!(!index.has_any_group(&["admins"]));
+        assert!(!index.has_any_group(&[]));
+        assert!(index.has_all_groups(&[]));
+    }
+
     #[test]
     fn test_is_expired() {
         let claims = UserClaims::new("alice", "local", 1000, 500);
@@ -293,6 +698,151 @@ mod tests {
         assert_eq!(claims.age(500), 0);
     }
 
+    #[test]
+    fn test_get_extra_typed_values() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Office {
+            city: String,
+            floor: u8,
+        }
+
+        let claims = UserClaims::new("alice", "local", 1000, 500).with_extra(serde_json::json!({
+            "clearance_level": 3,
+            "office": {"city": "Berlin", "floor": 4},
+        }));
+
+        assert_eq!(claims.get_extra::<u8>("clearance_level"), Some(3));
+        assert_eq!(
+            claims.get_extra::<Office>("office"),
+            Some(Office { city: "Berlin".to_string(), floor: 4 })
+        );
+        assert_eq!(claims.get_extra::<u8>("missing"), None);
+        // Wrong type is treated like a missing claim
+        assert_eq!(claims.get_extra::<String>("clearance_level"), None);
+    }
+
+    #[test]
+    fn test_deserialize_extra() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Custom {
+            department: String,
+            clearance_level: u8,
+        }
+
+        let claims = UserClaims::new("alice", "local", 1000, 500)
+            .with_extra(serde_json::json!({"department": "Engineering", "clearance_level": 2}));
+        assert_eq!(
+            claims.deserialize_extra::<Custom>(),
+            Some(Custom { department: "Engineering".to_string(), clearance_level: 2 })
+        );
+
+        let claims = UserClaims::new("alice", "local", 1000, 500);
+        assert_eq!(claims.deserialize_extra::<Custom>(), None);
+        assert_eq!(claims.get_extra::<u8>("clearance_level"), None);
+    }
+
+    #[test]
+    fn test_reserved_keys_dropped_from_extra() {
+        let claims = UserClaims::new("alice", "local", 1000, 500)
+            .with_extra(serde_json::json!({"exp": 9_999_999_999i64, "sub": "root", "department": "Ops"}));
+        assert_eq!(claims.extra, Some(serde_json::json!({"department": "Ops"})));
+
+        let json = serde_json::to_value(&claims).unwrap();
+        assert_eq!(json["exp"], 1000);
+        assert_eq!(json["sub"], "alice");
+
+        let built = UserClaims::builder()
+            .subject("bob")
+            .exp(1000)
+            .extra(serde_json::json!({"groups": ["admins"], "jti": "forged"}))
+            .build();
+        assert_eq!(built.extra, Some(serde_json::json!({})));
+        assert!(built.groups.is_empty());
+    }
+
+    #[test]
+    fn test_to_response_projection() {
+        let claims = UserClaims::new("alice", "ldap", 1000, 500)
+            .with_groups(vec!["admins"])
+            .with_not_before(600)
+            .with_extra(serde_json::json!({"password_hash": "$argon2id$...", "department": "Ops"}));
+
+        let response = claims.to_response();
+        assert_eq!(response.sub, "alice");
+        assert_eq!(response.provider, "ldap");
+        assert_eq!(response.groups, vec!["admins"]);
+        assert_eq!((response.exp, response.iat), (1000, 500));
+
+        let json = serde_json::to_value(&response).unwrap();
+        let mut keys: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["exp", "groups", "iat", "provider", "sub"]);
+    }
+
+    #[test]
+    fn test_builder_all_fields() {
+        let claims = UserClaims::builder()
+            .subject("alice")
+            .provider("ldap")
+            .groups(vec!["admins"])
+            .group("users")
+            .exp(2000)
+            .iat(1000)
+            .jti("fixed-jti")
+            .nbf(1500)
+            .extra(serde_json::json!({"department": "Engineering"}))
+            .build();
+
+        assert_eq!(claims.sub, "alice");
+        assert_eq!(claims.provider, "ldap");
+        assert_eq!(claims.groups, vec!["admins", "users"]);
+        assert_eq!(claims.exp, 2000);
+        assert_eq!(claims.iat, 1000);
+        assert_eq!(claims.jti, "fixed-jti");
+        assert_eq!(claims.nbf, Some(1500));
+        assert_eq!(claims.extra, Some(serde_json::json!({"department": "Engineering"})));
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        let first = UserClaims::builder().subject("alice").provider("local").build();
+        let second = UserClaims::builder().subject("alice").provider("local").build();
+
+        // jti defaults to a fresh UUID per build
+        assert!(uuid::Uuid::parse_str(&first.jti).is_ok());
+        assert_ne!(first.jti, second.jti);
+
+        assert!(first.iat > 0);
+        assert_eq!(first.exp - first.iat, 24 * 60 * 60);
+        assert!(first.groups.is_empty());
+        assert!(first.extra.is_none());
+    }
+
+    #[test]
+    fn test_nbf_omitted_when_unset() {
+        let claims = UserClaims::new("alice", "local", 1000, 500);
+        let json = serde_json::to_value(&claims).unwrap();
+        assert!(json.get("nbf").is_none());
+
+        let claims = claims.with_not_before(600);
+        let json = serde_json::to_value(&claims).unwrap();
+        assert_eq!(json["nbf"], 600);
+    }
+
+    #[test]
+    fn test_without_jti_omits_field() {
+        let claims = UserClaims::builder().subject("alice").without_jti().build();
+        assert!(claims.jti.is_empty());
+
+        let json = serde_json::to_value(&claims).unwrap();
+        assert!(json.get("jti").is_none());
+
+        // Tokens without a jti still deserialize
+        let decoded: UserClaims = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.sub, "alice");
+        assert!(decoded.jti.is_empty());
+    }
+
     #[test]
     fn test_serialization() {
         let claims = UserClaims::new("alice", "local", 1000, 500)
@@ -307,4 +857,36 @@ mod tests {
         assert_eq!(claims.exp, deserialized.exp);
         assert_eq!(claims.iat, deserialized.iat);
     }
+
+    #[test]
+    fn test_groups_omitted_when_empty() {
+        let claims = UserClaims::new("svc", "local", 1000, 500);
+        let json = serde_json::to_value(&claims).unwrap();
+        assert!(json.get("groups").is_none());
+
+        let claims: UserClaims = serde_json::from_value(serde_json::json!({
+            "sub": "svc", "provider": "local", "exp": 1000, "iat": 500
+        }))
+        .unwrap();
+        assert!(claims.groups.is_empty());
+        assert!(!claims.has_group("admins"));
+        assert!(!claims.has_any_group(&["admins"]));
+        assert!(claims.has_all_groups(&[]));
+    }
+
+    #[test]
+    fn test_same_principal_ignores_volatile_fields() {
+        let first = UserClaims::new("alice", "local", 1000, 500).with_groups(vec!["admins", "users"]);
+        let second = UserClaims::new("alice", "local", 2000, 900).with_groups(vec!["users", "admins"]);
+        assert_ne!(first.jti, second.jti);
+        assert!(first.same_principal(&second));
+        assert_ne!(first, second);
+
+        let other_provider = UserClaims::new("alice", "ldap", 1000, 500).with_groups(vec!["admins", "users"]);
+        let other_user = UserClaims::new("bob", "local", 1000, 500).with_groups(vec!["admins", "users"]);
+        let fewer_groups = UserClaims::new("alice", "local", 1000, 500).with_groups(vec!["users"]);
+        assert!(!first.same_principal(&other_provider));
+        assert!(!first.same_principal(&other_user));
+        assert!(!first.same_principal(&fewer_groups));
+    }
 }
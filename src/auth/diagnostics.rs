@@ -0,0 +1,107 @@
+//! Startup diagnostics reported by authentication providers.
+//!
+//! `AuthProvider::validate_config` only says whether a provider is usable;
+//! [`ProviderDiagnostics`] lists every check a provider ran and what it
+//! found, so operators can see at startup that it is wired correctly.
+
+use std::fmt;
+
+use serde::Serialize;
+
+/// Outcome of a single diagnostic check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticCheck {
+    /// Short identifier of what was checked (e.g. `database`, `user_count`)
+    pub name: String,
+    /// Whether the check passed
+    pub passed: bool,
+    /// What the check found, or why it failed
+    pub detail: String,
+}
+
+/// Report returned by [`AuthProvider::diagnose`](crate::AuthProvider::diagnose).
+///
+/// # Example
+///
+/// ```ignore
+/// let report = provider.diagnose().await;
+/// println!("{}", report);
+/// if !report.is_healthy() {
+///     return Err("provider misconfigured".into());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProviderDiagnostics {
+    /// Name of the provider that produced the report
+    pub provider: String,
+    /// Checks in the order they ran
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+impl ProviderDiagnostics {
+    /// Create an empty report for the named provider.
+    pub fn new<S: Into<String>>(provider: S) -> Self {
+        Self {
+            provider: provider.into(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Record a check.
+    pub fn with_check<N: Into<String>, D: Into<String>>(
+        mut self,
+        name: N,
+        passed: bool,
+        detail: D,
+    ) -> Self {
+        self.checks.push(DiagnosticCheck {
+            name: name.into(),
+            passed,
+            detail: detail.into(),
+        });
+        self
+    }
+
+    /// Look up a check by name.
+    pub fn check(&self, name: &str) -> Option<&DiagnosticCheck> {
+        self.checks.iter().find(|c| c.name == name)
+    }
+
+    /// Check whether every recorded check passed.
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+}
+
+impl fmt::Display for ProviderDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Provider '{}' diagnostics:", self.provider)?;
+        for check in &self.checks {
+            let mark = if check.passed { "✓" } else { "✗" };
+            write!(f, "\n  {} {}: {}", mark, check.name, check.detail)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics_report() {
+        let report = ProviderDiagnostics::new("local")
+            .with_check("database", true, "reachable")
+            .with_check("user_count", true, "3 users");
+        assert!(report.is_healthy());
+        assert_eq!(report.check("user_count").unwrap().detail, "3 users");
+        assert_eq!(
+            report.to_string(),
+            "Provider 'local' diagnostics:\n  ✓ database: reachable\n  ✓ user_count: 3 users"
+        );
+
+        let report = report.with_check("config", false, "missing base_dn");
+        assert!(!report.is_healthy());
+        assert!(report.check("ldap").is_none());
+    }
+}
@@ -5,9 +5,11 @@
 
 use async_trait::async_trait;
 use std::fmt::Debug;
+use std::time::Duration;
 
 use crate::error::AuthError;
 use super::claims::UserClaims;
+use super::diagnostics::ProviderDiagnostics;
 
 /// Core trait for authentication implementations.
 ///
@@ -68,7 +70,8 @@ pub trait AuthProvider: Send + Sync + Debug {
     ///
     /// Common errors include:
     /// - `AuthError::InvalidCredentials` - Wrong password
-    /// - `AuthError::UserNotFound` - User doesn't exist
+    /// - `AuthError::UserNotFound` - User doesn't exist (providers should prefer
+    ///   `InvalidCredentials` so logins don't reveal which usernames exist)
     /// - `AuthError::UserDisabled` - User account is disabled
     /// - `AuthError::LdapError` - LDAP connection failed
     /// - `AuthError::DatabaseError` - Database operation failed
@@ -78,6 +81,57 @@ pub trait AuthProvider: Send + Sync + Debug {
         password: &str,
     ) -> Result<UserClaims, AuthError>;
 
+    /// Authenticate with a bearer token issued by an external identity provider.
+    ///
+    /// This is the extension point for federated login (OAuth2/OIDC): an
+    /// implementation validates the upstream token (signature, issuer,
+    /// audience) and maps its claims to `UserClaims`.
+    ///
+    /// # Errors
+    ///
+    /// Default implementation returns `AuthError::Other` because password
+    /// based providers can't accept tokens.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// async fn authenticate_token(&self, token: &str) -> Result<UserClaims, AuthError> {
+    ///     let id_token = self.oidc_client.verify(token).await?;
+    ///     Ok(UserClaims::new(&id_token.email, self.name(), id_token.exp, id_token.iat))
+    /// }
+    /// ```
+    async fn authenticate_token(&self, _token: &str) -> Result<UserClaims, AuthError> {
+        Err(AuthError::other(format!(
+            "Token authentication is unsupported by provider '{}'",
+            self.name()
+        )))
+    }
+
+    /// Re-fetch a user's current groups without re-authenticating.
+    ///
+    /// Claims are frozen at login, so membership changes only show up in new
+    /// tokens. Call this to re-issue a token with fresh groups for a user
+    /// whose current token you have already verified.
+    ///
+    /// # Errors
+    ///
+    /// Default implementation returns `AuthError::Other` because not every
+    /// provider can look users up without their password.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut claims = validator.verify_token(&token)?;
+    /// claims.groups = provider.refresh_groups(&claims.sub).await?;
+    /// let refreshed = validator.generate_token(&claims)?;
+    /// ```
+    async fn refresh_groups(&self, _username: &str) -> Result<Vec<String>, AuthError> {
+        Err(AuthError::other(format!(
+            "Group refresh is unsupported by provider '{}'",
+            self.name()
+        )))
+    }
+
     /// Get the provider's display name.
     ///
     /// This name is used for:
@@ -102,6 +156,48 @@ pub trait AuthProvider: Send + Sync + Debug {
         Ok(())
     }
 
+    /// Check that the provider is usable, giving up after `timeout`.
+    ///
+    /// Runs `validate_config`, which may contact a backend (e.g. an LDAP
+    /// server), so a hung backend can't stall startup or a readiness probe.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `validate_config` error, or `AuthError::Other` if it
+    /// didn't finish within `timeout`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// provider.health(Duration::from_secs(5)).await?;
+    /// ```
+    async fn health(&self, timeout: Duration) -> Result<(), AuthError> {
+        match tokio::time::timeout(timeout, self.validate_config()).await {
+            Ok(result) => result,
+            Err(_) => Err(AuthError::other(format!(
+                "Provider '{}' health check timed out after {:?}",
+                self.name(),
+                timeout
+            ))),
+        }
+    }
+
+    /// Report what this provider checked about its setup.
+    ///
+    /// Unlike `validate_config`, this never fails: every check is recorded
+    /// with its outcome, so startup code can print the whole report.
+    /// Override it to add provider-specific checks (backend reachable,
+    /// user count, required config fields present).
+    ///
+    /// Default implementation records the result of `validate_config`.
+    async fn diagnose(&self) -> ProviderDiagnostics {
+        let report = ProviderDiagnostics::new(self.name());
+        match self.validate_config().await {
+            Ok(()) => report.with_check("config", true, "valid"),
+            Err(e) => report.with_check("config", false, e.to_string()),
+        }
+    }
+
     /// Get human-readable information about this provider.
     ///
     /// Used for documentation and debugging purposes.
@@ -109,11 +205,34 @@ pub trait AuthProvider: Send + Sync + Debug {
     fn info(&self) -> String {
         format!("Provider: {}", self.name())
     }
+
+    /// Check whether this provider should handle the given username.
+    ///
+    /// Used by [`ProviderRegistry`](crate::providers::ProviderRegistry) to route
+    /// logins that don't name a provider, e.g. sending `alice@corp.com` to LDAP
+    /// and plain usernames to the local database.
+    ///
+    /// Default implementation accepts every username.
+    fn supports_username(&self, _username: &str) -> bool {
+        true
+    }
+
+    /// Tear down any server-side state for a logged-out session.
+    ///
+    /// Called by logout handlers with the claims of the token being
+    /// discarded. Providers that keep sessions, refresh tokens or a
+    /// revocation list override this to clean them up.
+    ///
+    /// Default implementation does nothing (returns Ok).
+    async fn logout(&self, _claims: &UserClaims) -> Result<(), AuthError> {
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Mutex;
 
     #[derive(Debug)]
     struct MockProvider;
@@ -132,6 +251,7 @@ mod tests {
                 exp: 0,
                 iat: 0,
                 jti: "test-jti".to_string(),
+                nbf: None,
                 extra: None,
             })
         }
@@ -159,4 +279,136 @@ mod tests {
         let result = provider.validate_config().await;
         assert!(result.is_ok());
     }
+
+    #[derive(Debug)]
+    struct HungProvider;
+
+    #[async_trait]
+    impl AuthProvider for HungProvider {
+        async fn authenticate(&self, _username: &str, _password: &str) -> Result<UserClaims, AuthError> {
+            Err(AuthError::InvalidCredentials)
+        }
+
+        fn name(&self) -> &str {
+            "hung"
+        }
+
+        async fn validate_config(&self) -> Result<(), AuthError> {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_times_out() {
+        let started = std::time::Instant::now();
+        let err = HungProvider.health(Duration::from_millis(20)).await.unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(err.to_string().contains("timed out"), "{}", err);
+
+        assert!(MockProvider.health(Duration::from_millis(20)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_diagnose_default() {
+        let report = MockProvider.diagnose().await;
+        assert_eq!(report.provider, "mock");
+        assert!(report.is_healthy());
+        assert!(report.check("config").is_some());
+    }
+
+    #[test]
+    fn test_supports_username_default() {
+        let provider = MockProvider;
+        assert!(provider.supports_username("alice"));
+        assert!(provider.supports_username("alice@corp.com"));
+    }
+
+    /// Provider that records which subjects logged out.
+    #[derive(Debug, Default)]
+    struct SessionProvider {
+        logged_out: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl AuthProvider for SessionProvider {
+        async fn authenticate(&self, username: &str, _password: &str) -> Result<UserClaims, AuthError> {
+            Ok(UserClaims::new(username, "session", 0, 0))
+        }
+
+        fn name(&self) -> &str {
+            "session"
+        }
+
+        async fn logout(&self, claims: &UserClaims) -> Result<(), AuthError> {
+            self.logged_out.lock().unwrap().push(claims.sub.clone());
+            Ok(())
+        }
+    }
+
+    /// Provider that accepts tokens from a fake upstream identity provider.
+    #[derive(Debug)]
+    struct FederatedProvider;
+
+    #[async_trait]
+    impl AuthProvider for FederatedProvider {
+        async fn authenticate(&self, _username: &str, _password: &str) -> Result<UserClaims, AuthError> {
+            Err(AuthError::InvalidCredentials)
+        }
+
+        fn name(&self) -> &str {
+            "oidc"
+        }
+
+        async fn authenticate_token(&self, token: &str) -> Result<UserClaims, AuthError> {
+            let subject = token
+                .strip_prefix("idp-token-for-")
+                .ok_or(AuthError::InvalidToken)?;
+            Ok(UserClaims::new(subject, self.name(), 0, 0))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_token_default_unsupported() {
+        let result = MockProvider.authenticate_token("token").await;
+        match result {
+            Err(AuthError::Other(message)) => assert!(message.contains("unsupported")),
+            other => panic!("expected AuthError::Other, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_token_override() {
+        let claims = FederatedProvider
+            .authenticate_token("idp-token-for-alice")
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, "alice");
+        assert_eq!(claims.provider, "oidc");
+
+        assert!(FederatedProvider.authenticate_token("garbage").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_groups_default_unsupported() {
+        match MockProvider.refresh_groups("alice").await {
+            Err(AuthError::Other(message)) => assert!(message.contains("unsupported")),
+            other => panic!("expected AuthError::Other, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_logout_default() {
+        let claims = UserClaims::new("alice", "mock", 0, 0);
+        assert!(MockProvider.logout(&claims).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_logout_override_receives_claims() {
+        let provider = SessionProvider::default();
+        let claims = provider.authenticate("alice", "password").await.unwrap();
+
+        provider.logout(&claims).await.unwrap();
+        assert_eq!(*provider.logged_out.lock().unwrap(), vec!["alice"]);
+    }
 }
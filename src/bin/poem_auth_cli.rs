@@ -8,7 +8,7 @@
 
 use clap::{Parser, Subcommand};
 use poem_auth::{
-    hash_password, verify_password, LocalAuthProvider, AuthProvider, UserDatabase, SqliteUserDb,
+    AuthConfig, PasswordConfig, LocalAuthProvider, AuthProvider, UserDatabase, SqliteUserDb,
 };
 use std::io::{self, Write};
 
@@ -16,6 +16,11 @@ use std::io::{self, Write};
 #[command(name = "poem-auth")]
 #[command(about = "Authentication management utility for poem_auth", long_about = None)]
 struct Cli {
+    /// Auth config file whose `[password]` settings (e.g. the pepper) are
+    /// used to hash and verify passwords
+    #[arg(short, long, global = true, value_name = "PATH")]
+    config: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -84,6 +89,17 @@ enum Commands {
         db: String,
     },
 
+    /// Show a user's account details (never the password hash)
+    ShowUser {
+        /// Path to the database file
+        #[arg(short, long, default_value = "users.db")]
+        db: String,
+
+        /// Username to show
+        #[arg(value_name = "USERNAME")]
+        username: String,
+    },
+
     /// Change a user's password
     ChangePassword {
         /// Path to the database file
@@ -133,6 +149,11 @@ enum Commands {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    let password_config = match &cli.config {
+        Some(path) => AuthConfig::from_file(path)?.password,
+        None => PasswordConfig::default(),
+    };
+    let hasher = password_config.hasher()?;
 
     match cli.command {
         Commands::Hash { password } => {
@@ -147,7 +168,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             };
 
-            match hash_password(&pwd) {
+            match hasher.hash(&pwd) {
                 Ok(hash) => {
                     println!("\n✓ Password hashed successfully");
                     println!("Hash: {}", hash);
@@ -160,7 +181,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         Commands::Verify { password, hash } => {
-            match verify_password(&password, &hash) {
+            match hasher.verify(&password, &hash) {
                 Ok(()) => {
                     println!("✓ Password matches!");
                 }
@@ -200,7 +221,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             };
 
-            match hash_password(&pwd) {
+            match hasher.hash(&pwd) {
                 Ok(hash) => {
                     let db_instance = match SqliteUserDb::new(&db).await {
                         Ok(d) => d,
@@ -279,7 +300,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             println!(
                                 "{:<20} {:<10} {:<20}",
                                 user.username,
-                                if user.enabled { "Yes" } else { "No" },
+                                if user.is_enabled() { "Yes" } else { "No" },
                                 groups_str
                             );
                         }
@@ -292,6 +313,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
+        Commands::ShowUser { db, username } => {
+            let db_instance = match SqliteUserDb::new(&db).await {
+                Ok(d) => d,
+                Err(e) => {
+                    eprintln!("✗ Error opening database: {}", e);
+                    std::process::exit(1);
+                }
+            };
+
+            match db_instance.get_user(&username).await {
+                Ok(user) => {
+                    let groups = if user.groups.is_empty() {
+                        "(none)".to_string()
+                    } else {
+                        user.groups.join(", ")
+                    };
+                    println!("User: {}", user.username);
+                    println!("  Enabled: {}", if user.is_enabled() { "Yes" } else { "No" });
+                    println!("  Groups:  {}", groups);
+                    println!("  Created: {}", format_timestamp(user.created_at));
+                    println!("  Updated: {}", format_timestamp(user.updated_at));
+                    if let Some(deleted_at) = user.deleted_at {
+                        println!("  Deleted: {}", format_timestamp(deleted_at));
+                    }
+                }
+                Err(e) => {
+                    eprintln!("✗ Error getting user: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
         Commands::ChangePassword { db, username, password } => {
             let pwd = match password {
                 Some(p) => p,
@@ -304,7 +357,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             };
 
-            match hash_password(&pwd) {
+            match hasher.hash(&pwd) {
                 Ok(hash) => {
                     let db_instance = match SqliteUserDb::new(&db).await {
                         Ok(d) => d,
@@ -354,11 +407,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             };
 
             // Get the user, modify it, and re-save
-            match db_instance.get_user(&username).await {
-                Ok(mut user) => {
-                    user.enabled = enabled_bool;
-                    // For this demo, we'll need to update in place
-                    // In a real implementation, we'd have an update_user method
+            let mut user = match db_instance.get_user(&username).await {
+                Ok(user) => user,
+                Err(e) => {
+                    eprintln!("✗ Error getting user: {}", e);
+                    std::process::exit(1);
+                }
+            };
+
+            user.enabled = enabled_bool;
+            match db_instance.update_user(user).await {
+                Ok(()) => {
                     println!(
                         "✓ User '{}' status set to: {}",
                         username,
@@ -366,7 +425,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     );
                 }
                 Err(e) => {
-                    eprintln!("✗ Error getting user: {}", e);
+                    eprintln!("✗ Error updating user: {}", e);
                     std::process::exit(1);
                 }
             }
@@ -386,7 +445,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             match SqliteUserDb::new(&db).await {
                 Ok(db_instance) => {
-                    let provider = LocalAuthProvider::new(db_instance);
+                    let provider = LocalAuthProvider::new(db_instance).with_hasher(hasher);
                     match provider.authenticate(&username, &pwd).await {
                         Ok(claims) => {
                             println!("✓ Authentication successful!");
@@ -410,3 +469,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Format a Unix timestamp as RFC 3339 (UTC), falling back to the raw number.
+fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_else(|| timestamp.to_string())
+}
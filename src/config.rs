@@ -1,10 +1,16 @@
-/// Configuration module for loading auth settings from TOML files
-///
-/// Supports loading authentication configuration (database, JWT, users) from
-/// TOML files with environment variable overrides.
+//! Configuration module for loading auth settings from TOML files
+//!
+//! Supports loading authentication configuration (database, JWT, users) from
+//! TOML files with environment variable overrides.
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::Duration;
+
+use crate::error::{AuthError, ConfigError};
+use crate::middleware::RateLimitConfig;
+use crate::password::{Argon2Hasher, PasswordHasher, PepperedHasher};
 
 /// Complete authentication configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +24,24 @@ pub struct AuthConfig {
     /// Optional server configuration (host, port)
     #[serde(default)]
     pub server: Option<ServerConfig>,
+    /// Rate limits (defaults apply when the section or a field is omitted)
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    /// Per-provider overrides keyed by provider name, e.g. `[providers.ldap]`
+    #[serde(default)]
+    pub providers: HashMap<String, ProviderConfig>,
+    /// Password hashing settings
+    #[serde(default)]
+    pub password: PasswordConfig,
+}
+
+/// Settings for a single authentication provider
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    /// Token expiration time in hours for logins through this provider
+    /// (default: `jwt.expiration_hours`)
+    #[serde(default)]
+    pub expiration_hours: Option<u32>,
 }
 
 /// Database configuration
@@ -40,6 +64,81 @@ pub struct JwtConfig {
     pub expiration_hours: u32,
 }
 
+/// Password hashing configuration
+///
+/// Setting `pepper` hashes passwords with a [`PepperedHasher`]. Prefer
+/// supplying it through `AUTH_CONFIG` or a secrets manager over committing
+/// it to the config file, so a leaked file and database together aren't
+/// enough to crack passwords. To rotate the pepper, move the old one to
+/// `previous_peppers` under its id, so existing hashes keep verifying until
+/// they are rehashed.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PasswordConfig {
+    /// Server-side secret mixed into every password (default: none)
+    #[serde(default)]
+    pub pepper: Option<String>,
+    /// Id recorded in hashes made with `pepper`; change it along with the
+    /// pepper (default: "1")
+    #[serde(default = "default_pepper_id")]
+    pub pepper_id: String,
+    /// Retired peppers still accepted for verification, by pepper id
+    /// (default: none)
+    #[serde(default)]
+    pub previous_peppers: HashMap<String, String>,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            pepper: None,
+            pepper_id: default_pepper_id(),
+            previous_peppers: HashMap::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for PasswordConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasswordConfig")
+            .field("pepper", &self.pepper.as_ref().map(|_| "<redacted>"))
+            .field("pepper_id", &self.pepper_id)
+            .field("previous_peppers", &self.previous_peppers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl PasswordConfig {
+    /// Build the configured hasher: [`PepperedHasher`] if a pepper is set,
+    /// otherwise [`Argon2Hasher`]. Previous peppers are added with
+    /// [`PepperedHasher::with_previous_pepper`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::ConfigError` if a pepper is empty or its id is
+    /// invalid, or if previous peppers are set without a current one.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let provider = LocalAuthProvider::new(db).with_hasher(config.password.hasher()?);
+    /// ```
+    pub fn hasher(&self) -> Result<Box<dyn PasswordHasher>, AuthError> {
+        match &self.pepper {
+            Some(pepper) => {
+                let mut hasher = PepperedHasher::new(Argon2Hasher, self.pepper_id.clone(), pepper)?;
+                for (id, previous) in &self.previous_peppers {
+                    hasher = hasher.with_previous_pepper(id.clone(), previous)?;
+                }
+                Ok(Box::new(hasher))
+            }
+            None if !self.previous_peppers.is_empty() => Err(AuthError::config(
+                "password.previous_peppers requires password.pepper",
+            )),
+            None => Ok(Box::new(Argon2Hasher)),
+        }
+    }
+}
+
 /// User configuration for creation on startup
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserConfig {
@@ -63,8 +162,11 @@ pub struct TlsConfig {
     pub enabled: bool,
     /// Path to certificate file (PEM format)
     pub certificate: String,
-    /// Path to private key file (PEM format)
+    /// Path to private key file (PKCS#1, PKCS#8 or SEC1 PEM)
     pub key: String,
+    /// Passphrase for an encrypted PKCS#8 private key
+    #[serde(default)]
+    pub key_password: Option<String>,
     /// Optional path to CA certificate chain (PEM format)
     #[serde(default)]
     pub ca_chain: Option<String>,
@@ -82,6 +184,43 @@ pub struct ServerConfig {
     pub tls: Option<TlsConfig>,
 }
 
+impl UserConfig {
+    /// Create an enabled user with no groups.
+    pub fn new<U: Into<String>, P: Into<String>>(username: U, password: P) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+            groups: Vec::new(),
+            enabled: default_enabled(),
+        }
+    }
+
+    /// Set the user's groups.
+    pub fn with_groups<S: Into<String>>(mut self, groups: Vec<S>) -> Self {
+        self.groups = groups.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set whether the user is enabled.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
+
+impl TlsConfig {
+    /// Create an enabled TLS configuration from certificate and key paths.
+    pub fn new<C: Into<String>, K: Into<String>>(certificate: C, key: K) -> Self {
+        Self {
+            enabled: true,
+            certificate: certificate.into(),
+            key: key.into(),
+            key_password: None,
+            ca_chain: None,
+        }
+    }
+}
+
 fn default_auto_create() -> bool {
     true
 }
@@ -94,7 +233,28 @@ fn default_enabled() -> bool {
     true
 }
 
+fn default_pepper_id() -> String {
+    "1".to_string()
+}
+
 impl AuthConfig {
+    /// Start building a configuration in code.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let config = AuthConfig::builder()
+    ///     .database_path("users.db")
+    ///     .jwt_secret(std::env::var("JWT_SECRET")?)
+    ///     .add_user(UserConfig::new("admin", admin_password).with_groups(vec!["admins"]))
+    ///     .server("0.0.0.0", 8443)
+    ///     .tls(TlsConfig::new("cert.pem", "key.pem"))
+    ///     .build()?;
+    /// ```
+    pub fn builder() -> AuthConfigBuilder {
+        AuthConfigBuilder::default()
+    }
+
     /// Load configuration from TOML file
     ///
     /// # Arguments
@@ -103,7 +263,9 @@ impl AuthConfig {
     ///
     /// # Errors
     ///
-    /// Returns error if file cannot be read or TOML is invalid
+    /// Returns `ConfigError::FileNotFound` if the file doesn't exist,
+    /// `ConfigError::IoError` if it can't be read and `ConfigError::ParseError`
+    /// if the TOML is invalid.
     ///
     /// # Example
     ///
@@ -111,8 +273,12 @@ impl AuthConfig {
     /// let config = AuthConfig::from_file("auth.toml")?;
     /// config.validate()?;
     /// ```
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
-        let content = std::fs::read_to_string(path)?;
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ConfigError::file_not_found(path.display().to_string()),
+            _ => ConfigError::from(e),
+        })?;
         let config = toml::from_str(&content)?;
         Ok(config)
     }
@@ -131,7 +297,7 @@ impl AuthConfig {
     /// ```ignore
     /// let config = AuthConfig::from_env_or_file("auth.toml")?;
     /// ```
-    pub fn from_env_or_file(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_env_or_file(file_path: &str) -> Result<Self, ConfigError> {
         if let Ok(config_str) = std::env::var("AUTH_CONFIG") {
             let config = toml::from_str(&config_str)?;
             Ok(config)
@@ -145,6 +311,9 @@ impl AuthConfig {
     /// Checks:
     /// - JWT secret is at least 16 characters
     /// - Database path is not empty
+    /// - Rate limits are non-zero
+    /// - Per-provider expirations are non-zero
+    /// - The password peppers, if set, are non-empty with valid ids
     /// - TLS certificate and key files exist (if TLS is enabled)
     ///
     /// # Errors
@@ -157,6 +326,17 @@ impl AuthConfig {
         if self.database.path.is_empty() {
             return Err("Database path cannot be empty".to_string());
         }
+        if self.rate_limit.requests_per_minute == 0 || self.rate_limit.auth_endpoint_limit == 0 {
+            return Err("Rate limits must be greater than zero".to_string());
+        }
+        if let Some((name, _)) = self
+            .providers
+            .iter()
+            .find(|(_, provider)| provider.expiration_hours == Some(0))
+        {
+            return Err(format!("Provider '{}' expiration_hours must be greater than zero", name));
+        }
+        self.password.hasher().map_err(|e| e.to_string())?;
 
         // Validate TLS config if present
         if let Some(server) = &self.server {
@@ -183,6 +363,18 @@ impl AuthConfig {
         Ok(())
     }
 
+    /// Token lifetime for logins through `provider`
+    ///
+    /// Uses the provider's `expiration_hours` if set, otherwise `jwt.expiration_hours`.
+    pub fn expiration_for(&self, provider: &str) -> Duration {
+        let hours = self
+            .providers
+            .get(provider)
+            .and_then(|p| p.expiration_hours)
+            .unwrap_or(self.jwt.expiration_hours);
+        Duration::from_secs(u64::from(hours) * 60 * 60)
+    }
+
     /// Get server configuration with defaults
     pub fn server_config(&self) -> (String, u16) {
         match &self.server {
@@ -192,15 +384,147 @@ impl AuthConfig {
     }
 }
 
+/// Fluent builder for [`AuthConfig`], created by [`AuthConfig::builder`].
+///
+/// Unset fields get the same defaults as an omitted TOML entry: an
+/// auto-created database, 24 hour tokens, no users and default rate limits.
+/// The database path and JWT secret must be set for `build` to succeed.
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfigBuilder {
+    database_path: String,
+    auto_create: Option<bool>,
+    jwt_secret: String,
+    expiration_hours: Option<u32>,
+    users: Vec<UserConfig>,
+    server: Option<ServerConfig>,
+    rate_limit: RateLimitConfig,
+    providers: HashMap<String, ProviderConfig>,
+    password: PasswordConfig,
+}
+
+impl AuthConfigBuilder {
+    /// Set the SQLite database path.
+    pub fn database_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.database_path = path.into();
+        self
+    }
+
+    /// Set whether the database is created if missing (default: true).
+    pub fn auto_create(mut self, auto_create: bool) -> Self {
+        self.auto_create = Some(auto_create);
+        self
+    }
+
+    /// Set the JWT signing secret.
+    pub fn jwt_secret<S: Into<String>>(mut self, secret: S) -> Self {
+        self.jwt_secret = secret.into();
+        self
+    }
+
+    /// Set the token expiration in hours (default: 24).
+    pub fn expiration_hours(mut self, hours: u32) -> Self {
+        self.expiration_hours = Some(hours);
+        self
+    }
+
+    /// Add a user to create on startup.
+    pub fn add_user(mut self, user: UserConfig) -> Self {
+        self.users.push(user);
+        self
+    }
+
+    /// Set the host and port to bind to, keeping any TLS settings.
+    pub fn server<S: Into<String>>(mut self, host: S, port: u16) -> Self {
+        let tls = self.server.take().and_then(|server| server.tls);
+        self.server = Some(ServerConfig {
+            host: host.into(),
+            port,
+            tls,
+        });
+        self
+    }
+
+    /// Serve over TLS.
+    ///
+    /// Uses the default `0.0.0.0:3000` unless [`server`](Self::server) is set.
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        let (host, port) = match self.server.take() {
+            Some(server) => (server.host, server.port),
+            None => ("0.0.0.0".to_string(), 3000),
+        };
+        self.server = Some(ServerConfig {
+            host,
+            port,
+            tls: Some(tls),
+        });
+        self
+    }
+
+    /// Set the rate limits.
+    pub fn rate_limit(mut self, rate_limit: RateLimitConfig) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Set the token expiration for logins through the named provider.
+    pub fn provider_expiration_hours<S: Into<String>>(mut self, provider: S, hours: u32) -> Self {
+        self.providers.insert(
+            provider.into(),
+            ProviderConfig {
+                expiration_hours: Some(hours),
+            },
+        );
+        self
+    }
+
+    /// Mix `pepper` into password hashes, recording `pepper_id` in them.
+    pub fn password_pepper<I: Into<String>, P: Into<String>>(mut self, pepper_id: I, pepper: P) -> Self {
+        self.password.pepper = Some(pepper.into());
+        self.password.pepper_id = pepper_id.into();
+        self
+    }
+
+    /// Keep verifying hashes made with a retired pepper.
+    pub fn previous_password_pepper<I: Into<String>, P: Into<String>>(mut self, pepper_id: I, pepper: P) -> Self {
+        self.password.previous_peppers.insert(pepper_id.into(), pepper.into());
+        self
+    }
+
+    /// Build the configuration and [`validate`](AuthConfig::validate) it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the validation error message if the configuration is invalid.
+    pub fn build(self) -> Result<AuthConfig, String> {
+        let config = AuthConfig {
+            database: DatabaseConfig {
+                path: self.database_path,
+                auto_create: self.auto_create.unwrap_or_else(default_auto_create),
+            },
+            jwt: JwtConfig {
+                secret: self.jwt_secret,
+                expiration_hours: self.expiration_hours.unwrap_or_else(default_expiration_hours),
+            },
+            users: self.users,
+            server: self.server,
+            rate_limit: self.rate_limit,
+            providers: self.providers,
+            password: self.password,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_default_config_values() {
-        assert_eq!(default_auto_create(), true);
+        assert!(default_auto_create());
         assert_eq!(default_expiration_hours(), 24);
-        assert_eq!(default_enabled(), true);
+        assert!(default_enabled());
     }
 
     #[test]
@@ -216,6 +540,9 @@ mod tests {
             },
             users: vec![],
             server: None,
+            rate_limit: RateLimitConfig::default(),
+            providers: HashMap::new(),
+            password: PasswordConfig::default(),
         };
 
         assert!(config.validate().is_err());
@@ -234,6 +561,9 @@ mod tests {
             },
             users: vec![],
             server: None,
+            rate_limit: RateLimitConfig::default(),
+            providers: HashMap::new(),
+            password: PasswordConfig::default(),
         };
 
         assert!(config.validate().is_err());
@@ -252,8 +582,200 @@ mod tests {
             },
             users: vec![],
             server: None,
+            rate_limit: RateLimitConfig::default(),
+            providers: HashMap::new(),
+            password: PasswordConfig::default(),
         };
 
         assert!(config.validate().is_ok());
     }
+
+    #[test]
+    fn test_builder_produces_valid_config() {
+        let config = AuthConfig::builder()
+            .database_path("users.db")
+            .jwt_secret("my-super-secret-key")
+            .expiration_hours(8)
+            .add_user(UserConfig::new("admin", "admin-password").with_groups(vec!["admins"]))
+            .add_user(UserConfig::new("guest", "guest-password").with_enabled(false))
+            .server("127.0.0.1", 8080)
+            .provider_expiration_hours("ldap", 2)
+            .build()
+            .unwrap();
+
+        assert!(config.database.auto_create);
+        assert_eq!(config.users.len(), 2);
+        assert_eq!(config.users[0].groups, vec!["admins"]);
+        assert!(!config.users[1].enabled);
+        assert_eq!(config.server_config(), ("127.0.0.1".to_string(), 8080));
+        assert_eq!(config.expiration_for("local"), Duration::from_secs(8 * 3600));
+        assert_eq!(config.expiration_for("ldap"), Duration::from_secs(2 * 3600));
+
+        // Survives a TOML round trip unchanged
+        let reparsed: AuthConfig = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert!(reparsed.validate().is_ok());
+        assert_eq!(reparsed.users[0].username, "admin");
+        assert_eq!(reparsed.jwt.expiration_hours, 8);
+    }
+
+    #[test]
+    fn test_password_pepper_config() {
+        let config: AuthConfig = toml::from_str(
+            r#"
+            users = []
+            [database]
+            path = "users.db"
+            [jwt]
+            secret = "my-super-secret-key"
+            [password]
+            pepper = "server-side-pepper"
+            pepper_id = "2024-01"
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert!(!format!("{:?}", config).contains("server-side-pepper"));
+
+        let hash = config.password.hasher().unwrap().hash("password1").unwrap();
+        assert!(hash.starts_with("$pepper$2024-01$"));
+        let plain = PasswordConfig::default().hasher().unwrap();
+        assert!(plain.hash("password1").unwrap().starts_with("$argon2id$"));
+        // Hashes from before the pepper was enabled still verify
+        assert!(config.password.hasher().unwrap().verify("password1", &plain.hash("password1").unwrap()).is_ok());
+
+        let invalid = AuthConfig::builder()
+            .database_path("users.db")
+            .jwt_secret("my-super-secret-key")
+            .password_pepper("v$1", "pepper")
+            .build();
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_previous_password_peppers() {
+        let old: AuthConfig = toml::from_str(
+            r#"
+            users = []
+            [database]
+            path = "users.db"
+            [jwt]
+            secret = "my-super-secret-key"
+            [password]
+            pepper = "old-pepper"
+            pepper_id = "2024-01"
+            "#,
+        )
+        .unwrap();
+        let old_hash = old.password.hasher().unwrap().hash("password1").unwrap();
+
+        let rotated: AuthConfig = toml::from_str(
+            r#"
+            users = []
+            [database]
+            path = "users.db"
+            [jwt]
+            secret = "my-super-secret-key"
+            [password]
+            pepper = "new-pepper"
+            pepper_id = "2024-06"
+            [password.previous_peppers]
+            "2024-01" = "old-pepper"
+            "#,
+        )
+        .unwrap();
+        assert!(rotated.validate().is_ok());
+        assert!(!format!("{:?}", rotated).contains("old-pepper"));
+        let hasher = rotated.password.hasher().unwrap();
+        assert!(hasher.verify("password1", &old_hash).is_ok());
+        assert!(hasher.hash("password1").unwrap().starts_with("$pepper$2024-06$"));
+
+        // Without the previous pepper, the old hash no longer verifies
+        let mut forgotten = rotated.password.clone();
+        forgotten.previous_peppers.clear();
+        assert!(forgotten.hasher().unwrap().verify("password1", &old_hash).is_err());
+
+        let orphaned = AuthConfig::builder()
+            .database_path("users.db")
+            .jwt_secret("my-super-secret-key")
+            .previous_password_pepper("2024-01", "old-pepper")
+            .build();
+        assert!(orphaned.is_err());
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_config() {
+        assert!(AuthConfig::builder().database_path("users.db").build().is_err());
+        assert!(AuthConfig::builder().jwt_secret("my-super-secret-key").build().is_err());
+
+        // TLS files are checked like any other config
+        let result = AuthConfig::builder()
+            .database_path("users.db")
+            .jwt_secret("my-super-secret-key")
+            .tls(TlsConfig::new("/nonexistent/cert.pem", "/nonexistent/key.pem"))
+            .build();
+        assert!(result.unwrap_err().contains("TLS certificate not found"));
+    }
+
+    const MINIMAL_TOML: &str = r#"
+        users = []
+
+        [database]
+        path = "users.db"
+
+        [jwt]
+        secret = "my-super-secret-key"
+    "#;
+
+    #[test]
+    fn test_from_file_reports_missing_file() {
+        let err = AuthConfig::from_file("/nonexistent/auth.toml").unwrap_err();
+        assert!(matches!(&err, ConfigError::FileNotFound(path) if path == "/nonexistent/auth.toml"));
+    }
+
+    #[test]
+    fn test_rate_limit_section_defaults_when_omitted() {
+        let config: AuthConfig = toml::from_str(MINIMAL_TOML).unwrap();
+        assert_eq!(config.rate_limit, RateLimitConfig::default());
+    }
+
+    #[test]
+    fn test_rate_limit_section_parsing() {
+        let toml_str = format!(
+            "{}\n[rate_limit]\nrequests_per_minute = 600\nkey_based = false\n",
+            MINIMAL_TOML
+        );
+        let config: AuthConfig = toml::from_str(&toml_str).unwrap();
+
+        assert_eq!(config.rate_limit.requests_per_minute, 600);
+        assert!(!config.rate_limit.use_key_based);
+        // Omitted fields keep their defaults
+        assert_eq!(config.rate_limit.auth_endpoint_limit, 5);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_zero_rate_limit() {
+        let toml_str = format!("{}\n[rate_limit]\nauth_endpoint_limit = 0\n", MINIMAL_TOML);
+        let config: AuthConfig = toml::from_str(&toml_str).unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_provider_expiration_overrides() {
+        let toml_str = format!(
+            "{}\n[providers.ldap]\nexpiration_hours = 8\n\n[providers.local]\n",
+            MINIMAL_TOML
+        );
+        let config: AuthConfig = toml::from_str(&toml_str).unwrap();
+
+        assert_eq!(config.expiration_for("ldap"), Duration::from_secs(8 * 3600));
+        // No override (or no entry at all) falls back to jwt.expiration_hours
+        assert_eq!(config.expiration_for("local"), Duration::from_secs(24 * 3600));
+        assert_eq!(config.expiration_for("oauth2"), Duration::from_secs(24 * 3600));
+        assert!(config.validate().is_ok());
+
+        let toml_str = format!("{}\n[providers.ldap]\nexpiration_hours = 0\n", MINIMAL_TOML);
+        let config: AuthConfig = toml::from_str(&toml_str).unwrap();
+        assert!(config.validate().is_err());
+    }
 }
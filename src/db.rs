@@ -3,12 +3,14 @@
 //! This module provides traits and types for user database operations.
 //! Users can implement the `UserDatabase` trait to support custom storage backends.
 
+pub mod memory;
 pub mod models;
 
-pub use models::{UserDatabase, UserRecord};
+pub use memory::MemoryUserDb;
+pub use models::{UserDatabase, UserRecord, UsernamePolicy};
 
 /// Module for SQLite-specific implementations.
 /// Available when the `sqlite` feature is enabled.
 pub mod sqlite;
 
-pub use sqlite::SqliteUserDb;
+pub use sqlite::{SqliteDbOptions, SqliteUserDb};
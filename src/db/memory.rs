@@ -0,0 +1,434 @@
+//! In-memory user database.
+//!
+//! A dependency-light `UserDatabase` backed by a `HashMap`, intended for unit
+//! tests and examples where persistence isn't needed.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::models::{UserDatabase, UserRecord, UsernamePolicy};
+use crate::error::AuthError;
+
+/// In-memory user database.
+///
+/// Data lives only as long as the process. Clones share the same storage, so
+/// a test can hand one clone to a provider and inspect another.
+///
+/// `create_user` rejects usernames that break the [`UsernamePolicy`].
+///
+/// # Example
+///
+/// ```ignore
+/// use poem_auth::db::MemoryUserDb;
+/// use poem_auth::providers::LocalAuthProvider;
+///
+/// let db = MemoryUserDb::new();
+/// db.create_user(UserRecord::new("alice", &hash_password("secret")?)).await?;
+///
+/// let provider = LocalAuthProvider::new(db.clone());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryUserDb {
+    users: Arc<RwLock<HashMap<String, UserRecord>>>,
+    username_policy: UsernamePolicy,
+}
+
+impl MemoryUserDb {
+    /// Create an empty in-memory database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the policy new usernames must satisfy.
+    pub fn with_username_policy(mut self, policy: UsernamePolicy) -> Self {
+        self.username_policy = policy;
+        self
+    }
+
+    /// Apply `f` to a stored user, returning `UserNotFound` if it doesn't exist.
+    fn modify<F>(&self, username: &str, f: F) -> Result<(), AuthError>
+    where
+        F: FnOnce(&mut UserRecord),
+    {
+        let mut users = self.users.write().unwrap();
+        let user = users.get_mut(username).ok_or(AuthError::UserNotFound)?;
+        f(user);
+        user.updated_at = chrono::Utc::now().timestamp();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserDatabase for MemoryUserDb {
+    async fn get_user(&self, username: &str) -> Result<UserRecord, AuthError> {
+        self.users
+            .read()
+            .unwrap()
+            .get(username)
+            .cloned()
+            .ok_or(AuthError::UserNotFound)
+    }
+
+    async fn create_user(&self, user: UserRecord) -> Result<(), AuthError> {
+        user.validate_with(&self.username_policy)?;
+        let mut users = self.users.write().unwrap();
+        if users.contains_key(&user.username) {
+            return Err(AuthError::other(format!("User '{}' already exists", user.username)));
+        }
+        users.insert(user.username.clone(), user);
+        Ok(())
+    }
+
+    async fn update_password(&self, username: &str, hash: String) -> Result<(), AuthError> {
+        self.modify(username, |user| user.password_hash = hash)
+    }
+
+    async fn update_user(&self, user: UserRecord) -> Result<(), AuthError> {
+        let username = user.username.clone();
+        self.modify(&username, |stored| {
+            stored.password_hash = user.password_hash;
+            stored.groups = user.groups;
+            stored.enabled = user.enabled;
+            stored.deleted_at = user.deleted_at;
+        })
+    }
+
+    async fn count_users(&self) -> Result<u64, AuthError> {
+        let users = self.users.read().unwrap();
+        Ok(users.values().filter(|u| !u.is_deleted()).count() as u64)
+    }
+
+    async fn list_users(&self) -> Result<Vec<UserRecord>, AuthError> {
+        self.list_users_filtered(false).await
+    }
+
+    async fn list_users_filtered(&self, include_deleted: bool) -> Result<Vec<UserRecord>, AuthError> {
+        let mut users: Vec<UserRecord> = self
+            .users
+            .read()
+            .unwrap()
+            .values()
+            .filter(|u| include_deleted || !u.is_deleted())
+            .cloned()
+            .collect();
+        users.sort_by(|a, b| a.username.cmp(&b.username));
+        Ok(users)
+    }
+
+    async fn delete_user(&self, username: &str) -> Result<(), AuthError> {
+        self.users
+            .write()
+            .unwrap()
+            .remove(username)
+            .map(|_| ())
+            .ok_or(AuthError::UserNotFound)
+    }
+
+    async fn soft_delete_user(&self, username: &str) -> Result<(), AuthError> {
+        let now = chrono::Utc::now().timestamp();
+        self.modify(username, |user| user.deleted_at = Some(now))
+    }
+
+    async fn restore_user(&self, username: &str) -> Result<(), AuthError> {
+        let mut users = self.users.write().unwrap();
+        let user = users
+            .get_mut(username)
+            .filter(|user| user.deleted_at.is_some())
+            .ok_or(AuthError::UserNotFound)?;
+        user.deleted_at = None;
+        user.updated_at = chrono::Utc::now().timestamp();
+        Ok(())
+    }
+
+    async fn user_exists(&self, username: &str) -> Result<bool, AuthError> {
+        Ok(self.users.read().unwrap().contains_key(username))
+    }
+
+    async fn ping(&self) -> Result<(), AuthError> {
+        Ok(())
+    }
+
+    async fn update_groups(&self, username: &str, groups: Vec<String>) -> Result<(), AuthError> {
+        self.modify(username, |user| user.groups = groups)
+    }
+
+    async fn add_group(&self, username: &str, group: &str) -> Result<(), AuthError> {
+        self.modify(username, |user| {
+            if !user.has_group(group) {
+                user.groups.push(group.to_string());
+            }
+        })
+    }
+
+    async fn remove_group(&self, username: &str, group: &str) -> Result<(), AuthError> {
+        self.modify(username, |user| user.groups.retain(|g| g != group))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_db() -> MemoryUserDb {
+        let db = MemoryUserDb::new();
+        db.create_user(UserRecord::new("alice", "hash1").with_groups(vec!["users"]))
+            .await
+            .unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_create_and_get_user() {
+        let db = test_db().await;
+        let user = db.get_user("alice").await.unwrap();
+        assert_eq!(user.password_hash, "hash1");
+        assert_eq!(user.groups, vec!["users"]);
+    }
+
+    #[tokio::test]
+    async fn test_create_user_validates_username() {
+        let db = MemoryUserDb::new();
+        assert!(matches!(
+            db.create_user(UserRecord::new("", "hash")).await,
+            Err(AuthError::Other(_))
+        ));
+
+        let db = db.with_username_policy(UsernamePolicy::default().with_max_length(3));
+        assert!(db.create_user(UserRecord::new("alice", "hash")).await.is_err());
+        assert!(db.create_user(UserRecord::new("bob", "hash")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_list_users_in_group_default() {
+        let db = test_db().await;
+        db.create_user(UserRecord::new("bob", "hash").with_groups(vec!["admins", "users"]))
+            .await
+            .unwrap();
+        db.create_user(UserRecord::new("carol", "hash").with_groups(vec!["admins"]))
+            .await
+            .unwrap();
+        db.create_user(UserRecord::new("dave", "hash").with_groups(vec!["administrators"]))
+            .await
+            .unwrap();
+        db.soft_delete_user("carol").await.unwrap();
+
+        let mut admins: Vec<String> = db
+            .list_users_in_group("admins")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|u| u.username)
+            .collect();
+        admins.sort();
+        assert_eq!(admins, vec!["bob"]);
+        assert_eq!(db.count_by_group("users").await.unwrap(), 2);
+        assert!(db.list_users_in_group("nobody").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_missing_user() {
+        let db = test_db().await;
+        assert!(matches!(db.get_user("nobody").await, Err(AuthError::UserNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_create_duplicate_user() {
+        let db = test_db().await;
+        assert!(db.create_user(UserRecord::new("alice", "other")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_password() {
+        let db = test_db().await;
+        db.update_password("alice", "hash2".to_string()).await.unwrap();
+        assert_eq!(db.get_user("alice").await.unwrap().password_hash, "hash2");
+        assert!(matches!(
+            db.update_password("nobody", "hash".to_string()).await,
+            Err(AuthError::UserNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_user() {
+        let db = test_db().await;
+        let created_at = db.get_user("alice").await.unwrap().created_at;
+
+        let user = UserRecord::new("alice", "hash2").with_groups(vec!["admins"]).disable();
+        db.update_user(user).await.unwrap();
+
+        let stored = db.get_user("alice").await.unwrap();
+        assert_eq!(stored.password_hash, "hash2");
+        assert_eq!(stored.groups, vec!["admins"]);
+        assert!(!stored.enabled);
+        assert_eq!(stored.created_at, created_at);
+
+        assert!(matches!(
+            db.update_user(UserRecord::new("nobody", "hash")).await,
+            Err(AuthError::UserNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_count_users() {
+        let db = test_db().await;
+        db.create_user(UserRecord::new("bob", "hash")).await.unwrap();
+        db.create_user(UserRecord::new("carol", "hash")).await.unwrap();
+        db.soft_delete_user("carol").await.unwrap();
+
+        let names: Vec<_> = db.list_users().await.unwrap().into_iter().map(|u| u.username).collect();
+        assert_eq!(names, vec!["alice", "bob"]);
+        assert_eq!(db.list_users_filtered(true).await.unwrap().len(), 3);
+        assert_eq!(db.count_users().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() {
+        let db = test_db().await;
+        db.delete_user("alice").await.unwrap();
+        assert!(!db.user_exists("alice").await.unwrap());
+        assert!(matches!(db.delete_user("alice").await, Err(AuthError::UserNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_and_restore() {
+        let db = test_db().await;
+
+        db.soft_delete_user("alice").await.unwrap();
+        let user = db.get_user("alice").await.unwrap();
+        assert!(user.is_deleted());
+        assert!(!user.is_enabled());
+
+        db.restore_user("alice").await.unwrap();
+        let user = db.get_user("alice").await.unwrap();
+        assert!(!user.is_deleted());
+        assert!(user.is_enabled());
+
+        assert!(matches!(db.soft_delete_user("nobody").await, Err(AuthError::UserNotFound)));
+        assert!(matches!(db.restore_user("nobody").await, Err(AuthError::UserNotFound)));
+
+        // Restoring only undoes a soft delete, never an admin's disable
+        db.create_user(UserRecord::new("bob", "hash").disable()).await.unwrap();
+        assert!(matches!(db.restore_user("bob").await, Err(AuthError::UserNotFound)));
+        assert!(!db.get_user("bob").await.unwrap().enabled);
+
+        // A disabled account stays disabled across soft-delete and restore
+        db.soft_delete_user("bob").await.unwrap();
+        db.restore_user("bob").await.unwrap();
+        let user = db.get_user("bob").await.unwrap();
+        assert!(!user.is_deleted());
+        assert!(!user.enabled);
+    }
+
+    #[tokio::test]
+    async fn test_user_exists_and_ping() {
+        let db = test_db().await;
+        assert!(db.user_exists("alice").await.unwrap());
+        assert!(!db.user_exists("nobody").await.unwrap());
+        assert!(db.ping().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_update_groups() {
+        let db = test_db().await;
+        db.update_groups("alice", vec!["admins".to_string(), "ops".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(db.get_user("alice").await.unwrap().groups, vec!["admins", "ops"]);
+        assert!(matches!(
+            db.update_groups("nobody", vec![]).await,
+            Err(AuthError::UserNotFound)
+        ));
+    }
+
+    /// Backend that only implements the required methods, so group changes
+    /// and soft deletes go through the trait defaults. Like a backend that
+    /// predates soft delete, its `list_users` returns every row.
+    #[derive(Debug)]
+    struct RequiredOnly(MemoryUserDb);
+
+    #[async_trait]
+    impl UserDatabase for RequiredOnly {
+        async fn get_user(&self, username: &str) -> Result<UserRecord, AuthError> {
+            self.0.get_user(username).await
+        }
+
+        async fn create_user(&self, user: UserRecord) -> Result<(), AuthError> {
+            self.0.create_user(user).await
+        }
+
+        async fn update_password(&self, username: &str, hash: String) -> Result<(), AuthError> {
+            self.0.update_password(username, hash).await
+        }
+
+        async fn update_user(&self, user: UserRecord) -> Result<(), AuthError> {
+            self.0.update_user(user).await
+        }
+
+        async fn list_users(&self) -> Result<Vec<UserRecord>, AuthError> {
+            self.0.list_users_filtered(true).await
+        }
+
+        async fn delete_user(&self, username: &str) -> Result<(), AuthError> {
+            self.0.delete_user(username).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_update_groups_persists() {
+        let db = RequiredOnly(test_db().await);
+
+        db.update_groups("alice", vec!["admins".to_string()]).await.unwrap();
+        assert_eq!(db.0.get_user("alice").await.unwrap().groups, vec!["admins"]);
+
+        db.add_group("alice", "ops").await.unwrap();
+        db.remove_group("alice", "admins").await.unwrap();
+        assert_eq!(db.0.get_user("alice").await.unwrap().groups, vec!["ops"]);
+
+        assert!(matches!(
+            db.update_groups("nobody", vec![]).await,
+            Err(AuthError::UserNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_default_soft_delete_and_restore() {
+        let db = RequiredOnly(test_db().await);
+        db.0.create_user(UserRecord::new("bob", "hash").disable()).await.unwrap();
+
+        db.soft_delete_user("bob").await.unwrap();
+        assert!(db.0.get_user("bob").await.unwrap().is_deleted());
+        let names: Vec<_> = db.list_users_filtered(false).await.unwrap().into_iter().map(|u| u.username).collect();
+        assert_eq!(names, vec!["alice"]);
+        assert_eq!(db.list_users_filtered(true).await.unwrap().len(), 2);
+
+        db.restore_user("bob").await.unwrap();
+        let user = db.0.get_user("bob").await.unwrap();
+        assert!(!user.is_deleted());
+        assert!(!user.enabled);
+
+        assert!(matches!(db.restore_user("alice").await, Err(AuthError::UserNotFound)));
+        assert!(matches!(db.soft_delete_user("nobody").await, Err(AuthError::UserNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_group() {
+        let db = test_db().await;
+
+        db.add_group("alice", "admins").await.unwrap();
+        db.add_group("alice", "admins").await.unwrap();
+        assert_eq!(db.get_user("alice").await.unwrap().groups, vec!["users", "admins"]);
+
+        db.remove_group("alice", "users").await.unwrap();
+        db.remove_group("alice", "ops").await.unwrap();
+        assert_eq!(db.get_user("alice").await.unwrap().groups, vec!["admins"]);
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_storage() {
+        let db = test_db().await;
+        let other = db.clone();
+        other.create_user(UserRecord::new("bob", "hash")).await.unwrap();
+        assert!(db.user_exists("bob").await.unwrap());
+    }
+}
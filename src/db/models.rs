@@ -71,7 +71,20 @@ pub trait UserDatabase: Send + Sync + std::fmt::Debug {
     /// * `Err(AuthError::DatabaseError)` on database errors
     async fn update_password(&self, username: &str, hash: String) -> Result<(), AuthError>;
 
-    /// List all users in the system.
+    /// Replace a stored user record.
+    ///
+    /// The user is matched by `user.username`. Password hash, groups,
+    /// enabled flag and `deleted_at` are overwritten; `created_at` is kept
+    /// and `updated_at` is set to the current time.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success
+    /// * `Err(AuthError::UserNotFound)` if user doesn't exist
+    /// * `Err(AuthError::DatabaseError)` on database errors
+    async fn update_user(&self, user: UserRecord) -> Result<(), AuthError>;
+
+    /// List all users in the system, excluding soft-deleted accounts.
     ///
     /// # Returns
     ///
@@ -79,7 +92,31 @@ pub trait UserDatabase: Send + Sync + std::fmt::Debug {
     /// * `Err(AuthError::DatabaseError)` on database errors
     async fn list_users(&self) -> Result<Vec<UserRecord>, AuthError>;
 
-    /// Delete a user record.
+    /// List users, optionally including soft-deleted accounts.
+    ///
+    /// Default implementation filters `list_users`, so `include_deleted` only
+    /// returns deleted rows if `list_users` does. Backends that hide deleted
+    /// rows from `list_users` should override this.
+    ///
+    /// # Arguments
+    ///
+    /// * `include_deleted` - Whether soft-deleted users should be returned
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<UserRecord>)` list of matching users
+    /// * `Err(AuthError::DatabaseError)` on database errors
+    async fn list_users_filtered(&self, include_deleted: bool) -> Result<Vec<UserRecord>, AuthError> {
+        let mut users = self.list_users().await?;
+        if !include_deleted {
+            users.retain(|user| !user.is_deleted());
+        }
+        Ok(users)
+    }
+
+    /// Permanently delete a user record.
+    ///
+    /// Prefer `soft_delete_user` when the account history should be kept.
     ///
     /// # Returns
     ///
@@ -88,6 +125,72 @@ pub trait UserDatabase: Send + Sync + std::fmt::Debug {
     /// * `Err(AuthError::DatabaseError)` on database errors
     async fn delete_user(&self, username: &str) -> Result<(), AuthError>;
 
+    /// Soft-delete a user record.
+    ///
+    /// Records a `deleted_at` timestamp while keeping the row, so it can be
+    /// restored later with `restore_user`. The `enabled` flag is left as is,
+    /// but [`UserRecord::is_enabled`] reports the account as disabled.
+    ///
+    /// Default implementation uses `get_user` and `update_user`, but can be
+    /// overridden for efficiency.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success
+    /// * `Err(AuthError::UserNotFound)` if user doesn't exist
+    /// * `Err(AuthError::DatabaseError)` on database errors
+    async fn soft_delete_user(&self, username: &str) -> Result<(), AuthError> {
+        let mut user = self.get_user(username).await?;
+        user.deleted_at = Some(chrono::Utc::now().timestamp());
+        self.update_user(user).await
+    }
+
+    /// Restore a soft-deleted user record.
+    ///
+    /// Clears the `deleted_at` timestamp only, so an account that was
+    /// disabled before it was deleted comes back disabled.
+    ///
+    /// Default implementation uses `get_user` and `update_user`, but can be
+    /// overridden for efficiency.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success
+    /// * `Err(AuthError::UserNotFound)` if user doesn't exist or isn't soft-deleted
+    /// * `Err(AuthError::DatabaseError)` on database errors
+    async fn restore_user(&self, username: &str) -> Result<(), AuthError> {
+        let mut user = self.get_user(username).await?;
+        if !user.is_deleted() {
+            return Err(AuthError::UserNotFound);
+        }
+        user.deleted_at = None;
+        self.update_user(user).await
+    }
+
+    /// Count users, excluding soft-deleted accounts.
+    ///
+    /// Default implementation uses `list_users`, but can be overridden for efficiency.
+    async fn count_users(&self) -> Result<u64, AuthError> {
+        Ok(self.list_users().await?.len() as u64)
+    }
+
+    /// List users that belong to `group`, excluding soft-deleted accounts.
+    ///
+    /// Default implementation filters `list_users`, but can be overridden for efficiency.
+    #[doc(alias = "list_users_by_group")]
+    async fn list_users_in_group(&self, group: &str) -> Result<Vec<UserRecord>, AuthError> {
+        let mut users = self.list_users().await?;
+        users.retain(|user| user.has_group(group));
+        Ok(users)
+    }
+
+    /// Count users that belong to `group`, excluding soft-deleted accounts.
+    ///
+    /// Default implementation uses `list_users_in_group`, but can be overridden for efficiency.
+    async fn count_by_group(&self, group: &str) -> Result<u64, AuthError> {
+        Ok(self.list_users_in_group(group).await?.len() as u64)
+    }
+
     /// Check if a user exists.
     ///
     /// Default implementation uses `get_user`, but can be overridden for efficiency.
@@ -99,9 +202,25 @@ pub trait UserDatabase: Send + Sync + std::fmt::Debug {
         }
     }
 
+    /// Check that the database is reachable.
+    ///
+    /// Used by health checks. Default implementation lists users, but
+    /// implementations should override it with something cheaper.
+    async fn ping(&self) -> Result<(), AuthError> {
+        self.list_users().await.map(|_| ())
+    }
+
     /// Update a user's groups.
     ///
-    /// Default implementation should be overridden by actual implementations.
+    /// Default implementation does a read-modify-write through `get_user`
+    /// and `update_user`, which can lose concurrent updates; implementations
+    /// should override it with an atomic update.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success
+    /// * `Err(AuthError::UserNotFound)` if user doesn't exist
+    /// * `Err(AuthError::DatabaseError)` on database errors
     async fn update_groups(
         &self,
         username: &str,
@@ -109,8 +228,121 @@ pub trait UserDatabase: Send + Sync + std::fmt::Debug {
     ) -> Result<(), AuthError> {
         let mut user = self.get_user(username).await?;
         user.groups = groups;
-        // In a real implementation, this would update the database
-        // For now, we need a way to persist this change
+        self.update_user(user).await
+    }
+
+    /// Add a single group to a user.
+    ///
+    /// Adding a group the user already has is a no-op.
+    ///
+    /// Default implementation does a read-modify-write through `update_groups`,
+    /// which can lose concurrent updates; implementations should override it
+    /// with an atomic update.
+    async fn add_group(&self, username: &str, group: &str) -> Result<(), AuthError> {
+        let mut groups = self.get_user(username).await?.groups;
+        if !groups.iter().any(|g| g == group) {
+            groups.push(group.to_string());
+            self.update_groups(username, groups).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove a single group from a user.
+    ///
+    /// Removing a group the user doesn't have is a no-op.
+    ///
+    /// Default implementation does a read-modify-write through `update_groups`;
+    /// implementations should override it with an atomic update.
+    async fn remove_group(&self, username: &str, group: &str) -> Result<(), AuthError> {
+        let mut groups = self.get_user(username).await?.groups;
+        let before = groups.len();
+        groups.retain(|g| g != group);
+        if groups.len() != before {
+            self.update_groups(username, groups).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Rules applied to usernames by [`UserRecord::validate_with`].
+///
+/// By default a username is 1 to 64 characters of ASCII letters, digits and
+/// `.`, `_`, `-` or `@`. This keeps out empty names, whitespace and control
+/// characters, which break downstream filters and LDAP DNs.
+///
+/// # Example
+///
+/// ```ignore
+/// let policy = UsernamePolicy::default()
+///     .with_max_length(32)
+///     .with_allowed_symbols("._-");
+/// let db = MemoryUserDb::new().with_username_policy(policy);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsernamePolicy {
+    min_length: usize,
+    max_length: usize,
+    allowed_symbols: String,
+}
+
+impl Default for UsernamePolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 1,
+            max_length: 64,
+            allowed_symbols: "._-@".to_string(),
+        }
+    }
+}
+
+impl UsernamePolicy {
+    /// Set the minimum length in characters (default: 1).
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Set the maximum length in characters (default: 64).
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Set the characters allowed besides ASCII letters and digits
+    /// (default: `._-@`).
+    pub fn with_allowed_symbols<S: Into<String>>(mut self, symbols: S) -> Self {
+        self.allowed_symbols = symbols.into();
+        self
+    }
+
+    /// Check a username against this policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::Other` describing the first rule `username` breaks.
+    pub fn check(&self, username: &str) -> Result<(), AuthError> {
+        if username.is_empty() {
+            return Err(AuthError::other("Username must not be empty"));
+        }
+
+        let length = username.chars().count();
+        if length < self.min_length || length > self.max_length {
+            return Err(AuthError::other(format!(
+                "Username must be between {} and {} characters, got {}",
+                self.min_length, self.max_length, length
+            )));
+        }
+
+        if let Some(c) = username
+            .chars()
+            .find(|c| !c.is_ascii_alphanumeric() && !self.allowed_symbols.contains(*c))
+        {
+            return Err(AuthError::other(format!(
+                "Username contains illegal character {:?}; allowed are ASCII letters, digits and '{}'",
+                c, self.allowed_symbols
+            )));
+        }
+
         Ok(())
     }
 }
@@ -143,6 +375,12 @@ pub struct UserRecord {
 
     /// Unix timestamp when user was last updated.
     pub updated_at: i64,
+
+    /// Unix timestamp when user was soft-deleted, if it was.
+    ///
+    /// Soft-deleted users are also disabled and hidden from default listings.
+    #[serde(default)]
+    pub deleted_at: Option<i64>,
 }
 
 impl UserRecord {
@@ -164,6 +402,7 @@ impl UserRecord {
             enabled: true,
             created_at: now,
             updated_at: now,
+            deleted_at: None,
         }
     }
 
@@ -179,6 +418,23 @@ impl UserRecord {
         self
     }
 
+    /// Set the creation and last-update timestamps (Unix seconds).
+    ///
+    /// `new` stamps both with the current time; use this to preserve the
+    /// original dates when importing users or to build records with stable
+    /// values in tests.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let user = UserRecord::new("alice", &hash).with_timestamps(1_577_836_800, 1_704_067_200);
+    /// ```
+    pub fn with_timestamps(mut self, created_at: i64, updated_at: i64) -> Self {
+        self.created_at = created_at;
+        self.updated_at = updated_at;
+        self
+    }
+
     /// Set whether the user is enabled.
     pub fn with_enabled(mut self, enabled: bool) -> Self {
         self.enabled = enabled;
@@ -192,14 +448,37 @@ impl UserRecord {
     }
 
     /// Check if the user is enabled.
+    ///
+    /// False for soft-deleted users even while their `enabled` flag is set;
+    /// the flag itself keeps an admin's disable across delete and restore.
     pub fn is_enabled(&self) -> bool {
-        self.enabled
+        self.enabled && !self.is_deleted()
+    }
+
+    /// Check if the user has been soft-deleted.
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
     }
 
     /// Check if user has a specific group.
     pub fn has_group(&self, group: &str) -> bool {
         self.groups.iter().any(|g| g == group)
     }
+
+    /// Validate the username against the default [`UsernamePolicy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::Other` if the username is empty, too long or
+    /// contains illegal characters.
+    pub fn validate(&self) -> Result<(), AuthError> {
+        self.validate_with(&UsernamePolicy::default())
+    }
+
+    /// Validate the username against `policy`.
+    pub fn validate_with(&self, policy: &UsernamePolicy) -> Result<(), AuthError> {
+        policy.check(&self.username)
+    }
 }
 
 #[cfg(test)]
@@ -231,6 +510,38 @@ mod tests {
         assert!(!user.is_enabled());
     }
 
+    #[test]
+    fn test_user_record_not_deleted_by_default() {
+        let user = UserRecord::new("alice", "hash");
+        assert!(!user.is_deleted());
+    }
+
+    #[test]
+    fn test_user_record_validate() {
+        assert!(UserRecord::new("alice.smith@corp.com", "hash").validate().is_ok());
+
+        for bad in ["", &"a".repeat(65), "alice smith", "alice\n", "bob\u{0}", "ünïcode"] {
+            let result = UserRecord::new(bad, "hash").validate();
+            assert!(matches!(result, Err(AuthError::Other(_))), "accepted {:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_username_policy_configurable() {
+        let policy = UsernamePolicy::default()
+            .with_min_length(3)
+            .with_max_length(8)
+            .with_allowed_symbols("_");
+
+        assert!(UserRecord::new("bob_1", "hash").validate_with(&policy).is_ok());
+        assert!(UserRecord::new("bo", "hash").validate_with(&policy).is_err());
+        assert!(UserRecord::new("bob_smith", "hash").validate_with(&policy).is_err());
+        assert!(UserRecord::new("bob.1", "hash").validate_with(&policy).is_err());
+
+        let err = UserRecord::new("bob.1", "hash").validate_with(&policy).unwrap_err();
+        assert!(err.to_string().contains("'.'"));
+    }
+
     #[test]
     fn test_user_record_serialization() {
         let user = UserRecord::new("alice", "hash")
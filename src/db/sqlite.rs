@@ -4,14 +4,212 @@
 //! It's included when the `sqlite` feature is enabled.
 
 use async_trait::async_trait;
-use sqlx::sqlite::{SqlitePool, SqlitePoolOptions, SqliteConnectOptions};
+use futures_util::{Stream, StreamExt};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use sqlx::migrate::{Migration, MigrationType, Migrator};
 use sqlx::ConnectOptions;
+use std::borrow::Cow;
+use std::future::Future;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::db::{UserDatabase, UserRecord};
+use crate::audit::{AuditEvent, AuditFilter, AuditLog};
+use crate::db::{UserDatabase, UserRecord, UsernamePolicy};
 use crate::error::AuthError;
 
+/// Versioned schema migrations embedded from the crate's `migrations/` directory.
+///
+/// Exposed so the schema can be applied to a pool created elsewhere, or
+/// inspected with the `sqlx migrate` CLI.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Versions reserved for application migrations registered with
+/// [`SqliteDbOptions::with_migration`].
+///
+/// Built-in migrations are versioned by 14-digit UTC timestamps
+/// (`YYYYMMDDhhmmss`), so they never fall in this range, including ones
+/// added by future releases.
+pub const APP_MIGRATION_VERSIONS: std::ops::Range<i64> = 1..10_000_000_000_000;
+
+/// Columns selected for a `UserRecord`, in `USER_COLUMNS` order.
+type UserRow = (String, String, String, bool, i64, i64, Option<i64>);
+
+const USER_COLUMNS: &str = "username, password_hash, groups, enabled, created_at, updated_at, deleted_at";
+
+/// Active users in a group, via the indexed `user_groups` table.
+const USERS_IN_GROUP_QUERY: &str = "SELECT username, password_hash, groups, enabled, created_at, updated_at, deleted_at \
+     FROM user_groups JOIN users ON users.id = user_groups.user_id \
+     WHERE user_groups.group_name = ? AND users.deleted_at IS NULL ORDER BY username";
+
+/// Number of active users in a group, via the indexed `user_groups` table.
+const COUNT_IN_GROUP_QUERY: &str = "SELECT COUNT(*) \
+     FROM user_groups JOIN users ON users.id = user_groups.user_id \
+     WHERE user_groups.group_name = ? AND users.deleted_at IS NULL";
+
+/// How many times a write is retried after SQLite reports the database busy.
+const BUSY_RETRY_ATTEMPTS: u32 = 5;
+
+/// Delay before the first retry; doubles on each subsequent attempt.
+const BUSY_RETRY_BASE_DELAY: Duration = Duration::from_millis(25);
+
+/// Check whether an error is transient lock contention (`SQLITE_BUSY`/`SQLITE_LOCKED`).
+fn is_busy_error(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(db_err) => {
+            // Extended result codes keep the primary code in the low byte
+            let primary_code = db_err
+                .code()
+                .and_then(|code| code.parse::<i32>().ok())
+                .map(|code| code & 0xff);
+
+            matches!(primary_code, Some(5) | Some(6))
+                || db_err.message().contains("database is locked")
+        }
+        _ => false,
+    }
+}
+
+/// Run a write, retrying with exponential backoff while the database is busy.
+///
+/// This complements `busy_timeout`: contention that outlasts the timeout is
+/// retried a bounded number of times before the error is surfaced.
+async fn retry_on_busy<T, F, Fut>(mut operation: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Err(e) if attempt < BUSY_RETRY_ATTEMPTS && is_busy_error(&e) => {
+                let delay = BUSY_RETRY_BASE_DELAY * 2u32.pow(attempt);
+                tracing::debug!("SQLite busy, retrying write in {:?}", delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn user_from_row(row: UserRow) -> UserRecord {
+    let groups: Vec<String> = serde_json::from_str(&row.2)
+        .unwrap_or_default();
+
+    UserRecord {
+        username: row.0,
+        password_hash: row.1,
+        groups,
+        enabled: row.3,
+        created_at: row.4,
+        updated_at: row.5,
+        deleted_at: row.6,
+    }
+}
+
+/// Connection settings for [`SqliteUserDb`].
+///
+/// # Example
+///
+/// ```ignore
+/// use poem_auth::db::sqlite::{SqliteDbOptions, SqliteUserDb};
+/// use std::time::Duration;
+///
+/// let options = SqliteDbOptions::default()
+///     .with_max_connections(8)
+///     .with_busy_timeout(Duration::from_secs(10))
+///     .with_wal(true);
+///
+/// let db = SqliteUserDb::with_options("data/users.db", options).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct SqliteDbOptions {
+    /// Maximum number of pooled connections
+    pub max_connections: u32,
+
+    /// How long a connection waits on a locked database before failing
+    pub busy_timeout: Duration,
+
+    /// Use write-ahead logging, which lets readers proceed during writes
+    pub wal: bool,
+
+    /// Rules `create_user` enforces on new usernames
+    pub username_policy: UsernamePolicy,
+
+    /// Application migrations run after the built-in ones
+    pub migrations: Vec<Migration>,
+}
+
+impl SqliteDbOptions {
+    /// Set the maximum number of pooled connections.
+    pub fn with_max_connections(mut self, max_connections: u32) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Set the busy timeout.
+    pub fn with_busy_timeout(mut self, busy_timeout: Duration) -> Self {
+        self.busy_timeout = busy_timeout;
+        self
+    }
+
+    /// Enable or disable WAL journal mode.
+    pub fn with_wal(mut self, wal: bool) -> Self {
+        self.wal = wal;
+        self
+    }
+
+    /// Set the policy new usernames must satisfy.
+    pub fn with_username_policy(mut self, username_policy: UsernamePolicy) -> Self {
+        self.username_policy = username_policy;
+        self
+    }
+
+    /// Register an application migration, e.g. to create custom tables.
+    ///
+    /// Registered migrations run after the built-in [`MIGRATOR`] ones, in
+    /// registration order, and are recorded in the same `_sqlx_migrations`
+    /// table, so each `version` is applied once per database. Versions must
+    /// be unique and within [`APP_MIGRATION_VERSIONS`], which built-in
+    /// migrations never use (e.g. number them 1, 2, 3...). Once applied, a
+    /// migration's SQL must not change. Code that opens the same database
+    /// without registering it (such as `poem_auth_cli`) leaves it alone.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let options = SqliteDbOptions::default().with_migration(
+    ///     1,
+    ///     "create api_keys",
+    ///     "CREATE TABLE api_keys (key TEXT PRIMARY KEY, username TEXT NOT NULL)",
+    /// );
+    /// let db = SqliteUserDb::with_options("data/users.db", options).await?;
+    /// sqlx::query("INSERT INTO api_keys VALUES (?, ?)").bind(key).bind("alice").execute(db.pool()).await?;
+    /// ```
+    pub fn with_migration<D, S>(mut self, version: i64, description: D, sql: S) -> Self
+    where
+        D: Into<Cow<'static, str>>,
+        S: Into<Cow<'static, str>>,
+    {
+        self.migrations
+            .push(Migration::new(version, description.into(), MigrationType::Simple, sql.into()));
+        self
+    }
+}
+
+impl Default for SqliteDbOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: 5,
+            busy_timeout: Duration::from_secs(5),
+            wal: true,
+            username_policy: UsernamePolicy::default(),
+            migrations: Vec::new(),
+        }
+    }
+}
+
 /// SQLite-backed user database.
 ///
 /// Provides a complete implementation of the `UserDatabase` trait using SQLite.
@@ -28,12 +226,16 @@ use crate::error::AuthError;
 #[derive(Debug, Clone)]
 pub struct SqliteUserDb {
     pool: Arc<SqlitePool>,
+    username_policy: UsernamePolicy,
+    /// Application migrations from `SqliteDbOptions::with_migration`
+    migrations: Arc<[Migration]>,
 }
 
 impl SqliteUserDb {
     /// Create a new SQLite database.
     ///
     /// Creates the database file if it doesn't exist and runs migrations.
+    /// Uses [`SqliteDbOptions::default`] for connection settings.
     ///
     /// # Arguments
     ///
@@ -45,15 +247,42 @@ impl SqliteUserDb {
     /// let db = SqliteUserDb::new("data/users.db").await?;
     /// ```
     pub async fn new(path: &str) -> Result<Self, AuthError> {
+        Self::with_options(path, SqliteDbOptions::default()).await
+    }
+
+    /// Create a new SQLite database with custom connection settings.
+    ///
+    /// Creates the database file if it doesn't exist and runs migrations.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the SQLite database file
+    /// * `options` - Pool size, busy timeout and journal mode
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let options = SqliteDbOptions::default().with_max_connections(1);
+    /// let db = SqliteUserDb::with_options("data/users.db", options).await?;
+    /// ```
+    pub async fn with_options(path: &str, options: SqliteDbOptions) -> Result<Self, AuthError> {
+        let journal_mode = if options.wal {
+            SqliteJournalMode::Wal
+        } else {
+            SqliteJournalMode::Delete
+        };
+
         // Create connection options with minimal verbosity
         let connect_options = SqliteConnectOptions::from_str(path)
             .map_err(|e| AuthError::database(format!("Invalid database path: {}", e)))?
             .create_if_missing(true)
+            .busy_timeout(options.busy_timeout)
+            .journal_mode(journal_mode)
             .log_statements(tracing::log::LevelFilter::Debug);
 
         // Create connection pool
         let pool = SqlitePoolOptions::new()
-            .max_connections(5)
+            .max_connections(options.max_connections)
             .connect_with(connect_options)
             .await
             .map_err(|e| AuthError::database(format!("Failed to connect to database: {}", e)))?;
@@ -61,6 +290,8 @@ impl SqliteUserDb {
         // Create database
         let db = Self {
             pool: Arc::new(pool),
+            username_policy: options.username_policy,
+            migrations: options.migrations.into(),
         };
 
         // Run migrations
@@ -69,54 +300,111 @@ impl SqliteUserDb {
         Ok(db)
     }
 
+    /// Create an in-memory SQLite database.
+    ///
+    /// Uses a single long-lived connection so the database persists across
+    /// queries for the lifetime of this `SqliteUserDb`. Nothing is written to
+    /// disk, which makes it ideal for tests.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let db = SqliteUserDb::in_memory().await?;
+    /// db.create_user(UserRecord::new("alice", &hash)).await?;
+    /// ```
+    pub async fn in_memory() -> Result<Self, AuthError> {
+        let connect_options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .map_err(|e| AuthError::database(format!("Invalid database path: {}", e)))?
+            .log_statements(tracing::log::LevelFilter::Debug);
+
+        // Each connection to `sqlite::memory:` is a separate database,
+        // so keep exactly one connection open forever
+        let pool = SqlitePoolOptions::new()
+            .min_connections(1)
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(connect_options)
+            .await
+            .map_err(|e| AuthError::database(format!("Failed to connect to database: {}", e)))?;
+
+        let db = Self {
+            pool: Arc::new(pool),
+            username_policy: UsernamePolicy::default(),
+            migrations: Arc::new([]),
+        };
+
+        db.migrate().await?;
+
+        Ok(db)
+    }
+
     /// Run database migrations to create schema.
+    ///
+    /// Applies the versioned SQL files in `migrations/` (see [`MIGRATOR`]),
+    /// then the application migrations registered with
+    /// [`SqliteDbOptions::with_migration`]; migrations already recorded in
+    /// `_sqlx_migrations` are skipped, so this is safe to call repeatedly.
+    /// Recorded migrations that aren't registered here are ignored, so a
+    /// database with application migrations can still be opened with
+    /// [`SqliteUserDb::new`].
+    ///
+    /// Ignoring them (sqlx's `ignore_missing`) has a cost: a database that a
+    /// newer release of this crate has already migrated further opens
+    /// without an error in an older one, which then runs against a schema it
+    /// doesn't know. Upgrade every process sharing a database together.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::ConfigError` if an application migration reuses a
+    /// version or is outside [`APP_MIGRATION_VERSIONS`], and
+    /// `AuthError::DatabaseError` if a migration fails.
     pub async fn migrate(&self) -> Result<(), AuthError> {
-        // Create users table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS users (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                username TEXT NOT NULL UNIQUE,
-                password_hash TEXT NOT NULL,
-                groups TEXT NOT NULL DEFAULT '[]',
-                enabled BOOLEAN NOT NULL DEFAULT 1,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL
-            )
-            "#,
-        )
-        .execute(self.pool.as_ref())
-        .await
-        .map_err(|e| AuthError::database(format!("Failed to create users table: {}", e)))?;
+        self.upgrade_legacy_schema().await?;
 
-        // Create audit_log table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS audit_log (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                timestamp INTEGER NOT NULL,
-                event_type TEXT NOT NULL,
-                username TEXT,
-                provider TEXT NOT NULL,
-                ip_address TEXT,
-                details TEXT
-            )
-            "#,
-        )
-        .execute(self.pool.as_ref())
+        let mut migrations = MIGRATOR.migrations.to_vec();
+        for migration in self.migrations.iter() {
+            if !APP_MIGRATION_VERSIONS.contains(&migration.version) {
+                return Err(AuthError::config(format!(
+                    "Migration version {} is outside the application range {:?}",
+                    migration.version, APP_MIGRATION_VERSIONS
+                )));
+            }
+            if migrations.iter().any(|existing| existing.version == migration.version) {
+                return Err(AuthError::config(format!(
+                    "Migration version {} is already in use",
+                    migration.version
+                )));
+            }
+            migrations.push(migration.clone());
+        }
+
+        Migrator {
+            migrations: Cow::Owned(migrations),
+            ignore_missing: true,
+            ..Migrator::DEFAULT
+        }
+        .run(self.pool.as_ref())
         .await
-        .map_err(|e| AuthError::database(format!("Failed to create audit_log table: {}", e)))?;
+        .map_err(|e| AuthError::database(format!("Failed to run migrations: {}", e)))
+    }
 
-        // Create indexes
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_users_username ON users(username)")
-            .execute(self.pool.as_ref())
+    /// Add the soft-delete column to `users` tables created before it existed.
+    ///
+    /// Such databases predate versioned migrations, and the initial migration
+    /// only creates missing tables, so the column has to be added here first.
+    async fn upgrade_legacy_schema(&self) -> Result<(), AuthError> {
+        let columns = sqlx::query_as::<_, (String,)>("SELECT name FROM pragma_table_info('users')")
+            .fetch_all(self.pool.as_ref())
             .await
-            .map_err(|e| AuthError::database(format!("Failed to create index: {}", e)))?;
+            .map_err(|e| AuthError::database(format!("Failed to inspect users table: {}", e)))?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log(timestamp)")
-            .execute(self.pool.as_ref())
-            .await
-            .map_err(|e| AuthError::database(format!("Failed to create index: {}", e)))?;
+        if !columns.is_empty() && !columns.iter().any(|(name,)| name == "deleted_at") {
+            sqlx::query("ALTER TABLE users ADD COLUMN deleted_at INTEGER")
+                .execute(self.pool.as_ref())
+                .await
+                .map_err(|e| AuthError::database(format!("Failed to add deleted_at column: {}", e)))?;
+        }
 
         Ok(())
     }
@@ -125,13 +413,55 @@ impl SqliteUserDb {
     pub fn pool(&self) -> &SqlitePool {
         &self.pool
     }
+
+    /// Stream audit events matching `filter`, oldest first.
+    ///
+    /// Rows are read through a cursor as the stream is polled instead of
+    /// being collected up front, so exporting millions of events doesn't
+    /// load them into memory. Pair with
+    /// [`write_ndjson`](crate::audit::write_ndjson) for SIEM ingestion.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let filter = AuditFilter::new().with_since(last_export);
+    /// let written = write_ndjson(db.export_events(&filter), &mut stdout).await?;
+    /// ```
+    pub fn export_events(
+        &self,
+        filter: &AuditFilter,
+    ) -> impl Stream<Item = Result<AuditEvent, AuthError>> + '_ {
+        sqlx::query_as::<_, (i64, String, Option<String>, String, Option<String>, Option<String>)>(
+            "SELECT timestamp, event_type, username, provider, ip_address, details FROM audit_log \
+             WHERE (?1 IS NULL OR username = ?1) AND (?2 IS NULL OR event_type = ?2) \
+             AND (?3 IS NULL OR timestamp >= ?3) AND (?4 IS NULL OR timestamp < ?4) \
+             ORDER BY id",
+        )
+        .bind(filter.username.clone())
+        .bind(filter.event_type.clone())
+        .bind(filter.since)
+        .bind(filter.until)
+        .fetch(self.pool.as_ref())
+        .map(|row| {
+            let (timestamp, event_type, username, provider, ip_address, details) =
+                row.map_err(|e| AuthError::database(format!("Failed to read audit events: {}", e)))?;
+            Ok(AuditEvent {
+                timestamp,
+                event_type,
+                username,
+                provider,
+                ip_address: ip_address.and_then(|ip| ip.parse().ok()),
+                details,
+            })
+        })
+    }
 }
 
 #[async_trait]
 impl UserDatabase for SqliteUserDb {
     async fn get_user(&self, username: &str) -> Result<UserRecord, AuthError> {
-        let user = sqlx::query_as::<_, (String, String, String, bool, i64, i64)>(
-            "SELECT username, password_hash, groups, enabled, created_at, updated_at FROM users WHERE username = ?"
+        let row = sqlx::query_as::<_, UserRow>(
+            &format!("SELECT {} FROM users WHERE username = ?", USER_COLUMNS)
         )
         .bind(username)
         .fetch_optional(self.pool.as_ref())
@@ -139,33 +469,28 @@ impl UserDatabase for SqliteUserDb {
         .map_err(|e| AuthError::database(e.to_string()))?
         .ok_or(AuthError::UserNotFound)?;
 
-        let groups: Vec<String> = serde_json::from_str(&user.2)
-            .unwrap_or_default();
-
-        Ok(UserRecord {
-            username: user.0,
-            password_hash: user.1,
-            groups,
-            enabled: user.3,
-            created_at: user.4,
-            updated_at: user.5,
-        })
+        Ok(user_from_row(row))
     }
 
     async fn create_user(&self, user: UserRecord) -> Result<(), AuthError> {
+        user.validate_with(&self.username_policy)?;
+
         let groups_json = serde_json::to_string(&user.groups)
             .map_err(|e| AuthError::database(format!("Failed to serialize groups: {}", e)))?;
 
-        sqlx::query(
-            "INSERT INTO users (username, password_hash, groups, enabled, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)"
-        )
-        .bind(&user.username)
-        .bind(&user.password_hash)
-        .bind(&groups_json)
-        .bind(user.enabled)
-        .bind(user.created_at)
-        .bind(user.updated_at)
-        .execute(self.pool.as_ref())
+        retry_on_busy(|| {
+            sqlx::query(
+                "INSERT INTO users (username, password_hash, groups, enabled, created_at, updated_at, deleted_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&user.username)
+            .bind(&user.password_hash)
+            .bind(&groups_json)
+            .bind(user.enabled)
+            .bind(user.created_at)
+            .bind(user.updated_at)
+            .bind(user.deleted_at)
+            .execute(self.pool.as_ref())
+        })
         .await
         .map_err(|e| {
             if e.to_string().contains("UNIQUE constraint failed") {
@@ -181,13 +506,42 @@ impl UserDatabase for SqliteUserDb {
     async fn update_password(&self, username: &str, hash: String) -> Result<(), AuthError> {
         let now = chrono::Utc::now().timestamp();
 
-        let result = sqlx::query("UPDATE users SET password_hash = ?, updated_at = ? WHERE username = ?")
-            .bind(&hash)
+        let result = retry_on_busy(|| {
+            sqlx::query("UPDATE users SET password_hash = ?, updated_at = ? WHERE username = ?")
+                .bind(&hash)
+                .bind(now)
+                .bind(username)
+                .execute(self.pool.as_ref())
+        })
+        .await
+        .map_err(|e| AuthError::database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AuthError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn update_user(&self, user: UserRecord) -> Result<(), AuthError> {
+        let now = chrono::Utc::now().timestamp();
+        let groups_json = serde_json::to_string(&user.groups)
+            .map_err(|e| AuthError::database(format!("Failed to serialize groups: {}", e)))?;
+
+        let result = retry_on_busy(|| {
+            sqlx::query(
+                "UPDATE users SET password_hash = ?, groups = ?, enabled = ?, updated_at = ?, deleted_at = ? WHERE username = ?"
+            )
+            .bind(&user.password_hash)
+            .bind(&groups_json)
+            .bind(user.enabled)
             .bind(now)
-            .bind(username)
+            .bind(user.deleted_at)
+            .bind(&user.username)
             .execute(self.pool.as_ref())
-            .await
-            .map_err(|e| AuthError::database(e.to_string()))?;
+        })
+        .await
+        .map_err(|e| AuthError::database(e.to_string()))?;
 
         if result.rows_affected() == 0 {
             return Err(AuthError::UserNotFound);
@@ -196,37 +550,79 @@ impl UserDatabase for SqliteUserDb {
         Ok(())
     }
 
+    async fn count_users(&self) -> Result<u64, AuthError> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL")
+            .fetch_one(self.pool.as_ref())
+            .await
+            .map_err(|e| AuthError::database(e.to_string()))?;
+
+        Ok(count as u64)
+    }
+
+    async fn list_users_in_group(&self, group: &str) -> Result<Vec<UserRecord>, AuthError> {
+        let rows = sqlx::query_as::<_, UserRow>(USERS_IN_GROUP_QUERY)
+            .bind(group)
+            .fetch_all(self.pool.as_ref())
+            .await
+            .map_err(|e| AuthError::database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(user_from_row).collect())
+    }
+
+    async fn count_by_group(&self, group: &str) -> Result<u64, AuthError> {
+        let (count,): (i64,) = sqlx::query_as(COUNT_IN_GROUP_QUERY)
+            .bind(group)
+            .fetch_one(self.pool.as_ref())
+            .await
+            .map_err(|e| AuthError::database(e.to_string()))?;
+
+        Ok(count as u64)
+    }
+
     async fn list_users(&self) -> Result<Vec<UserRecord>, AuthError> {
-        let rows = sqlx::query_as::<_, (String, String, String, bool, i64, i64)>(
-            "SELECT username, password_hash, groups, enabled, created_at, updated_at FROM users ORDER BY username"
+        self.list_users_filtered(false).await
+    }
+
+    async fn list_users_filtered(&self, include_deleted: bool) -> Result<Vec<UserRecord>, AuthError> {
+        let filter = if include_deleted { "" } else { "WHERE deleted_at IS NULL " };
+        let rows = sqlx::query_as::<_, UserRow>(
+            &format!("SELECT {} FROM users {}ORDER BY username", USER_COLUMNS, filter)
         )
         .fetch_all(self.pool.as_ref())
         .await
         .map_err(|e| AuthError::database(e.to_string()))?;
 
-        let users = rows.into_iter().map(|row| {
-            let groups: Vec<String> = serde_json::from_str(&row.2)
-                .unwrap_or_default();
-
-            UserRecord {
-                username: row.0,
-                password_hash: row.1,
-                groups,
-                enabled: row.3,
-                created_at: row.4,
-                updated_at: row.5,
-            }
-        }).collect();
-
-        Ok(users)
+        Ok(rows.into_iter().map(user_from_row).collect())
     }
 
     async fn delete_user(&self, username: &str) -> Result<(), AuthError> {
-        let result = sqlx::query("DELETE FROM users WHERE username = ?")
-            .bind(username)
-            .execute(self.pool.as_ref())
-            .await
-            .map_err(|e| AuthError::database(e.to_string()))?;
+        let result = retry_on_busy(|| {
+            sqlx::query("DELETE FROM users WHERE username = ?")
+                .bind(username)
+                .execute(self.pool.as_ref())
+        })
+        .await
+        .map_err(|e| AuthError::database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AuthError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn soft_delete_user(&self, username: &str) -> Result<(), AuthError> {
+        let now = chrono::Utc::now().timestamp();
+
+        let result = retry_on_busy(|| {
+            sqlx::query("UPDATE users SET deleted_at = ?, updated_at = ? WHERE username = ?")
+                .bind(now)
+                .bind(now)
+                .bind(username)
+                .execute(self.pool.as_ref())
+        })
+        .await
+        .map_err(|e| AuthError::database(e.to_string()))?;
 
         if result.rows_affected() == 0 {
             return Err(AuthError::UserNotFound);
@@ -235,20 +631,101 @@ impl UserDatabase for SqliteUserDb {
         Ok(())
     }
 
+    async fn restore_user(&self, username: &str) -> Result<(), AuthError> {
+        let now = chrono::Utc::now().timestamp();
+
+        let result = retry_on_busy(|| {
+            sqlx::query("UPDATE users SET deleted_at = NULL, updated_at = ? WHERE username = ? AND deleted_at IS NOT NULL")
+                .bind(now)
+                .bind(username)
+                .execute(self.pool.as_ref())
+        })
+        .await
+        .map_err(|e| AuthError::database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AuthError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn ping(&self) -> Result<(), AuthError> {
+        sqlx::query("SELECT 1")
+            .execute(self.pool.as_ref())
+            .await
+            .map_err(|e| AuthError::database(e.to_string()))?;
+
+        Ok(())
+    }
+
     async fn update_groups(&self, username: &str, groups: Vec<String>) -> Result<(), AuthError> {
         let now = chrono::Utc::now().timestamp();
         let groups_json = serde_json::to_string(&groups)
             .map_err(|e| AuthError::database(format!("Failed to serialize groups: {}", e)))?;
 
-        let result = sqlx::query("UPDATE users SET groups = ?, updated_at = ? WHERE username = ?")
-            .bind(&groups_json)
+        let result = retry_on_busy(|| {
+            sqlx::query("UPDATE users SET groups = ?, updated_at = ? WHERE username = ?")
+                .bind(&groups_json)
+                .bind(now)
+                .bind(username)
+                .execute(self.pool.as_ref())
+        })
+        .await
+        .map_err(|e| AuthError::database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AuthError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn add_group(&self, username: &str, group: &str) -> Result<(), AuthError> {
+        let now = chrono::Utc::now().timestamp();
+
+        // Append in a single statement so concurrent updates can't be lost
+        let result = retry_on_busy(|| {
+            sqlx::query(
+                "UPDATE users SET groups = json_insert(groups, '$[#]', ?), updated_at = ? \
+                 WHERE username = ? AND NOT EXISTS (SELECT 1 FROM json_each(users.groups) WHERE value = ?)"
+            )
+            .bind(group)
             .bind(now)
             .bind(username)
+            .bind(group)
             .execute(self.pool.as_ref())
-            .await
-            .map_err(|e| AuthError::database(e.to_string()))?;
+        })
+        .await
+        .map_err(|e| AuthError::database(e.to_string()))?;
 
-        if result.rows_affected() == 0 {
+        // Nothing changed: either the user already has the group or doesn't exist
+        if result.rows_affected() == 0 && !self.user_exists(username).await? {
+            return Err(AuthError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn remove_group(&self, username: &str, group: &str) -> Result<(), AuthError> {
+        let now = chrono::Utc::now().timestamp();
+
+        let result = retry_on_busy(|| {
+            sqlx::query(
+                "UPDATE users SET groups = (SELECT json_group_array(value) FROM json_each(users.groups) WHERE value != ?), \
+                 updated_at = ? \
+                 WHERE username = ? AND EXISTS (SELECT 1 FROM json_each(users.groups) WHERE value = ?)"
+            )
+            .bind(group)
+            .bind(now)
+            .bind(username)
+            .bind(group)
+            .execute(self.pool.as_ref())
+        })
+        .await
+        .map_err(|e| AuthError::database(e.to_string()))?;
+
+        if result.rows_affected() == 0 && !self.user_exists(username).await? {
             return Err(AuthError::UserNotFound);
         }
 
@@ -256,6 +733,31 @@ impl UserDatabase for SqliteUserDb {
     }
 }
 
+/// Audit events are appended to the `audit_log` table.
+#[async_trait]
+impl AuditLog for SqliteUserDb {
+    async fn record(&self, event: AuditEvent) -> Result<(), AuthError> {
+        let ip_address = event.ip_address.map(|ip| ip.to_string());
+
+        retry_on_busy(|| {
+            sqlx::query(
+                "INSERT INTO audit_log (timestamp, event_type, username, provider, ip_address, details) VALUES (?, ?, ?, ?, ?, ?)"
+            )
+            .bind(event.timestamp)
+            .bind(&event.event_type)
+            .bind(&event.username)
+            .bind(&event.provider)
+            .bind(&ip_address)
+            .bind(&event.details)
+            .execute(self.pool.as_ref())
+        })
+        .await
+        .map_err(|e| AuthError::database(format!("Failed to record audit event: {}", e)))?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -289,6 +791,312 @@ mod tests {
         assert_eq!(fetched.groups, vec!["admins", "users"]);
     }
 
+    #[tokio::test]
+    async fn test_application_migrations_create_tables() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("custom.db");
+        let options = || {
+            SqliteDbOptions::default().with_migration(
+                1,
+                "create api_keys",
+                "CREATE TABLE api_keys (key TEXT PRIMARY KEY, username TEXT NOT NULL)",
+            )
+        };
+
+        let db = SqliteUserDb::with_options(db_path.to_str().unwrap(), options()).await.unwrap();
+        sqlx::query("INSERT INTO api_keys (key, username) VALUES ('k1', 'alice')")
+            .execute(db.pool())
+            .await
+            .unwrap();
+        drop(db);
+
+        // Reopening doesn't reapply the migration
+        let db = SqliteUserDb::with_options(db_path.to_str().unwrap(), options()).await.unwrap();
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM api_keys")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        drop(db);
+
+        // Opening without the application migration (as the CLI does) still works
+        let db = SqliteUserDb::new(db_path.to_str().unwrap()).await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash")).await.unwrap();
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM api_keys")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        drop(db);
+
+        let built_in = MIGRATOR.iter().next().unwrap().version;
+        assert!(MIGRATOR.iter().all(|m| !APP_MIGRATION_VERSIONS.contains(&m.version)));
+        let clash = SqliteDbOptions::default().with_migration(built_in, "clash", "SELECT 1");
+        let result = SqliteUserDb::with_options(db_path.to_str().unwrap(), clash).await;
+        assert!(matches!(result, Err(AuthError::ConfigError(_))));
+
+        let duplicate = options().with_migration(1, "again", "SELECT 1");
+        let result = SqliteUserDb::with_options(db_path.to_str().unwrap(), duplicate).await;
+        assert!(matches!(result, Err(AuthError::ConfigError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fixed_timestamps_round_trip() {
+        let db = test_db().await.unwrap();
+        let user = UserRecord::new("alice", "hash123").with_timestamps(1_577_836_800, 1_704_067_200);
+
+        db.create_user(user).await.unwrap();
+
+        let fetched = db.get_user("alice").await.unwrap();
+        assert_eq!(fetched.created_at, 1_577_836_800);
+        assert_eq!(fetched.updated_at, 1_704_067_200);
+    }
+
+    #[tokio::test]
+    async fn test_with_options_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("options.db");
+        let options = SqliteDbOptions::default()
+            .with_max_connections(2)
+            .with_busy_timeout(Duration::from_secs(1))
+            .with_wal(false);
+
+        let db = SqliteUserDb::with_options(db_path.to_str().unwrap(), options).await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash")).await.unwrap();
+
+        let fetched = db.get_user("alice").await.unwrap();
+        assert_eq!(fetched.username, "alice");
+
+        let (mode,) = sqlx::query_as::<_, (String,)>("PRAGMA journal_mode")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(mode.to_lowercase(), "delete");
+    }
+
+    #[tokio::test]
+    async fn test_create_user_validates_username() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        let result = db.create_user(UserRecord::new("bad\tname", "hash")).await;
+        assert!(matches!(result, Err(AuthError::Other(_))));
+        assert_eq!(db.count_users().await.unwrap(), 0);
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("policy.db");
+        let options = SqliteDbOptions::default()
+            .with_username_policy(UsernamePolicy::default().with_allowed_symbols(" "));
+        let db = SqliteUserDb::with_options(db_path.to_str().unwrap(), options).await.unwrap();
+        db.create_user(UserRecord::new("alice smith", "hash")).await.unwrap();
+        assert!(db.create_user(UserRecord::new("bob@corp.com", "hash")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_events_streams_matching_rows() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        for i in 0..250 {
+            let event_type = if i % 5 == 0 { "master_auth_failure" } else { "login_success" };
+            let mut event = AuditEvent::new(event_type, "local")
+                .with_username(format!("user{}", i % 10))
+                .with_ip_address(Some("203.0.113.7".parse().unwrap()));
+            event.timestamp = 1_000 + i;
+            db.record(event).await.unwrap();
+        }
+
+        assert_eq!(db.export_events(&AuditFilter::new()).count().await, 250);
+
+        let failures = AuditFilter::new().with_event_type("master_auth_failure");
+        let events: Vec<_> = db.export_events(&failures).collect().await;
+        assert_eq!(events.len(), 50);
+        let first = events[0].as_ref().unwrap();
+        assert_eq!(first.timestamp, 1_000);
+        assert_eq!(first.ip_address, Some("203.0.113.7".parse().unwrap()));
+
+        let window = AuditFilter::new().with_username("user3").with_since(1_100).with_until(1_200);
+        assert_eq!(db.export_events(&window).count().await, 10);
+
+        let mut ndjson = Vec::new();
+        let written = crate::audit::write_ndjson(db.export_events(&failures), &mut ndjson)
+            .await
+            .unwrap();
+        assert_eq!(written, 50);
+        let lines: Vec<&str> = std::str::from_utf8(&ndjson).unwrap().lines().collect();
+        assert_eq!(lines.len(), 50);
+        let parsed: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(parsed["event_type"], "master_auth_failure");
+        assert_eq!(parsed["username"], "user5");
+        assert_eq!(parsed["timestamp"], 1_005);
+    }
+
+    #[tokio::test]
+    async fn test_record_audit_event() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        let event = AuditEvent::new("master_auth_failure", "master")
+            .with_username("admin")
+            .with_ip_address(Some("203.0.113.7".parse().unwrap()));
+        db.record(event).await.unwrap();
+
+        let row = sqlx::query_as::<_, (String, Option<String>, String, Option<String>)>(
+            "SELECT event_type, username, provider, ip_address FROM audit_log",
+        )
+        .fetch_one(db.pool())
+        .await
+        .unwrap();
+        assert_eq!(
+            row,
+            (
+                "master_auth_failure".to_string(),
+                Some("admin".to_string()),
+                "master".to_string(),
+                Some("203.0.113.7".to_string())
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_default_options_use_wal() {
+        let db = test_db().await.unwrap();
+
+        let (mode,) = sqlx::query_as::<_, (String,)>("PRAGMA journal_mode")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(mode.to_lowercase(), "wal");
+    }
+
+    #[tokio::test]
+    async fn test_update_user_and_count() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash")).await.unwrap();
+        db.create_user(UserRecord::new("bob", "hash")).await.unwrap();
+        assert_eq!(db.count_users().await.unwrap(), 2);
+
+        let user = db.get_user("alice").await.unwrap()
+            .with_groups(vec!["admins"])
+            .disable();
+        db.update_user(user).await.unwrap();
+
+        let stored = db.get_user("alice").await.unwrap();
+        assert_eq!(stored.groups, vec!["admins"]);
+        assert!(!stored.enabled);
+
+        db.soft_delete_user("bob").await.unwrap();
+        assert_eq!(db.count_users().await.unwrap(), 1);
+
+        assert!(matches!(
+            db.update_user(UserRecord::new("nobody", "hash")).await,
+            Err(AuthError::UserNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_add_group() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash").with_groups(vec!["users"]))
+            .await
+            .unwrap();
+
+        db.add_group("alice", "admins").await.unwrap();
+        assert_eq!(db.get_user("alice").await.unwrap().groups, vec!["users", "admins"]);
+
+        // Adding an existing group is idempotent
+        db.add_group("alice", "admins").await.unwrap();
+        assert_eq!(db.get_user("alice").await.unwrap().groups, vec!["users", "admins"]);
+
+        assert!(matches!(
+            db.add_group("nobody", "admins").await,
+            Err(AuthError::UserNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_remove_group() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash").with_groups(vec!["users", "admins"]))
+            .await
+            .unwrap();
+
+        db.remove_group("alice", "admins").await.unwrap();
+        assert_eq!(db.get_user("alice").await.unwrap().groups, vec!["users"]);
+
+        // Removing a group the user doesn't have is a no-op
+        db.remove_group("alice", "ops").await.unwrap();
+        assert_eq!(db.get_user("alice").await.unwrap().groups, vec!["users"]);
+
+        // Removing the last group leaves an empty list
+        db.remove_group("alice", "users").await.unwrap();
+        assert!(db.get_user("alice").await.unwrap().groups.is_empty());
+
+        assert!(matches!(
+            db.remove_group("nobody", "users").await,
+            Err(AuthError::UserNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        assert!(db.ping().await.is_ok());
+
+        db.pool().close().await;
+        assert!(db.ping().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writers_retry_when_busy() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("busy.db");
+        // No busy timeout, so contention surfaces as SQLITE_BUSY immediately
+        let options = SqliteDbOptions::default()
+            .with_max_connections(3)
+            .with_busy_timeout(Duration::ZERO);
+        let db = SqliteUserDb::with_options(db_path.to_str().unwrap(), options).await.unwrap();
+
+        // Hold the write lock so both writers initially hit SQLITE_BUSY
+        let mut lock_conn = db.pool().acquire().await.unwrap();
+        sqlx::query("BEGIN IMMEDIATE").execute(&mut *lock_conn).await.unwrap();
+
+        let writer1 = {
+            let db = db.clone();
+            tokio::spawn(async move { db.create_user(UserRecord::new("alice", "hash1")).await })
+        };
+        let writer2 = {
+            let db = db.clone();
+            tokio::spawn(async move { db.create_user(UserRecord::new("bob", "hash2")).await })
+        };
+
+        // Keep the hold well inside the retry budget so the test isn't timing-sensitive
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        sqlx::query("COMMIT").execute(&mut *lock_conn).await.unwrap();
+        drop(lock_conn);
+
+        writer1.await.unwrap().expect("first writer should succeed after retrying");
+        writer2.await.unwrap().expect("second writer should succeed after retrying");
+        assert_eq!(db.list_users().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_create_and_get_user() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        let user = UserRecord::new("alice", "hash123")
+            .with_groups(vec!["admins"]);
+
+        db.create_user(user).await.unwrap();
+
+        let fetched = db.get_user("alice").await.unwrap();
+        assert_eq!(fetched.username, "alice");
+        assert_eq!(fetched.groups, vec!["admins"]);
+        assert_eq!(db.list_users().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_databases_are_isolated() {
+        let db1 = SqliteUserDb::in_memory().await.unwrap();
+        let db2 = SqliteUserDb::in_memory().await.unwrap();
+
+        db1.create_user(UserRecord::new("alice", "hash")).await.unwrap();
+        assert!(db2.get_user("alice").await.is_err());
+    }
+
     #[tokio::test]
     async fn test_user_not_found() {
         let db = test_db().await.unwrap();
@@ -354,6 +1162,80 @@ mod tests {
         assert!(db.delete_user("nonexistent").await.is_err());
     }
 
+    #[tokio::test]
+    async fn test_soft_delete_user() {
+        let db = test_db().await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash")).await.unwrap();
+
+        db.soft_delete_user("alice").await.unwrap();
+
+        // Row is kept and marked deleted; the enabled flag is untouched,
+        // but the account no longer counts as enabled
+        let fetched = db.get_user("alice").await.unwrap();
+        assert!(fetched.enabled);
+        assert!(fetched.is_deleted());
+        assert!(!fetched.is_enabled());
+    }
+
+    #[tokio::test]
+    async fn test_soft_deleted_excluded_from_listing() {
+        let db = test_db().await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash1")).await.unwrap();
+        db.create_user(UserRecord::new("bob", "hash2")).await.unwrap();
+
+        db.soft_delete_user("alice").await.unwrap();
+
+        let users = db.list_users().await.unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].username, "bob");
+
+        let all_users = db.list_users_filtered(true).await.unwrap();
+        assert_eq!(all_users.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_restore_user() {
+        let db = test_db().await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash")).await.unwrap();
+
+        db.soft_delete_user("alice").await.unwrap();
+        db.restore_user("alice").await.unwrap();
+
+        let fetched = db.get_user("alice").await.unwrap();
+        assert!(fetched.enabled);
+        assert!(!fetched.is_deleted());
+        assert_eq!(db.list_users().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_restore_ignores_disabled_user() {
+        let db = test_db().await.unwrap();
+        db.create_user(UserRecord::new("bob", "hash").disable()).await.unwrap();
+
+        assert!(matches!(db.restore_user("bob").await, Err(AuthError::UserNotFound)));
+        assert!(!db.get_user("bob").await.unwrap().enabled);
+    }
+
+    #[tokio::test]
+    async fn test_restore_keeps_user_disabled() {
+        let db = test_db().await.unwrap();
+        db.create_user(UserRecord::new("bob", "hash").disable()).await.unwrap();
+
+        db.soft_delete_user("bob").await.unwrap();
+        db.restore_user("bob").await.unwrap();
+
+        let fetched = db.get_user("bob").await.unwrap();
+        assert!(!fetched.is_deleted());
+        assert!(!fetched.enabled);
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_nonexistent() {
+        let db = test_db().await.unwrap();
+        assert!(matches!(db.soft_delete_user("nonexistent").await, Err(AuthError::UserNotFound)));
+        assert!(matches!(db.restore_user("nonexistent").await, Err(AuthError::UserNotFound)));
+    }
+
     #[tokio::test]
     async fn test_update_groups() {
         let db = test_db().await.unwrap();
@@ -366,4 +1248,154 @@ mod tests {
         let fetched = db.get_user("alice").await.unwrap();
         assert_eq!(fetched.groups, vec!["users", "admins"]);
     }
+
+    async fn usernames_in_group(db: &SqliteUserDb, group: &str) -> Vec<String> {
+        db.list_users_in_group(group)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|u| u.username)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_group_queries_follow_every_write() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash").with_groups(vec!["admins", "users"]))
+            .await
+            .unwrap();
+        db.create_user(UserRecord::new("bob", "hash").with_groups(vec!["users"]))
+            .await
+            .unwrap();
+        db.create_user(UserRecord::new("carol", "hash")).await.unwrap();
+
+        assert_eq!(usernames_in_group(&db, "users").await, vec!["alice", "bob"]);
+        assert_eq!(db.count_by_group("admins").await.unwrap(), 1);
+
+        db.add_group("carol", "admins").await.unwrap();
+        db.remove_group("alice", "admins").await.unwrap();
+        assert_eq!(usernames_in_group(&db, "admins").await, vec!["carol"]);
+
+        db.update_groups("bob", vec!["ops".to_string()]).await.unwrap();
+        assert_eq!(usernames_in_group(&db, "users").await, vec!["alice"]);
+
+        // Soft-deleted users are hidden, hard-deleted ones are gone
+        db.soft_delete_user("carol").await.unwrap();
+        assert_eq!(db.count_by_group("admins").await.unwrap(), 0);
+        db.delete_user("bob").await.unwrap();
+        assert_eq!(db.count_by_group("ops").await.unwrap(), 0);
+        let (rows,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM user_groups WHERE group_name = 'ops'")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(rows, 0);
+    }
+
+    #[tokio::test]
+    async fn test_group_queries_use_index() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+
+        for query in [USERS_IN_GROUP_QUERY, COUNT_IN_GROUP_QUERY] {
+            let plan: Vec<(i64, i64, i64, String)> =
+                sqlx::query_as(&format!("EXPLAIN QUERY PLAN {}", query))
+                    .bind("admins")
+                    .fetch_all(db.pool())
+                    .await
+                    .unwrap();
+            let details: Vec<&str> = plan.iter().map(|row| row.3.as_str()).collect();
+
+            assert!(
+                details.iter().any(|d| d.contains("idx_user_groups_group")),
+                "group lookup doesn't use the index: {:?}",
+                details
+            );
+            assert!(
+                !details.iter().any(|d| d.starts_with("SCAN")),
+                "group lookup scans a table: {:?}",
+                details
+            );
+        }
+    }
+
+    async fn schema_objects(db: &SqliteUserDb) -> Vec<(String, String)> {
+        sqlx::query_as::<_, (String, String)>(
+            "SELECT type, name FROM sqlite_master WHERE name NOT LIKE 'sqlite_%' ORDER BY type, name",
+        )
+        .fetch_all(db.pool())
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_migrations_create_schema_idempotently() {
+        let db = test_db().await.unwrap();
+        let objects = schema_objects(&db).await;
+        let names: Vec<&str> = objects.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "idx_audit_log_timestamp",
+                "idx_user_groups_group",
+                "idx_users_username",
+                "_sqlx_migrations",
+                "audit_log",
+                "user_groups",
+                "users",
+                "users_groups_after_delete",
+                "users_groups_after_insert",
+                "users_groups_after_update",
+            ]
+        );
+
+        let (applied,) = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM _sqlx_migrations")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(applied as usize, MIGRATOR.iter().count());
+
+        db.create_user(UserRecord::new("alice", "hash")).await.unwrap();
+        db.migrate().await.unwrap();
+        db.migrate().await.unwrap();
+        assert_eq!(schema_objects(&db).await, objects);
+        assert_eq!(db.count_users().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_users_table_gains_deleted_at() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        for table in ["user_groups", "users", "_sqlx_migrations"] {
+            sqlx::query(&format!("DROP TABLE {}", table)).execute(db.pool()).await.unwrap();
+        }
+        sqlx::query(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, username TEXT NOT NULL UNIQUE, \
+             password_hash TEXT NOT NULL, groups TEXT NOT NULL DEFAULT '[]', enabled BOOLEAN NOT NULL DEFAULT 1, \
+             created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL)",
+        )
+        .execute(db.pool())
+        .await
+        .unwrap();
+
+        db.migrate().await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash")).await.unwrap();
+        db.soft_delete_user("alice").await.unwrap();
+        assert!(db.get_user("alice").await.unwrap().is_deleted());
+    }
+
+    #[tokio::test]
+    async fn test_user_groups_backfilled_on_migrate() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        db.create_user(UserRecord::new("alice", "hash").with_groups(vec!["admins"]))
+            .await
+            .unwrap();
+
+        // Simulate an unversioned database created before user_groups existed
+        sqlx::query("DROP TABLE user_groups").execute(db.pool()).await.unwrap();
+        for trigger in ["users_groups_after_insert", "users_groups_after_update", "users_groups_after_delete"] {
+            sqlx::query(&format!("DROP TRIGGER {}", trigger)).execute(db.pool()).await.unwrap();
+        }
+        sqlx::query("DROP TABLE _sqlx_migrations").execute(db.pool()).await.unwrap();
+
+        db.migrate().await.unwrap();
+        assert_eq!(usernames_in_group(&db, "admins").await, vec!["alice"]);
+    }
 }
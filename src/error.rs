@@ -50,17 +50,29 @@ pub enum AuthError {
     #[error("Invalid token")]
     InvalidToken,
 
+    /// Token isn't a well-formed JWT (bad base64, JSON or segment count).
+    #[error("Malformed token")]
+    MalformedToken,
+
+    /// Token is well-formed but its signature doesn't match.
+    #[error("Invalid token signature")]
+    InvalidSignature,
+
     /// Token has expired.
     #[error("Token expired")]
     TokenExpired,
 
+    /// Token's not-before (`nbf`) time has not been reached yet.
+    #[error("Token not yet valid")]
+    TokenNotYetValid,
+
     /// Master authentication failed.
     #[error("Master authentication failed")]
     MasterAuthFailed,
 
-    /// Rate limit exceeded.
-    #[error("Rate limit exceeded")]
-    RateLimitExceeded,
+    /// Rate limit exceeded; carries the seconds to wait before retrying.
+    #[error("Rate limit exceeded, retry in {0} seconds")]
+    RateLimitExceeded(u64),
 
     /// Secrets operation failed.
     #[error("Secrets error: {0}")]
@@ -111,9 +123,68 @@ impl AuthError {
     pub fn is_token_error(&self) -> bool {
         matches!(
             self,
-            AuthError::InvalidToken | AuthError::TokenExpired | AuthError::JwtError(_)
+            AuthError::InvalidToken
+                | AuthError::MalformedToken
+                | AuthError::InvalidSignature
+                | AuthError::TokenExpired
+                | AuthError::TokenNotYetValid
+                | AuthError::JwtError(_)
         )
     }
+
+    /// Check if retrying the operation later could succeed.
+    ///
+    /// True for transient backend failures (database, LDAP) and rate
+    /// limiting; false for errors a retry can't fix, such as bad credentials,
+    /// invalid or expired tokens and misconfiguration.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AuthError::DatabaseError(_)
+            | AuthError::LdapError(_)
+            | AuthError::RateLimitExceeded(_) => true,
+            AuthError::InvalidCredentials
+            | AuthError::UserNotFound
+            | AuthError::UserDisabled
+            | AuthError::ConfigError(_)
+            | AuthError::JwtError(_)
+            | AuthError::Other(_)
+            | AuthError::ProviderNotFound(_)
+            | AuthError::InvalidToken
+            | AuthError::MalformedToken
+            | AuthError::InvalidSignature
+            | AuthError::TokenExpired
+            | AuthError::TokenNotYetValid
+            | AuthError::MasterAuthFailed
+            | AuthError::SecretsError(_)
+            | AuthError::PasswordValidationError(_) => false,
+        }
+    }
+
+    /// Short, stable snake_case name of the error variant.
+    ///
+    /// Suitable as a metrics label: it never includes the error message.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AuthError::InvalidCredentials => "invalid_credentials",
+            AuthError::UserNotFound => "user_not_found",
+            AuthError::UserDisabled => "user_disabled",
+            AuthError::LdapError(_) => "ldap_error",
+            AuthError::DatabaseError(_) => "database_error",
+            AuthError::ConfigError(_) => "config_error",
+            AuthError::JwtError(_) => "jwt_error",
+            AuthError::Other(_) => "other",
+            AuthError::ProviderNotFound(_) => "provider_not_found",
+            AuthError::InvalidToken => "invalid_token",
+            AuthError::MalformedToken => "malformed_token",
+            AuthError::InvalidSignature => "invalid_signature",
+            AuthError::TokenExpired => "token_expired",
+            AuthError::TokenNotYetValid => "token_not_yet_valid",
+            AuthError::MasterAuthFailed => "master_auth_failed",
+            AuthError::RateLimitExceeded(_) => "rate_limit_exceeded",
+            AuthError::SecretsError(_) => "secrets_error",
+            AuthError::PasswordValidationError(_) => "password_validation_error",
+        }
+    }
 }
 
 /// Errors that can occur during configuration loading.
@@ -174,6 +245,12 @@ impl From<toml::de::Error> for ConfigError {
     }
 }
 
+impl From<ConfigError> for AuthError {
+    fn from(err: ConfigError) -> Self {
+        AuthError::ConfigError(err.to_string())
+    }
+}
+
 /// Errors that can occur during secrets management.
 #[derive(Debug, Error)]
 pub enum SecretsError {
@@ -238,12 +315,64 @@ mod tests {
     fn test_token_error_detection() {
         assert!(AuthError::InvalidToken.is_token_error());
         assert!(AuthError::TokenExpired.is_token_error());
+        assert!(AuthError::TokenNotYetValid.is_token_error());
+        assert!(AuthError::MalformedToken.is_token_error());
+        assert!(AuthError::InvalidSignature.is_token_error());
         assert!(!AuthError::InvalidCredentials.is_token_error());
     }
 
+    #[test]
+    fn test_retryable_classification() {
+        let retryable = [
+            AuthError::database("locked"),
+            AuthError::ldap("connection reset"),
+            AuthError::RateLimitExceeded(30),
+        ];
+        let permanent = [
+            AuthError::InvalidCredentials,
+            AuthError::UserNotFound,
+            AuthError::UserDisabled,
+            AuthError::config("missing"),
+            AuthError::jwt("bad"),
+            AuthError::other("nope"),
+            AuthError::ProviderNotFound("oauth2".to_string()),
+            AuthError::InvalidToken,
+            AuthError::MalformedToken,
+            AuthError::InvalidSignature,
+            AuthError::TokenExpired,
+            AuthError::TokenNotYetValid,
+            AuthError::MasterAuthFailed,
+            AuthError::SecretsError("keyring".to_string()),
+            AuthError::PasswordValidationError("too short".to_string()),
+        ];
+
+        for err in &retryable {
+            assert!(err.is_retryable(), "{} should be retryable", err.kind());
+        }
+        for err in &permanent {
+            assert!(!err.is_retryable(), "{} should not be retryable", err.kind());
+        }
+    }
+
+    #[test]
+    fn test_error_kind() {
+        assert_eq!(AuthError::InvalidCredentials.kind(), "invalid_credentials");
+        assert_eq!(AuthError::database("secret detail").kind(), "database_error");
+    }
+
     #[test]
     fn test_config_error_creation() {
         let err = ConfigError::missing("database.path");
         assert!(matches!(err, ConfigError::Missing(_)));
     }
+
+    #[test]
+    fn test_config_error_converts_into_auth_error() {
+        let err: AuthError = ConfigError::missing("database.path").into();
+        assert!(matches!(&err, AuthError::ConfigError(msg) if msg == "Missing configuration: database.path"));
+        assert_eq!(err.kind(), "config_error");
+
+        let err: AuthError = SecretsError::not_found("jwt-secret").into();
+        assert!(matches!(err, AuthError::SecretsError(_)));
+    }
 }
@@ -4,16 +4,43 @@
 //! cryptographic overhead on hot paths.
 
 use crate::auth::UserClaims;
+#[cfg(feature = "cache")]
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 #[cfg(feature = "cache")]
-use moka::future::Cache;
+use moka::{future::Cache, Expiry};
+
+/// Snapshot of a [`TokenCache`]'s effectiveness, returned by [`TokenCache::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups that returned cached claims
+    pub hits: u64,
+    /// Lookups that found nothing
+    pub misses: u64,
+    /// Entries currently cached (approximate, see [`TokenCache::len`])
+    pub entries: u64,
+}
+
+impl CacheStats {
+    /// Fraction of lookups that were hits, or 0.0 before any lookup.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
 
 /// In-memory cache for JWT validation results.
 ///
 /// Caches decoded JWT claims keyed by token string to avoid redundant
-/// cryptographic verification. Cache entries expire after a configurable TTL.
+/// cryptographic verification. Cache entries expire after a configurable TTL,
+/// or when the token's `exp` is reached if that comes first, so cached claims
+/// never outlive their token.
 ///
 /// # Example
 ///
@@ -32,6 +59,8 @@ use moka::future::Cache;
 pub struct TokenCache {
     cache: Cache<String, Arc<UserClaims>>,
     ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
 }
 
 #[cfg(feature = "cache")]
@@ -49,13 +78,22 @@ impl TokenCache {
     pub fn with_ttl(ttl: Duration) -> Self {
         let cache = Cache::builder()
             .time_to_live(ttl)
+            .expire_after(ExpireAtTokenExp)
             .build();
 
-        Self { cache, ttl }
+        Self {
+            cache,
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
     }
 
     /// Insert a token and its decoded claims into the cache.
     ///
+    /// The entry lives for `min(ttl, time until claims.exp)`; claims that are
+    /// already expired are never returned by `get`.
+    ///
     /// # Arguments
     ///
     /// * `token` - The JWT token string
@@ -64,15 +102,36 @@ impl TokenCache {
         self.cache.insert(token, Arc::new(claims)).await;
     }
 
+    /// Insert a batch of tokens, e.g. to warm the cache on deploy.
+    ///
+    /// Each entry expires exactly as if passed to [`insert`](Self::insert),
+    /// so tokens that are already expired are skipped by `get`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let known_good: Vec<(String, UserClaims)> = load_replay_set().await?;
+    /// cache.insert_many(known_good).await;
+    /// ```
+    pub async fn insert_many(&self, entries: Vec<(String, UserClaims)>) {
+        for (token, claims) in entries {
+            self.insert(token, claims).await;
+        }
+    }
+
     /// Retrieve cached claims for a token, if available.
     ///
     /// Returns `None` if token is not in cache or cache entry has expired.
+    /// Each call counts as a hit or miss in [`stats`](Self::stats).
     ///
     /// # Arguments
     ///
     /// * `token` - The JWT token string
     pub async fn get(&self, token: &str) -> Option<UserClaims> {
-        self.cache.get(token).await.map(|arc_claims| (*arc_claims).clone())
+        let claims = self.cache.get(token).await;
+        let counter = if claims.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        claims.map(|arc_claims| (*arc_claims).clone())
     }
 
     /// Remove a token from the cache (useful for revocation).
@@ -103,6 +162,46 @@ impl TokenCache {
     pub fn is_empty(&self) -> bool {
         self.cache.entry_count() == 0
     }
+
+    /// Hit and miss counts since the cache was created, plus its size.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let stats = cache.stats();
+    /// tracing::info!(hits = stats.hits, misses = stats.misses, "hit rate {:.2}", stats.hit_rate());
+    /// ```
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.cache.entry_count(),
+        }
+    }
+
+    /// Fraction of lookups that were hits, see [`CacheStats::hit_rate`].
+    pub fn hit_rate(&self) -> f64 {
+        self.stats().hit_rate()
+    }
+}
+
+/// Per-entry expiry that ends an entry at its token's `exp`.
+///
+/// moka evicts at the earliest of this and the cache-wide `time_to_live`.
+#[cfg(feature = "cache")]
+struct ExpireAtTokenExp;
+
+#[cfg(feature = "cache")]
+impl Expiry<String, Arc<UserClaims>> for ExpireAtTokenExp {
+    fn expire_after_create(
+        &self,
+        _token: &String,
+        claims: &Arc<UserClaims>,
+        _created_at: std::time::Instant,
+    ) -> Option<Duration> {
+        let remaining = claims.exp - chrono::Utc::now().timestamp();
+        Some(Duration::from_secs(remaining.max(0) as u64))
+    }
 }
 
 #[cfg(feature = "cache")]
@@ -117,7 +216,7 @@ impl std::fmt::Debug for TokenCache {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TokenCache")
             .field("ttl", &self.ttl)
-            .field("entry_count", &self.cache.entry_count())
+            .field("stats", &self.stats())
             .finish()
     }
 }
@@ -141,6 +240,9 @@ impl TokenCache {
     /// Insert operation (no-op when cache feature is disabled).
     pub async fn insert(&self, _token: String, _claims: UserClaims) {}
 
+    /// Bulk insert operation (no-op when cache feature is disabled).
+    pub async fn insert_many(&self, _entries: Vec<(String, UserClaims)>) {}
+
     /// Get operation (always returns None when cache feature is disabled).
     pub async fn get(&self, _token: &str) -> Option<UserClaims> {
         None
@@ -166,6 +268,16 @@ impl TokenCache {
     pub fn is_empty(&self) -> bool {
         true
     }
+
+    /// Cache statistics (always zero when cache feature is disabled).
+    pub fn stats(&self) -> CacheStats {
+        CacheStats::default()
+    }
+
+    /// Hit rate (always 0.0 when cache feature is disabled).
+    pub fn hit_rate(&self) -> f64 {
+        0.0
+    }
 }
 
 #[cfg(not(feature = "cache"))]
@@ -186,11 +298,16 @@ impl std::fmt::Debug for TokenCache {
 mod tests {
     use super::*;
 
+    /// Claims valid for another hour, so only the cache TTL applies.
+    fn claims_for(sub: &str, provider: &str) -> UserClaims {
+        let now = chrono::Utc::now().timestamp();
+        UserClaims::new(sub, provider, now + 3600, now)
+    }
+
     #[tokio::test]
     async fn test_cache_insert_and_retrieve() {
         let cache = TokenCache::new();
-        let claims = UserClaims::new("alice", "local", 1000, 500)
-            .with_groups(vec!["admins"]);
+        let claims = claims_for("alice", "local").with_groups(vec!["admins"]);
 
         cache.insert("token123".to_string(), claims.clone()).await;
 
@@ -206,10 +323,44 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[tokio::test]
+    async fn test_cache_stats_count_hits_and_misses() {
+        let cache = TokenCache::new();
+        assert_eq!(cache.hit_rate(), 0.0);
+
+        cache.insert("token123".to_string(), claims_for("alice", "local")).await;
+        assert!(cache.get("token123").await.is_some());
+        assert!(cache.get("token123").await.is_some());
+        assert!(cache.get("missing").await.is_none());
+        cache.cache.run_pending_tasks().await;
+
+        let stats = cache.stats();
+        assert_eq!(stats, CacheStats { hits: 2, misses: 1, entries: 1 });
+        assert!((cache.hit_rate() - 2.0 / 3.0).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn test_insert_many() {
+        let cache = TokenCache::new();
+        let now = chrono::Utc::now().timestamp();
+        cache
+            .insert_many(vec![
+                ("token-a".to_string(), claims_for("alice", "local")),
+                ("token-b".to_string(), claims_for("bob", "ldap")),
+                ("token-c".to_string(), UserClaims::new("carol", "local", now - 10, now - 3600)),
+            ])
+            .await;
+
+        assert_eq!(cache.get("token-a").await.unwrap().sub, "alice");
+        assert_eq!(cache.get("token-b").await.unwrap().provider, "ldap");
+        // Per-entry expiry still applies
+        assert!(cache.get("token-c").await.is_none());
+    }
+
     #[tokio::test]
     async fn test_cache_remove() {
         let cache = TokenCache::new();
-        let claims = UserClaims::new("bob", "local", 1000, 500);
+        let claims = claims_for("bob", "local");
 
         cache.insert("token456".to_string(), claims).await;
         assert!(cache.get("token456").await.is_some());
@@ -228,7 +379,7 @@ mod tests {
     #[tokio::test]
     async fn test_cache_default() {
         let cache = TokenCache::default();
-        let claims = UserClaims::new("charlie", "local", 1000, 500);
+        let claims = claims_for("charlie", "local");
 
         cache.insert("token789".to_string(), claims).await;
         assert!(cache.get("token789").await.is_some());
@@ -237,8 +388,7 @@ mod tests {
     #[tokio::test]
     async fn test_cache_preserves_all_claims() {
         let cache = TokenCache::new();
-        let mut claims = UserClaims::new("diana", "ldap", 2000, 1000);
-        claims = claims.with_groups(vec!["developers", "admins"]);
+        let claims = claims_for("diana", "ldap").with_groups(vec!["developers", "admins"]);
 
         cache.insert("token999".to_string(), claims.clone()).await;
 
@@ -247,4 +397,31 @@ mod tests {
         assert_eq!(retrieved.provider, "ldap");
         assert_eq!(retrieved.groups, vec!["developers", "admins"]);
     }
+
+    #[test]
+    fn test_entry_expires_at_token_exp() {
+        // The 5-minute cache TTL would keep these entries; the token's exp must not
+        let now = chrono::Utc::now().timestamp();
+        let expiry = |exp| {
+            let claims = Arc::new(UserClaims::new("erin", "local", exp, now - 60));
+            ExpireAtTokenExp
+                .expire_after_create(&"near-expiry".to_string(), &claims, std::time::Instant::now())
+                .unwrap()
+        };
+
+        let near = expiry(now + 10);
+        assert!(near <= Duration::from_secs(10) && near >= Duration::from_secs(9));
+        assert_eq!(expiry(now - 1), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_never_served() {
+        let cache = TokenCache::new();
+        let now = chrono::Utc::now().timestamp();
+        cache
+            .insert("expired".to_string(), UserClaims::new("frank", "local", now - 10, now - 3600))
+            .await;
+
+        assert!(cache.get("expired").await.is_none());
+    }
 }
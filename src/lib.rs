@@ -81,6 +81,94 @@
 //! }
 //! ```
 //!
+//! ## Authorization Macros
+//!
+//! With the `macros` feature (enabled by default), the `require_*` attribute
+//! macros are re-exported from the main crate, so no direct dependency on
+//! `poem_auth_macros` is needed:
+//!
+//! ```rust
+//! use poem::{handler, IntoResponse, Response};
+//! use poem_auth::{require_any_groups, require_group, UserClaims};
+//!
+//! #[require_group("admins")]
+//! #[handler]
+//! async fn admin_panel(claims: UserClaims) -> Response {
+//!     format!("Welcome, {}", claims.sub).into_response()
+//! }
+//!
+//! #[require_any_groups("admins", "moderators")]
+//! #[handler]
+//! async fn moderation(claims: UserClaims) -> Response {
+//!     "Moderation panel".into_response()
+//! }
+//! ```
+//!
+//! `require_any_scope` and `require_all_scopes` do the same for OAuth-style
+//! scopes in the token's `scope` claim (see [`UserClaims::with_scopes`]):
+//!
+//! ```rust
+//! use poem::{handler, IntoResponse, Response};
+//! use poem_auth::{require_all_scopes, require_any_scope, UserClaims};
+//!
+//! #[require_any_scope("read:users", "admin")]
+//! #[handler]
+//! async fn list_users(claims: UserClaims) -> Response {
+//!     "User list".into_response()
+//! }
+//!
+//! #[require_all_scopes("read:users", "write:users")]
+//! #[handler]
+//! fn edit_user(claims: UserClaims) -> Response {
+//!     "Edit user".into_response()
+//! }
+//! ```
+//!
+//! `require_provider` checks how the user authenticated (`claims.provider`),
+//! e.g. `#[require_provider("ldap")]` keeps out local-password logins.
+//!
+//! Stacked macros combine with AND and are checked top to bottom; the first
+//! failing requirement is the one reported:
+//!
+//! ```rust
+//! use poem::{handler, IntoResponse, Response};
+//! use poem_auth::{require_group, UserClaims};
+//!
+//! #[require_group("admins")]
+//! #[require_group("verified")]
+//! #[handler]
+//! async fn verified_admins(claims: UserClaims) -> Response {
+//!     "Verified admins only".into_response()
+//! }
+//! ```
+//!
+//! Stacked attributes are validated like a single one:
+//!
+//! ```rust,compile_fail
+//! use poem::{handler, IntoResponse, Response};
+//! use poem_auth::{require_group, UserClaims};
+//!
+//! #[require_group("admins")]
+//! #[require_group()]
+//! #[handler]
+//! async fn incomplete(claims: UserClaims) -> Response {
+//!     "Unreachable".into_response()
+//! }
+//! ```
+//!
+//! At least one name is required:
+//!
+//! ```rust,compile_fail
+//! use poem::{handler, IntoResponse, Response};
+//! use poem_auth::{require_any_scope, UserClaims};
+//!
+//! #[require_any_scope()]
+//! #[handler]
+//! async fn nothing_required(claims: UserClaims) -> Response {
+//!     "Unreachable".into_response()
+//! }
+//! ```
+//!
 //! ## Feature Flags
 //!
 //! - **`sqlite`** (default) - SQLite user database support
@@ -88,7 +176,13 @@
 //! - **`keyring-support`** (default) - OS keyring integration for secrets
 //! - **`cache`** (default) - In-memory token caching
 //! - **`rate-limit`** - Rate limiting middleware
+//! - **`middleware`** - `AuditMiddleware`, which records every request in an audit log
+//! - **`metrics`** - Auth outcome metrics via the `metrics` facade (see [`telemetry`])
 //! - **`cors`** - CORS support
+//! - **`openapi`** - `poem-openapi` service with Swagger UI for the login and user management endpoints
+//! - **`testing`** - Harness for testing protected handlers (see [`testing`](crate::testing))
+//! - **`macros`** (default) - `require_group`, `require_any_groups`, `require_all_groups`,
+//!   `require_any_scope`, `require_all_scopes` and `require_provider`
 //! - **`all`** - Enable all features
 //!
 //! ## Security
@@ -108,13 +202,17 @@
 //! - [`auth::UserClaims`] - User information structure
 //! - [`db::UserDatabase`] - Database abstraction
 //! - [`middleware`] - Poem middleware components
+//! - [`net::client_ip`] - Client IP resolution behind reverse proxies
 
+pub mod audit;
 pub mod auth;
 pub mod db;
 pub mod error;
 pub mod password;
 pub mod jwt;
+pub mod keys;
 pub mod middleware;
+pub mod net;
 pub mod api;
 
 // Providers
@@ -123,44 +221,75 @@ pub mod providers;
 // Configuration and integration
 pub mod config;
 pub mod quick_start;
+pub mod telemetry;
 pub mod poem_integration;
 
+/// Test harness for downstream integration tests.
+/// Available when the `testing` feature is enabled.
+#[cfg(feature = "testing")]
+pub mod testing;
+
 // Re-export commonly used types
-pub use auth::{AuthProvider, UserClaims};
-pub use db::{UserDatabase, UserRecord};
+pub use audit::{write_ndjson, AuditEvent, AuditFilter, AuditLog};
+pub use auth::{AuthProvider, DiagnosticCheck, ProviderDiagnostics, UserClaims};
+pub use db::{MemoryUserDb, UserDatabase, UserRecord, UsernamePolicy};
 #[cfg(feature = "sqlite")]
-pub use db::SqliteUserDb;
+pub use db::{SqliteDbOptions, SqliteUserDb};
 pub use error::{AuthError, ConfigError, SecretsError};
-pub use providers::LocalAuthProvider;
+pub use providers::{LocalAuthProvider, ProviderRegistry};
 #[cfg(feature = "ldap")]
 pub use providers::{LdapAuthProvider, LdapConfig};
-pub use password::{hash_password, verify_password};
-pub use jwt::{JwtValidator, Token, TokenCache};
+pub use password::{hash_password, verify_password, Argon2Hasher, PasswordHasher, PepperedHasher};
+pub use jwt::{verify_bearer, JwtHeader, JwtValidator, SecretPolicy, Token, TokenCache, VerifiedToken};
 pub use middleware::{extract_jwt_claims, MasterAuth, MasterCredentials};
-#[cfg(feature = "rate-limit")]
-pub use middleware::{RateLimit, RateLimitConfig};
-pub use api::types::{LoginRequest, LoginResponse, CreateUserRequest, UpdatePasswordRequest, ErrorResponse, UserClaimsResponse};
+pub use middleware::{IpAllowlist, IpCidr, RateLimit, RateLimitConfig};
+#[cfg(feature = "middleware")]
+pub use middleware::AuditMiddleware;
+pub use api::types::{LoginRequest, LoginResponse, CreateUserRequest, UpdatePasswordRequest, ErrorResponse, UserClaimsResponse, IntrospectionRequest, IntrospectionResponse};
 
 // Configuration and integration exports
-pub use config::{AuthConfig, ServerConfig, TlsConfig};
+pub use config::{AuthConfig, AuthConfigBuilder, PasswordConfig, ProviderConfig, ServerConfig, TlsConfig, UserConfig};
 pub use quick_start::initialize_from_config;
-pub use poem_integration::{PoemAppState, AuthGuard, HasGroup, HasAnyGroup, HasAllGroups, And, Or, Not, LoginResponseBuilder};
+pub use poem_integration::{PoemAppState, HealthStatus, TokenSource, AuthLayer, ClaimsExt, AuthEndpointExt, RequireGuard, AuthGuard, AsyncAuthGuard, DenialMode, HasGroup, HasAnyGroup, HasAllGroups, HasAnyScope, HasAllScopes, HasProvider, And, Or, Not, LoginResponseBuilder};
 
 // Procedural macros for authorization (Phase 2B)
 #[cfg(feature = "macros")]
-pub use poem_auth_macros::{require_group, require_any_groups, require_all_groups};
+pub use poem_auth_macros::{require_group, require_any_groups, require_all_groups, require_any_scope, require_all_scopes, require_provider};
 
 /// Prelude with commonly used imports.
 ///
+/// Includes the core traits and types, the authorization guards, and (with the
+/// `macros` feature) the `require_*` attribute macros.
+///
 /// # Example
 ///
 /// ```rust,ignore
 /// use poem_auth::prelude::*;
+///
+/// #[require_group("admins")]
+/// #[handler]
+/// async fn admin_panel(claims: UserClaims) -> Response {
+///     "Welcome to admin panel".into()
+/// }
+///
+/// let guard = And { first: HasGroup("admins".into()), second: IsEnabled };
 /// ```
 pub mod prelude {
     pub use crate::auth::{AuthProvider, UserClaims};
+    pub use crate::config::{AuthConfig, AuthConfigBuilder, UserConfig};
     pub use crate::db::{UserDatabase, UserRecord};
     pub use crate::error::AuthError;
+    pub use crate::poem_integration::{
+        AuthGuard, HasGroup, HasAnyGroup, HasAllGroups, HasAnyScope, HasAllScopes, HasProvider, And,
+        Or, Not, IsEnabled,
+        AuthEndpointExt, LoginResponseBuilder,
+    };
+
+    #[cfg(feature = "macros")]
+    pub use poem_auth_macros::{
+        require_group, require_any_groups, require_all_groups, require_any_scope, require_all_scopes,
+        require_provider,
+    };
 }
 
 #[cfg(test)]
@@ -169,4 +298,30 @@ mod tests {
     fn test_library_compiles() {
         // This test just ensures the library compiles successfully
     }
+
+    #[test]
+    fn test_prelude_brings_guards_into_scope() {
+        use crate::prelude::*;
+
+        let claims = UserClaims::new("alice", "local", 0, 0).with_groups(vec!["admins"]);
+        let guard = And {
+            first: HasGroup("admins".to_string()),
+            second: Not(HasAnyGroup(vec!["banned".to_string()])),
+        };
+        assert!(guard.check(&claims));
+        assert!(!HasAllGroups(vec!["admins".to_string(), "ops".to_string()]).check(&claims));
+        assert!(Or { first: IsEnabled, second: HasGroup("ops".to_string()) }.check(&claims));
+        let _ = LoginResponseBuilder::success;
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn test_prelude_brings_macros_into_scope() {
+        // Fails to compile if the macros aren't re-exported through the prelude
+        #[allow(unused_imports)]
+        use crate::prelude::{
+            require_group, require_any_groups, require_all_groups, require_any_scope, require_all_scopes,
+            require_provider,
+        };
+    }
 }
@@ -1,16 +1,20 @@
 //! Poem middleware utilities for authentication.
 //!
 //! This module provides utilities and helpers for JWT authentication, master admin authentication,
-//! and rate limiting in Poem applications.
+//! request auditing and rate limiting in Poem applications.
 
+#[cfg(feature = "middleware")]
+pub mod audit;
+pub mod ip_allowlist;
 pub mod jwt_auth;
 pub mod master_auth;
 
-#[cfg(feature = "rate-limit")]
 pub mod rate_limit;
 
+#[cfg(feature = "middleware")]
+pub use audit::AuditMiddleware;
+pub use ip_allowlist::{IpAllowlist, IpCidr};
 pub use jwt_auth::extract_jwt_claims;
 pub use master_auth::{MasterAuth, MasterCredentials};
 
-#[cfg(feature = "rate-limit")]
-pub use rate_limit::{RateLimit, RateLimitConfig};
+pub use rate_limit::{rate_limited_response, RateLimit, RateLimitConfig};
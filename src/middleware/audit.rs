@@ -0,0 +1,174 @@
+//! Request audit trail middleware.
+//!
+//! [`AuditMiddleware`] records one [`AuditEvent`] per request to the wrapped
+//! routes, with the caller's `sub` and the response status, so handlers
+//! don't need their own audit calls.
+
+use std::sync::{Arc, Mutex};
+
+use poem::{Endpoint, IntoResponse, Middleware, Request, Response, Result};
+
+use crate::audit::{AuditEvent, AuditLog};
+use crate::auth::UserClaims;
+use crate::net::client_ip;
+
+/// Event type of the events recorded by [`AuditMiddleware`].
+pub const REQUEST_EVENT: &str = "http_request";
+
+/// Provider recorded for requests without a valid token.
+const ANONYMOUS_PROVIDER: &str = "anonymous";
+
+/// Middleware that records every request in an [`AuditLog`].
+///
+/// After the wrapped endpoint responds, an `http_request` event is recorded
+/// with the caller's `sub` and provider, the client IP, and details of the
+/// form `{"method": "GET", "path": "/admin", "status": 403}`. The token isn't
+/// verified again: the claims are the ones an outer
+/// [`AuthLayer`](crate::poem_integration::AuthLayer), or an inner layer,
+/// guard or `UserClaims` extractor, already verified. Requests where nothing
+/// verified a token are recorded with provider `anonymous` and no username.
+///
+/// Failing to record only logs a warning; the response is returned either
+/// way.
+///
+/// # Example
+///
+/// ```ignore
+/// use poem::{get, EndpointExt, Route};
+/// use poem_auth::middleware::AuditMiddleware;
+///
+/// let db = Arc::new(SqliteUserDb::new("users.db").await?);
+/// let app = Route::new()
+///     .at("/admin", get(admin_panel).require_group("admins"))
+///     .with(AuditMiddleware::new(db));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AuditMiddleware {
+    audit_log: Arc<dyn AuditLog>,
+    trust_forwarded: bool,
+}
+
+impl AuditMiddleware {
+    /// Record requests in `audit_log`.
+    pub fn new(audit_log: Arc<dyn AuditLog>) -> Self {
+        Self {
+            audit_log,
+            trust_forwarded: false,
+        }
+    }
+
+    /// Resolve the client IP from `X-Forwarded-For`/`X-Real-IP` (default: false).
+    ///
+    /// Only enable behind a reverse proxy that overwrites these headers.
+    pub fn with_trust_forwarded(mut self, trust: bool) -> Self {
+        self.trust_forwarded = trust;
+        self
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for AuditMiddleware {
+    type Output = AuditEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        AuditEndpoint {
+            inner: ep,
+            audit: self.clone(),
+        }
+    }
+}
+
+/// Endpoint produced by [`AuditMiddleware`]
+#[derive(Debug)]
+pub struct AuditEndpoint<E> {
+    inner: E,
+    audit: AuditMiddleware,
+}
+
+impl<E: Endpoint> Endpoint for AuditEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let outer_claims = req.extensions().get::<UserClaims>().cloned();
+        let verified = VerifiedClaims::default();
+        req.extensions_mut().insert(verified.clone());
+        let ip = client_ip(&req, self.audit.trust_forwarded);
+        let method = req.method().to_string();
+        let path = req.uri().path().to_string();
+
+        let result = self.inner.call(req).await.map(IntoResponse::into_response);
+        let status = match &result {
+            Ok(response) => response.status(),
+            Err(err) => err.status(),
+        };
+
+        let claims = outer_claims.or_else(|| verified.take());
+        let details = serde_json::json!({
+            "method": method,
+            "path": path,
+            "status": status.as_u16(),
+        });
+        let event = match claims {
+            Some(claims) => AuditEvent::new(REQUEST_EVENT, claims.provider).with_username(claims.sub),
+            None => AuditEvent::new(REQUEST_EVENT, ANONYMOUS_PROVIDER),
+        }
+        .with_ip_address(ip)
+        .with_details(details.to_string());
+        if let Err(e) = self.audit.audit_log.record(event).await {
+            tracing::warn!(error = %e, path = %path, "Failed to record request audit event");
+        }
+
+        result
+    }
+}
+
+/// Claims verified while an audited request was handled.
+///
+/// [`AuditEndpoint`] attaches an empty one to each request, and inner layers,
+/// guards and extractors fill it in through [`VerifiedClaims::record`] when
+/// they verify the token.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VerifiedClaims(Arc<Mutex<Option<UserClaims>>>);
+
+impl VerifiedClaims {
+    /// Remember `claims` for the audit event of `req`, if it is audited.
+    pub(crate) fn record(req: &Request, claims: &UserClaims) {
+        if let Some(slot) = req.extensions().get::<VerifiedClaims>() {
+            *slot.0.lock().unwrap() = Some(claims.clone());
+        }
+    }
+
+    fn take(&self) -> Option<UserClaims> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AuthError;
+    use async_trait::async_trait;
+    use poem::{handler, http::StatusCode, EndpointExt};
+
+    #[derive(Debug)]
+    struct FailingAuditLog;
+
+    #[async_trait]
+    impl AuditLog for FailingAuditLog {
+        async fn record(&self, _event: AuditEvent) -> std::result::Result<(), AuthError> {
+            Err(AuthError::database("audit store unavailable"))
+        }
+    }
+
+    #[handler]
+    fn index() -> &'static str {
+        "ok"
+    }
+
+    #[tokio::test]
+    async fn test_audit_failure_does_not_block_response() {
+        let ep = index.with(AuditMiddleware::new(Arc::new(FailingAuditLog)));
+        let resp = ep.get_response(Request::builder().finish()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.into_body().into_string().await.unwrap(), "ok");
+    }
+}
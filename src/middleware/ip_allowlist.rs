@@ -0,0 +1,238 @@
+//! Network allowlists for administrative endpoints.
+//!
+//! [`IpAllowlist`] restricts the wrapped routes to clients inside trusted
+//! IPv4 and IPv6 ranges. Apply it around master-authenticated routes so
+//! requests from elsewhere are rejected with 403 Forbidden before any
+//! credentials are checked.
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use poem::{
+    http::StatusCode, web::Json, Endpoint, Error as PoemError, IntoResponse, Middleware, Request,
+    Result,
+};
+
+use crate::api::types::ErrorResponse;
+use crate::error::AuthError;
+use crate::net::client_ip;
+
+/// A CIDR range such as `10.0.0.0/8` or `2001:db8::/32`.
+///
+/// A bare address parses as a single-host range (`/32` or `/128`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Check whether `ip` falls inside the range.
+    ///
+    /// IPv4-mapped IPv6 addresses (`::ffff:10.0.0.1`) match IPv4 ranges.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                prefix_matches(u32::from(network).into(), u32::from(ip).into(), self.prefix_len, 32)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                prefix_matches(u128::from(network), u128::from(ip), self.prefix_len, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Compare the leading `prefix_len` of `bits`-wide addresses.
+fn prefix_matches(network: u128, ip: u128, prefix_len: u8, bits: u8) -> bool {
+    let host_bits = u32::from(bits - prefix_len);
+    network.checked_shr(host_bits).unwrap_or(0) == ip.checked_shr(host_bits).unwrap_or(0)
+}
+
+impl FromStr for IpCidr {
+    type Err = AuthError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || AuthError::config(format!("Invalid CIDR range: '{}'", s));
+        let (address, prefix) = match s.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (s.trim(), None),
+        };
+
+        let network: IpAddr = address.parse().map_err(|_| invalid())?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix {
+            Some(prefix) => prefix.parse::<u8>().map_err(|_| invalid())?,
+            None => max_len,
+        };
+        if prefix_len > max_len {
+            return Err(invalid());
+        }
+
+        Ok(Self { network, prefix_len })
+    }
+}
+
+/// Middleware that only admits clients from trusted networks.
+///
+/// Requests whose client IP is outside every range, or unknown, get 403
+/// Forbidden without reaching the wrapped endpoint. The client IP is
+/// resolved with [`client_ip`]; forwarded headers are ignored unless
+/// [`with_trust_forwarded`](Self::with_trust_forwarded) is enabled.
+///
+/// # Example
+///
+/// ```ignore
+/// use poem::{post, EndpointExt, Route};
+/// use poem_auth::middleware::IpAllowlist;
+///
+/// let allowlist = IpAllowlist::new(&["10.0.0.0/8", "fd00::/8"])?;
+/// let app = Route::new().nest(
+///     "/admin",
+///     Route::new().at("/users", post(create_user)).with(allowlist),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct IpAllowlist {
+    ranges: Vec<IpCidr>,
+    trust_forwarded: bool,
+}
+
+impl IpAllowlist {
+    /// Create an allowlist from CIDR strings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::ConfigError` if any range can't be parsed.
+    pub fn new<S: AsRef<str>>(ranges: &[S]) -> std::result::Result<Self, AuthError> {
+        let ranges = ranges
+            .iter()
+            .map(|range| range.as_ref().parse())
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Self {
+            ranges,
+            trust_forwarded: false,
+        })
+    }
+
+    /// Resolve the client IP from `X-Forwarded-For`/`X-Real-IP` (default: false).
+    ///
+    /// Only enable behind a reverse proxy that overwrites these headers.
+    pub fn with_trust_forwarded(mut self, trust: bool) -> Self {
+        self.trust_forwarded = trust;
+        self
+    }
+
+    /// Check whether `ip` falls inside any allowed range.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(ip))
+    }
+
+    /// The allowed ranges.
+    pub fn ranges(&self) -> &[IpCidr] {
+        &self.ranges
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for IpAllowlist {
+    type Output = IpAllowlistEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        IpAllowlistEndpoint {
+            inner: ep,
+            allowlist: self.clone(),
+        }
+    }
+}
+
+/// Endpoint produced by [`IpAllowlist`]
+#[derive(Debug)]
+pub struct IpAllowlistEndpoint<E> {
+    inner: E,
+    allowlist: IpAllowlist,
+}
+
+impl<E: Endpoint> Endpoint for IpAllowlistEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        match client_ip(&req, self.allowlist.trust_forwarded) {
+            Some(ip) if self.allowlist.contains(&ip) => self.inner.call(req).await,
+            ip => {
+                tracing::warn!(client_ip = ?ip, "Rejected request from outside the IP allowlist");
+                let body = Json(ErrorResponse::forbidden("Client address is not allowed"));
+                Err(PoemError::from_response((StatusCode::FORBIDDEN, body).into_response()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poem::{handler, EndpointExt};
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cidr_parsing_and_matching() {
+        let v4: IpCidr = "10.1.0.0/16".parse().unwrap();
+        assert!(v4.contains(&ip("10.1.255.7")));
+        assert!(!v4.contains(&ip("10.2.0.1")));
+        assert!(v4.contains(&ip("::ffff:10.1.0.9")));
+
+        let host: IpCidr = "192.0.2.5".parse().unwrap();
+        assert!(host.contains(&ip("192.0.2.5")));
+        assert!(!host.contains(&ip("192.0.2.6")));
+
+        let any: IpCidr = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains(&ip("203.0.113.1")));
+        assert!(!any.contains(&ip("2001:db8::1")));
+
+        for invalid in ["10.0.0.0/33", "2001:db8::/129", "10.0.0/8", "fd00::/x", ""] {
+            assert!(invalid.parse::<IpCidr>().is_err(), "{} accepted", invalid);
+        }
+    }
+
+    #[test]
+    fn test_ipv6_ranges() {
+        let allowlist = IpAllowlist::new(&["2001:db8:abcd::/48", "10.0.0.0/8"]).unwrap();
+        assert!(allowlist.contains(&ip("2001:db8:abcd:12::1")));
+        assert!(!allowlist.contains(&ip("2001:db8:abce::1")));
+        assert!(!allowlist.contains(&ip("fe80::1")));
+        assert!(IpAllowlist::new(&["not-a-range"]).is_err());
+    }
+
+    #[handler]
+    fn admin() -> &'static str {
+        "admin"
+    }
+
+    #[tokio::test]
+    async fn test_middleware_rejects_outside_addresses() {
+        let allowlist = IpAllowlist::new(&["2001:db8:abcd::/48"])
+            .unwrap()
+            .with_trust_forwarded(true);
+        let ep = admin.with(allowlist);
+        let from = |addr: &str| Request::builder().header("X-Forwarded-For", addr).finish();
+
+        let resp = ep.get_response(from("2001:db8:abcd::42")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.into_body().into_string().await.unwrap(), "admin");
+
+        let resp = ep.get_response(from("2001:db8:ffff::42")).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let body: serde_json::Value = resp.into_body().into_json().await.unwrap();
+        assert_eq!(body["error"], "forbidden");
+
+        // A client can't get in by prepending an allowed address
+        let resp = ep.get_response(from("2001:db8:abcd::42, 2001:db8:ffff::42")).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // Unknown client addresses are rejected too
+        let resp = ep.get_response(Request::builder().finish()).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -38,11 +38,7 @@ pub async fn extract_jwt_claims(
         .and_then(|h| h.to_str().ok())
         .ok_or(AuthError::InvalidToken)?;
 
-    // Extract token from "Bearer <token>" format
-    let token = JwtValidator::extract_token(auth_header)?;
-
-    // Validate and decode token
-    validator.verify_token(token)
+    validator.verify_from_header(auth_header)
 }
 
 #[cfg(test)]
@@ -58,4 +54,25 @@ mod tests {
         let token = validator.generate_token(&claims).unwrap();
         assert!(!token.token.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_extract_jwt_claims() {
+        let validator = JwtValidator::new("my-very-long-secret-key").unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let claims = UserClaims::new("alice", "local", now + 3600, now);
+        let token = validator.generate_token(&claims).unwrap().token;
+
+        let req = Request::builder()
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .finish();
+        assert_eq!(extract_jwt_claims(&req, &validator).await.unwrap().sub, "alice");
+
+        let req = Request::builder().header(AUTHORIZATION, token).finish();
+        assert!(matches!(
+            extract_jwt_claims(&req, &validator).await,
+            Err(AuthError::InvalidToken)
+        ));
+        let req = Request::builder().finish();
+        assert!(extract_jwt_claims(&req, &validator).await.is_err());
+    }
 }
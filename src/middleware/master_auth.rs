@@ -3,19 +3,38 @@
 //! Provides authentication using a master username/password pair to protect
 //! administrative operations like user creation, deletion, and configuration changes.
 
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use poem::http::{header, StatusCode};
+use poem::web::headers::{authorization::Basic, Authorization, HeaderMapExt};
+use poem::{Error as PoemError, FromRequest, Request, RequestBody, Response};
+
+use crate::audit::{AuditEvent, AuditLog};
+use crate::db::UserDatabase;
 use crate::error::AuthError;
 use crate::password;
 
+/// Provider name recorded in master auth audit events.
+const MASTER_PROVIDER: &str = "master";
+
 /// Master credentials for administrative access.
 ///
 /// Used to authenticate requests to protected administrative endpoints.
 /// The master password hash is stored securely and compared using constant-time comparison.
 ///
+/// As a handler parameter it extracts HTTP Basic credentials and validates
+/// them with the [`MasterAuth`] attached to the route via `.data(...)`, so
+/// the handler only runs for the master user.
+///
 /// # Example
 ///
 /// ```ignore
 /// use poem_auth::middleware::MasterCredentials;
 ///
+/// let app = Route::new().at("/admin", get(admin_handler)).data(master_auth);
+///
+/// #[handler]
 /// async fn admin_handler(creds: MasterCredentials) -> impl IntoResponse {
 ///     // Only reached if master auth succeeded
 ///     format!("Admin authenticated as: {}", creds.username)
@@ -29,11 +48,46 @@ pub struct MasterCredentials {
 
 impl MasterCredentials {
     /// Create new master credentials.
+    ///
+    /// This doesn't authenticate anything; get verified credentials from the
+    /// extractor or [`MasterAuth::validate`].
     pub fn new(username: String) -> Self {
         Self { username }
     }
 }
 
+/// Validates HTTP Basic credentials with the route's [`MasterAuth`] data.
+///
+/// Attempts are recorded as with [`MasterAuth::validate_audited`], using the
+/// peer address. Missing or wrong credentials are rejected with 401 and a
+/// `Basic` challenge; a route without `MasterAuth` data fails with 500.
+impl<'a> FromRequest<'a> for MasterCredentials {
+    async fn from_request(req: &'a Request, _body: &mut RequestBody) -> Result<Self, PoemError> {
+        let Some(master) = req.data::<MasterAuth>() else {
+            tracing::error!("MasterCredentials extracted on a route without MasterAuth data");
+            return Err(PoemError::from_status(StatusCode::INTERNAL_SERVER_ERROR));
+        };
+        let Some(Authorization(basic)) = req.headers().typed_get::<Authorization<Basic>>() else {
+            return Err(basic_challenge());
+        };
+
+        let ip = crate::net::client_ip(req, false);
+        master
+            .validate_audited(basic.username(), basic.password(), ip)
+            .await
+            .map_err(|_| basic_challenge())
+    }
+}
+
+fn basic_challenge() -> PoemError {
+    PoemError::from_response(
+        Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header(header::WWW_AUTHENTICATE, "Basic realm=\"master\"")
+            .finish(),
+    )
+}
+
 /// Master authentication validator.
 ///
 /// Validates that provided credentials match the stored master password hash.
@@ -58,6 +112,8 @@ pub struct MasterAuth {
     username: String,
     /// Argon2 hash of the master password
     password_hash: String,
+    /// Where `validate_audited` records attempts, if anywhere
+    audit_log: Option<Arc<dyn AuditLog>>,
 }
 
 impl MasterAuth {
@@ -78,9 +134,23 @@ impl MasterAuth {
         Self {
             username: username.to_string(),
             password_hash: password_hash.to_string(),
+            audit_log: None,
         }
     }
 
+    /// Record master auth attempts made through `validate_audited` in `audit_log`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let db = Arc::new(SqliteUserDb::new("users.db").await?);
+    /// let master_auth = MasterAuth::new("admin", &master_hash).with_audit_log(db);
+    /// ```
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLog>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
     /// Validate master credentials.
     ///
     /// Checks that the provided username and password match the master credentials.
@@ -112,6 +182,88 @@ impl MasterAuth {
         Ok(MasterCredentials::new(self.username.clone()))
     }
 
+    /// Validate master credentials and record the attempt in the audit log.
+    ///
+    /// Behaves like [`validate`](Self::validate), and additionally records a
+    /// `master_auth_success` or `master_auth_failure` event with provider
+    /// `master`, the attempted username and `client_ip` when an audit log is
+    /// configured. Failing to record only logs a warning, so an unavailable
+    /// audit store doesn't lock administrators out.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let ip = poem_auth::net::client_ip(req, true);
+    /// let creds = master_auth.validate_audited("admin", &password, ip).await?;
+    /// ```
+    pub async fn validate_audited(
+        &self,
+        username: &str,
+        password: &str,
+        client_ip: Option<IpAddr>,
+    ) -> Result<MasterCredentials, AuthError> {
+        let result = self.validate(username, password);
+
+        if let Some(audit_log) = &self.audit_log {
+            let event_type = match &result {
+                Ok(_) => "master_auth_success",
+                Err(_) => "master_auth_failure",
+            };
+            let event = AuditEvent::new(event_type, MASTER_PROVIDER)
+                .with_username(username)
+                .with_ip_address(client_ip);
+            if let Err(e) = audit_log.record(event).await {
+                tracing::warn!(error = %e, "Failed to record master auth audit event");
+            }
+        }
+
+        result
+    }
+
+    /// Replace a user's groups on behalf of an authenticated administrator.
+    ///
+    /// Calls [`UserDatabase::update_groups`] and, when an audit log is
+    /// configured, records a `groups_changed` event for `username` whose
+    /// details are a JSON object with the acting master username (`actor`)
+    /// and the groups before and after the change (`old_groups`,
+    /// `new_groups`). As with `validate_audited`, failing to record only logs
+    /// a warning.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let creds = master_auth.validate_audited("admin", &password, ip).await?;
+    /// master_auth
+    ///     .update_groups_audited(&creds, db.as_ref(), "alice", vec!["admins".into()])
+    ///     .await?;
+    /// ```
+    pub async fn update_groups_audited(
+        &self,
+        actor: &MasterCredentials,
+        db: &dyn UserDatabase,
+        username: &str,
+        groups: Vec<String>,
+    ) -> Result<(), AuthError> {
+        let old_groups = db.get_user(username).await?.groups;
+        db.update_groups(username, groups.clone()).await?;
+
+        if let Some(audit_log) = &self.audit_log {
+            let details = serde_json::json!({
+                "actor": actor.username,
+                "old_groups": old_groups,
+                "new_groups": groups,
+            });
+            let event = AuditEvent::new("groups_changed", MASTER_PROVIDER)
+                .with_username(username)
+                .with_details(details.to_string());
+            if let Err(e) = audit_log.record(event).await {
+                tracing::warn!(error = %e, "Failed to record group change audit event");
+            }
+        }
+
+        Ok(())
+    }
+
     /// Get the master username.
     pub fn username(&self) -> &str {
         &self.username
@@ -121,6 +273,22 @@ impl MasterAuth {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Audit log that keeps events in memory.
+    #[derive(Debug, Default)]
+    struct RecordingAuditLog {
+        events: Mutex<Vec<AuditEvent>>,
+    }
+
+    #[async_trait]
+    impl AuditLog for RecordingAuditLog {
+        async fn record(&self, event: AuditEvent) -> Result<(), AuthError> {
+            self.events.lock().unwrap().push(event);
+            Ok(())
+        }
+    }
 
     #[test]
     fn test_master_credentials_creation() {
@@ -177,6 +345,101 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_validate_audited_records_outcome() {
+        let hash = crate::password::hash_password("my-secure-master-password").unwrap();
+        let audit_log = Arc::new(RecordingAuditLog::default());
+        let auth = MasterAuth::new("admin", &hash).with_audit_log(audit_log.clone());
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+
+        let result = auth.validate_audited("admin", "wrong-password", Some(ip)).await;
+        assert!(result.is_err());
+        auth.validate_audited("admin", "my-secure-master-password", None)
+            .await
+            .unwrap();
+
+        let events = audit_log.events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type, "master_auth_failure");
+        assert_eq!(events[0].username.as_deref(), Some("admin"));
+        assert_eq!(events[0].provider, "master");
+        assert_eq!(events[0].ip_address, Some(ip));
+        assert_eq!(events[1].event_type, "master_auth_success");
+    }
+
+    #[tokio::test]
+    async fn test_update_groups_audited_records_diff() {
+        use crate::db::{MemoryUserDb, UserRecord};
+
+        let hash = crate::password::hash_password("my-secure-master-password").unwrap();
+        let audit_log = Arc::new(RecordingAuditLog::default());
+        let auth = MasterAuth::new("admin", &hash).with_audit_log(audit_log.clone());
+        let creds = auth.validate("admin", "my-secure-master-password").unwrap();
+
+        let db = MemoryUserDb::new();
+        db.create_user(UserRecord::new("alice", "hash").with_groups(vec!["users", "ops"]))
+            .await
+            .unwrap();
+
+        auth.update_groups_audited(&creds, &db, "alice", vec!["users".into(), "admins".into()])
+            .await
+            .unwrap();
+        assert_eq!(db.get_user("alice").await.unwrap().groups, vec!["users", "admins"]);
+
+        let events = audit_log.events.lock().unwrap().clone();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, "groups_changed");
+        assert_eq!(events[0].username.as_deref(), Some("alice"));
+        let details: serde_json::Value =
+            serde_json::from_str(events[0].details.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            details,
+            serde_json::json!({
+                "actor": "admin",
+                "old_groups": ["users", "ops"],
+                "new_groups": ["users", "admins"],
+            })
+        );
+
+        // Unknown users fail without recording anything
+        let result = auth.update_groups_audited(&creds, &db, "ghost", vec![]).await;
+        assert!(matches!(result, Err(AuthError::UserNotFound)));
+        assert_eq!(audit_log.events.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_master_credentials_extractor() {
+        use base64::Engine;
+        use poem::{get, handler, Endpoint, EndpointExt, Route};
+
+        #[handler]
+        fn admin(creds: MasterCredentials) -> String {
+            creds.username
+        }
+
+        let hash = crate::password::hash_password("master-pass").unwrap();
+        let app = Route::new().at("/admin", get(admin)).data(MasterAuth::new("admin", &hash));
+        let basic = |user: &str, pass: &str| {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(format!("{user}:{pass}"));
+            Request::builder().uri_str("/admin").header(header::AUTHORIZATION, format!("Basic {encoded}")).finish()
+        };
+
+        let response = app.get_response(Request::builder().uri_str("/admin").finish()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Basic realm=\"master\"");
+
+        let response = app.get_response(basic("admin", "wrong")).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.get_response(basic("admin", "master-pass")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.into_body().into_string().await.unwrap(), "admin");
+
+        let unconfigured = Route::new().at("/admin", get(admin));
+        let response = unconfigured.get_response(basic("admin", "master-pass")).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
     #[test]
     fn test_master_auth_different_instances_compatible() {
         let test_password = "shared-password";
@@ -1,15 +1,59 @@
 //! Rate limiting middleware for protecting authentication endpoints.
 //!
-//! Provides IP-based rate limiting to prevent brute force attacks on login
+//! Provides rate limiting to prevent brute force attacks on login
 //! and other sensitive endpoints.
 
 #[cfg(feature = "rate-limit")]
-use governor::{Quota, RateLimiter, state::NotKeyed, state::InMemoryState, clock::DefaultClock};
+use governor::{Quota, RateLimiter, state::NotKeyed, state::InMemoryState, clock::{Clock, DefaultClock}};
+use poem::{http::{header, StatusCode}, web::Json, IntoResponse, Response};
+use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
+#[cfg(feature = "rate-limit")]
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use crate::api::types::ErrorResponse;
+use crate::error::AuthError;
+
+/// Whole seconds to advertise for a wait: rounded up, at least 1.
+fn retry_after_secs(wait: Duration) -> u64 {
+    let secs = wait.as_secs();
+    if wait.subsec_nanos() > 0 || secs == 0 {
+        secs + 1
+    } else {
+        secs
+    }
+}
+
+/// Build a 429 Too Many Requests response for a rate-limited request.
+///
+/// Sets `Retry-After` to the wait time in whole seconds (rounded up, at
+/// least 1) and returns an [`ErrorResponse`] body with code
+/// `rate_limit_exceeded`.
+///
+/// # Example
+///
+/// ```ignore
+/// if let Err(wait) = limiter.check_auth_limit_with_retry(&ip) {
+///     return rate_limited_response(wait);
+/// }
+/// ```
+pub fn rate_limited_response(retry_after: Duration) -> Response {
+    let secs = retry_after_secs(retry_after);
+
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(ErrorResponse::rate_limit_exceeded(secs)),
+    )
+        .into_response();
+    response.headers_mut().insert(header::RETRY_AFTER, secs.into());
+    response
+}
 
 /// Rate limiter configuration.
 ///
-/// Controls how rate limiting is applied to endpoints.
+/// Controls how rate limiting is applied to endpoints. Can be loaded from the
+/// `[rate_limit]` section of the TOML config; omitted fields use the defaults.
 ///
 /// # Example
 ///
@@ -20,7 +64,8 @@ use std::net::IpAddr;
 ///     .with_requests_per_minute(100)
 ///     .with_auth_endpoint_limit(5);
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RateLimitConfig {
     /// Maximum requests per minute for general endpoints
     pub requests_per_minute: u32,
@@ -29,6 +74,10 @@ pub struct RateLimitConfig {
     pub auth_endpoint_limit: u32,
 
     /// Whether to use key-based (IP address) rate limiting
+    ///
+    /// Accepted but not enforced yet: [`RateLimit`] applies each limit
+    /// globally, across all clients, whatever this is set to.
+    #[serde(rename = "key_based")]
     pub use_key_based: bool,
 }
 
@@ -55,10 +104,24 @@ impl RateLimitConfig {
     }
 
     /// Enable or disable key-based rate limiting (by IP).
+    ///
+    /// Not enforced yet; see [`use_key_based`](Self::use_key_based).
     pub fn with_key_based(mut self, enabled: bool) -> Self {
         self.use_key_based = enabled;
         self
     }
+
+    /// Check that both limits are non-zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::ConfigError` if either limit is zero.
+    pub fn validate(&self) -> Result<(), AuthError> {
+        if self.requests_per_minute == 0 || self.auth_endpoint_limit == 0 {
+            return Err(AuthError::config("Rate limits must be greater than zero"));
+        }
+        Ok(())
+    }
 }
 
 impl Default for RateLimitConfig {
@@ -73,8 +136,9 @@ impl Default for RateLimitConfig {
 
 /// Rate limiter for protecting endpoints from brute force attacks.
 ///
-/// Uses IP address-based rate limiting with configurable limits for
-/// general and authentication endpoints.
+/// Applies configurable limits for general and authentication endpoints.
+/// The limits are global: the client IP is accepted by the checks but not
+/// used yet, so one noisy client can exhaust a limit for everyone.
 ///
 /// # Example
 ///
@@ -86,10 +150,10 @@ impl Default for RateLimitConfig {
 /// let limiter = RateLimit::new(config);
 ///
 /// let ip = "192.168.1.1".parse()?;
-/// if limiter.check_auth_limit(&ip).is_ok() {
-///     // Allow authentication attempt
-/// } else {
-///     // Reject due to rate limit
+/// match limiter.check_auth_limit(&ip) {
+///     Ok(()) => { /* allow authentication attempt */ }
+///     Err(AuthError::RateLimitExceeded(secs)) => { /* reject, retry in `secs` */ }
+///     Err(_) => unreachable!(),
 /// }
 /// ```
 #[cfg(feature = "rate-limit")]
@@ -102,21 +166,34 @@ pub struct RateLimit {
 #[cfg(feature = "rate-limit")]
 impl RateLimit {
     /// Create a new rate limiter with the given configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either limit is zero; use [`try_new`](Self::try_new) for
+    /// limits that aren't known to be valid.
     pub fn new(config: RateLimitConfig) -> Self {
-        // Create limiters: governor uses number-per-period, so we calculate for 1 minute
-        let general = RateLimiter::direct(
-            Quota::per_minute(std::num::NonZeroU32::new(config.requests_per_minute).unwrap()),
-        );
+        Self::try_new(config).expect("rate limits must be greater than zero")
+    }
+
+    /// Create a new rate limiter, rejecting zero limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::ConfigError` if either limit is zero.
+    pub fn try_new(config: RateLimitConfig) -> Result<Self, AuthError> {
+        config.validate()?;
 
-        let auth = RateLimiter::direct(
-            Quota::per_minute(std::num::NonZeroU32::new(config.auth_endpoint_limit).unwrap()),
-        );
+        // Create limiters: governor uses number-per-period, so we calculate for 1 minute
+        // (validate rejected zero limits, so the fallback is never used)
+        let per_minute = |limit| Quota::per_minute(NonZeroU32::new(limit).unwrap_or(NonZeroU32::MIN));
+        let general = RateLimiter::direct(per_minute(config.requests_per_minute));
+        let auth = RateLimiter::direct(per_minute(config.auth_endpoint_limit));
 
-        Self {
+        Ok(Self {
             config,
             general_limiter: std::sync::Mutex::new(general),
             auth_limiter: std::sync::Mutex::new(auth),
-        }
+        })
     }
 
     /// Create a new rate limiter with default configuration.
@@ -126,21 +203,62 @@ impl RateLimit {
 
     /// Check if a general endpoint request from an IP is allowed.
     ///
-    /// Returns `Ok(())` if the request is allowed, or `Err(())` if rate limited.
-    pub fn check_general_limit(&self, _ip: &IpAddr) -> Result<(), ()> {
+    /// Returns `Ok(())` if the request is allowed, or
+    /// [`AuthError::RateLimitExceeded`] with the seconds to wait if rate limited.
+    pub fn check_general_limit(&self, ip: &IpAddr) -> Result<(), AuthError> {
         // Per-IP limiting would require a more complex implementation with per-key limits
         // For now, use a simple global limiter
-        let limiter = self.general_limiter.lock().unwrap();
-        limiter.check().map_err(|_| ())
+        self.check_general_limit_with_retry(ip)
+            .map_err(|wait| AuthError::RateLimitExceeded(retry_after_secs(wait)))
     }
 
     /// Check if an auth endpoint request from an IP is allowed.
     ///
-    /// Returns `Ok(())` if the request is allowed, or `Err(())` if rate limited.
+    /// Returns `Ok(())` if the request is allowed, or
+    /// [`AuthError::RateLimitExceeded`] with the seconds to wait if rate limited.
     /// Auth endpoints have stricter limits.
-    pub fn check_auth_limit(&self, _ip: &IpAddr) -> Result<(), ()> {
+    pub fn check_auth_limit(&self, ip: &IpAddr) -> Result<(), AuthError> {
+        self.check_auth_limit_with_retry(ip)
+            .map_err(|wait| AuthError::RateLimitExceeded(retry_after_secs(wait)))
+    }
+
+    /// Check a general endpoint request, returning how long to wait if limited.
+    pub fn check_general_limit_with_retry(&self, _ip: &IpAddr) -> Result<(), Duration> {
+        let limiter = self.general_limiter.lock().unwrap();
+        limiter.check().map_err(|not_until| {
+            crate::telemetry::record_rate_limited("general");
+            not_until.wait_time_from(DefaultClock::default().now())
+        })
+    }
+
+    /// Check an auth endpoint request, returning how long to wait if limited.
+    ///
+    /// The wait is the time until governor next has a token available;
+    /// pass it to [`rate_limited_response`] to build the 429 response.
+    pub fn check_auth_limit_with_retry(&self, _ip: &IpAddr) -> Result<(), Duration> {
         let limiter = self.auth_limiter.lock().unwrap();
-        limiter.check().map_err(|_| ())
+        limiter.check().map_err(|not_until| {
+            crate::telemetry::record_rate_limited("auth");
+            not_until.wait_time_from(DefaultClock::default().now())
+        })
+    }
+
+    /// Enforce the auth endpoint limit, producing a ready-made 429 response.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// #[handler]
+    /// async fn login(addr: &RemoteAddr, ...) -> Response {
+    ///     if let Err(resp) = limiter.enforce_auth_limit(&ip) {
+    ///         return resp;
+    ///     }
+    ///     // ... authenticate
+    /// }
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn enforce_auth_limit(&self, ip: &IpAddr) -> Result<(), Response> {
+        self.check_auth_limit_with_retry(ip).map_err(rate_limited_response)
     }
 
     /// Get the configuration.
@@ -178,18 +296,44 @@ impl RateLimit {
         Self { config }
     }
 
+    /// Create a new rate limiter, rejecting zero limits like the real one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::ConfigError` if either limit is zero.
+    pub fn try_new(config: RateLimitConfig) -> Result<Self, AuthError> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+
     /// Create a new rate limiter with default configuration (no-op when feature disabled).
     pub fn default_config() -> Self {
         Self::new(RateLimitConfig::default())
     }
 
     /// Check general limit (always allows when feature disabled).
-    pub fn check_general_limit(&self, _ip: &IpAddr) -> Result<(), ()> {
+    pub fn check_general_limit(&self, _ip: &IpAddr) -> Result<(), AuthError> {
         Ok(())
     }
 
     /// Check auth limit (always allows when feature disabled).
-    pub fn check_auth_limit(&self, _ip: &IpAddr) -> Result<(), ()> {
+    pub fn check_auth_limit(&self, _ip: &IpAddr) -> Result<(), AuthError> {
+        Ok(())
+    }
+
+    /// Check general limit with retry time (always allows when feature disabled).
+    pub fn check_general_limit_with_retry(&self, _ip: &IpAddr) -> Result<(), Duration> {
+        Ok(())
+    }
+
+    /// Check auth limit with retry time (always allows when feature disabled).
+    pub fn check_auth_limit_with_retry(&self, _ip: &IpAddr) -> Result<(), Duration> {
+        Ok(())
+    }
+
+    /// Enforce the auth limit (always allows when feature disabled).
+    #[allow(clippy::result_large_err)]
+    pub fn enforce_auth_limit(&self, _ip: &IpAddr) -> Result<(), Response> {
         Ok(())
     }
 
@@ -223,6 +367,13 @@ mod tests {
         assert!(config.use_key_based);
     }
 
+    #[test]
+    fn test_zero_limits_are_rejected() {
+        assert!(RateLimit::try_new(RateLimitConfig::new(0, 5)).is_err());
+        assert!(RateLimit::try_new(RateLimitConfig::new(100, 0)).is_err());
+        assert!(RateLimit::try_new(RateLimitConfig::new(100, 5)).is_ok());
+    }
+
     #[test]
     fn test_rate_limit_config_builder() {
         let config = RateLimitConfig::default()
@@ -269,6 +420,63 @@ mod tests {
         assert!(limiter.check_auth_limit(&ip).is_err());
     }
 
+    #[test]
+    fn test_rejection_carries_retry_after() {
+        let limiter = RateLimit::new(RateLimitConfig::new(1, 1));
+        let ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        assert!(limiter.check_auth_limit(&ip).is_ok());
+        match limiter.check_auth_limit(&ip) {
+            // 1 per minute replenishes after up to 60 seconds
+            Err(AuthError::RateLimitExceeded(secs)) => assert!(secs > 0 && secs <= 60),
+            other => panic!("expected RateLimitExceeded, got {:?}", other),
+        }
+
+        assert!(limiter.check_general_limit(&ip).is_ok());
+        assert!(matches!(
+            limiter.check_general_limit(&ip),
+            Err(AuthError::RateLimitExceeded(secs)) if secs > 0
+        ));
+    }
+
+    #[test]
+    fn test_exhausted_quota_sets_retry_after() {
+        let config = RateLimitConfig::new(2, 2);
+        let limiter = RateLimit::new(config);
+        let ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        assert!(limiter.enforce_auth_limit(&ip).is_ok());
+        assert!(limiter.enforce_auth_limit(&ip).is_ok());
+
+        let response = limiter.enforce_auth_limit(&ip).unwrap_err();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        // 2 per minute replenishes one token every 30 seconds
+        assert!(retry_after > 0 && retry_after <= 30);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_response() {
+        let response = rate_limited_response(Duration::from_millis(2500));
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "3");
+
+        let body: ErrorResponse =
+            serde_json::from_slice(&response.into_body().into_vec().await.unwrap()).unwrap();
+        assert_eq!(body.error, "rate_limit_exceeded");
+
+        // Sub-second waits still advertise a positive delay
+        let response = rate_limited_response(Duration::ZERO);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "1");
+    }
+
     #[test]
     fn test_general_vs_auth_limits() {
         let config = RateLimitConfig::new(100, 5);
@@ -315,6 +523,16 @@ mod tests_disabled {
         }
     }
 
+    #[test]
+    fn test_rate_limit_stub_enforce_allows() {
+        let limiter = RateLimit::new(RateLimitConfig::new(1, 1));
+        let ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        for _ in 0..10 {
+            assert!(limiter.enforce_auth_limit(&ip).is_ok());
+        }
+    }
+
     #[test]
     fn test_rate_limit_stub_debug() {
         let config = RateLimitConfig::default();
@@ -0,0 +1,97 @@
+//! Client network helpers shared by rate limiting, audit logging and IP allowlists.
+
+use std::net::IpAddr;
+
+use poem::Request;
+
+/// Resolve the client IP address for a request.
+///
+/// With `trust_forwarded`, the right-most `X-Forwarded-For` entry wins, then
+/// `X-Real-IP`, then the peer address. The right-most entry is the one the
+/// nearest proxy appended; entries to its left come from the client and can
+/// be forged. Only enable it behind exactly one reverse proxy that appends to
+/// `X-Forwarded-For` (or sets `X-Real-IP`); otherwise clients can spoof their
+/// address. Without it, the headers are ignored and the peer address is used.
+///
+/// Returns `None` when no valid address is available (e.g. a Unix socket peer).
+///
+/// # Example
+///
+/// ```ignore
+/// use poem_auth::net::client_ip;
+///
+/// #[handler]
+/// async fn login(req: &Request) -> Response {
+///     let ip = client_ip(req, true).unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+///     // ...
+/// }
+/// ```
+pub fn client_ip(req: &Request, trust_forwarded: bool) -> Option<IpAddr> {
+    if trust_forwarded {
+        let forwarded = req
+            .header("X-Forwarded-For")
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|last| last.trim().parse().ok());
+        let real_ip = || {
+            req.header("X-Real-IP")
+                .and_then(|value| value.trim().parse().ok())
+        };
+
+        if let Some(ip) = forwarded.or_else(real_ip) {
+            return Some(ip);
+        }
+    }
+
+    req.remote_addr().as_socket_addr().map(|addr| addr.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poem::web::{LocalAddr, RemoteAddr};
+    use poem::http::uri::Scheme;
+    use poem::{Addr, Body, RequestParts};
+
+    const PEER: &str = "10.0.0.1";
+
+    fn request(headers: &[(&str, &str)]) -> Request {
+        let mut builder = poem::http::Request::builder();
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let (parts, _) = builder.body(()).unwrap().into_parts();
+        let peer = RemoteAddr(Addr::SocketAddr(format!("{}:4000", PEER).parse().unwrap()));
+        let parts = RequestParts::from((parts, LocalAddr::default(), peer, Scheme::HTTP));
+        Request::from_parts(parts, Body::empty())
+    }
+
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_forwarded_trusted() {
+        // Only the entry appended by the proxy counts, not what the client sent
+        let req = request(&[("X-Forwarded-For", "198.51.100.1, 203.0.113.7")]);
+        assert_eq!(client_ip(&req, true), ip("203.0.113.7"));
+
+        let req = request(&[("X-Real-IP", "2001:db8::1")]);
+        assert_eq!(client_ip(&req, true), ip("2001:db8::1"));
+
+        // An unparsable X-Forwarded-For falls through to X-Real-IP
+        let req = request(&[("X-Forwarded-For", "unknown"), ("X-Real-IP", "203.0.113.8")]);
+        assert_eq!(client_ip(&req, true), ip("203.0.113.8"));
+    }
+
+    #[test]
+    fn test_forwarded_untrusted() {
+        let req = request(&[("X-Forwarded-For", "203.0.113.7"), ("X-Real-IP", "203.0.113.8")]);
+        assert_eq!(client_ip(&req, false), ip(PEER));
+    }
+
+    #[test]
+    fn test_missing_headers() {
+        assert_eq!(client_ip(&request(&[]), true), ip(PEER));
+        assert_eq!(client_ip(&Request::builder().finish(), true), None);
+    }
+}
@@ -3,13 +3,61 @@
 //! This module provides secure password hashing using Argon2id.
 //! All passwords should be hashed before storage.
 
-use argon2::{Argon2, PasswordHasher, PasswordHash, PasswordVerifier};
+use argon2::{Argon2, PasswordHash, PasswordHasher as _, PasswordVerifier};
 use argon2::password_hash::SaltString;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
 use rand::rngs::OsRng;
+use sha2::Sha256;
 
 use crate::error::AuthError;
 
-/// Hash a password using Argon2id.
+/// Password hashing algorithm used for stored credentials.
+///
+/// [`Argon2Hasher`] is the default. Implement this to use another algorithm
+/// (e.g. to verify hashes migrated from a legacy system) or to inject a cheap
+/// mock in tests, then pass it to
+/// [`LocalAuthProvider::with_hasher`](crate::providers::LocalAuthProvider::with_hasher).
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Debug)]
+/// struct BcryptHasher;
+///
+/// impl PasswordHasher for BcryptHasher {
+///     fn hash(&self, password: &str) -> Result<String, AuthError> {
+///         bcrypt::hash(password, 12).map_err(|e| AuthError::PasswordValidationError(e.to_string()))
+///     }
+///
+///     fn verify(&self, password: &str, hash: &str) -> Result<(), AuthError> {
+///         match bcrypt::verify(password, hash) {
+///             Ok(true) => Ok(()),
+///             _ => Err(AuthError::InvalidCredentials),
+///         }
+///     }
+/// }
+/// ```
+pub trait PasswordHasher: Send + Sync + std::fmt::Debug {
+    /// Hash a plaintext password for storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::PasswordValidationError` if the password is
+    /// rejected or hashing fails.
+    fn hash(&self, password: &str) -> Result<String, AuthError>;
+
+    /// Verify a plaintext password against a stored hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::InvalidCredentials` if the password doesn't match,
+    /// or `AuthError::PasswordValidationError` if the hash is malformed.
+    fn verify(&self, password: &str, hash: &str) -> Result<(), AuthError>;
+}
+
+/// Argon2id password hasher, the default [`PasswordHasher`].
 ///
 /// Uses secure defaults:
 /// - Algorithm: Argon2id
@@ -17,6 +65,215 @@ use crate::error::AuthError;
 /// - Time cost: 2 iterations
 /// - Parallelism: 1 thread
 ///
+/// Hashes are PHC format strings that include the algorithm, parameters,
+/// salt, and hash.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Argon2Hasher;
+
+/// Reject passwords outside the accepted length range.
+fn check_password_length(password: &str) -> Result<(), AuthError> {
+    if password.is_empty() || password.len() > 128 {
+        return Err(AuthError::PasswordValidationError(
+            "Password must be between 1 and 128 characters".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+impl PasswordHasher for Argon2Hasher {
+    fn hash(&self, password: &str) -> Result<String, AuthError> {
+        check_password_length(password)?;
+
+        // Generate a random salt
+        let salt = SaltString::generate(&mut OsRng);
+
+        // Create Argon2 instance with recommended parameters
+        let argon2 = Argon2::default();
+
+        // Hash the password
+        let password_hash = argon2
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| {
+                AuthError::PasswordValidationError(format!("Failed to hash password: {}", e))
+            })?
+            .to_string();
+
+        Ok(password_hash)
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<(), AuthError> {
+        // Parse the hash
+        let parsed_hash = PasswordHash::new(hash).map_err(|e| {
+            AuthError::PasswordValidationError(format!("Invalid password hash format: {}", e))
+        })?;
+
+        // Create Argon2 instance
+        let argon2 = Argon2::default();
+
+        // Verify the password
+        argon2
+            .verify_password(password.as_bytes(), &parsed_hash)
+            .map_err(|_| AuthError::InvalidCredentials)
+    }
+}
+
+/// Prefix of hashes produced by [`PepperedHasher`], followed by the pepper id.
+const PEPPER_PREFIX: &str = "$pepper$";
+
+/// A [`PasswordHasher`] that mixes a server-side secret (pepper) into passwords.
+///
+/// The password is combined with the pepper using HMAC-SHA256 before it is
+/// hashed or verified by the inner hasher, so a leaked user database can't be
+/// cracked offline without also obtaining the pepper. Keep the pepper outside
+/// the database (environment, keyring or secrets manager).
+///
+/// Stored hashes record the id of the pepper that produced them, e.g.
+/// `$pepper$2024-01$argon2id$v=19$...`, and
+/// [`needs_rehash`](Self::needs_rehash) reports hashes to migrate. Hashes
+/// created before the pepper was enabled are verified by the inner hasher
+/// alone, so existing users can still log in and be rehashed. A hash created
+/// under a pepper this hasher doesn't know fails like a wrong password (and
+/// logs a warning), so callers can't tell it apart from bad credentials. When
+/// changing the pepper, keep the old one with
+/// [`with_previous_pepper`](Self::with_previous_pepper) and rehash passwords
+/// as users log in.
+///
+/// # Example
+///
+/// ```ignore
+/// let pepper = std::env::var("PASSWORD_PEPPER")?;
+/// let hasher = PepperedHasher::new(Argon2Hasher, "2024-01", pepper)?;
+/// let provider = LocalAuthProvider::new(db).with_hasher(Box::new(hasher));
+/// ```
+pub struct PepperedHasher<H = Argon2Hasher> {
+    inner: H,
+    id: String,
+    pepper: Vec<u8>,
+    /// Retired peppers still accepted for verification, by id
+    previous: Vec<(String, Vec<u8>)>,
+}
+
+impl<H: PasswordHasher> PepperedHasher<H> {
+    /// Wrap `inner` with the pepper identified by `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::ConfigError` if the pepper is empty, or the id is
+    /// empty or contains `$`.
+    pub fn new<S: Into<String>, P: AsRef<[u8]>>(inner: H, id: S, pepper: P) -> Result<Self, AuthError> {
+        let (id, pepper) = Self::check_pepper(id.into(), pepper.as_ref())?;
+        Ok(Self {
+            inner,
+            id,
+            pepper,
+            previous: Vec::new(),
+        })
+    }
+
+    /// Keep accepting hashes created under a retired pepper.
+    ///
+    /// New hashes always use the current pepper.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`new`](Self::new).
+    pub fn with_previous_pepper<S: Into<String>, P: AsRef<[u8]>>(
+        mut self,
+        id: S,
+        pepper: P,
+    ) -> Result<Self, AuthError> {
+        self.previous.push(Self::check_pepper(id.into(), pepper.as_ref())?);
+        Ok(self)
+    }
+
+    /// The id of the current pepper.
+    pub fn pepper_id(&self) -> &str {
+        &self.id
+    }
+
+    /// Check whether `hash` wasn't created under the current pepper.
+    ///
+    /// True for unpeppered hashes and hashes from a previous pepper; rehash
+    /// those the next time the user's password is verified.
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        !matches!(split_peppered_hash(hash), Some((id, _)) if id == self.id)
+    }
+
+    fn check_pepper(id: String, pepper: &[u8]) -> Result<(String, Vec<u8>), AuthError> {
+        if id.is_empty() || id.contains('$') {
+            return Err(AuthError::config(format!("Invalid pepper id: '{}'", id)));
+        }
+        if pepper.is_empty() {
+            return Err(AuthError::config("Pepper must not be empty"));
+        }
+        Ok((id, pepper.to_vec()))
+    }
+
+    /// Find the pepper with `id`, current or previous.
+    fn pepper(&self, id: &str) -> Option<&[u8]> {
+        if id == self.id {
+            return Some(&self.pepper);
+        }
+        self.previous
+            .iter()
+            .find(|(previous_id, _)| previous_id == id)
+            .map(|(_, pepper)| pepper.as_slice())
+    }
+}
+
+/// HMAC the password with the pepper, encoded for the inner hasher.
+fn apply_pepper(pepper: &[u8], password: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(pepper).expect("HMAC accepts keys of any length");
+    mac.update(password.as_bytes());
+    STANDARD_NO_PAD.encode(mac.finalize().into_bytes())
+}
+
+/// Split a peppered hash into its pepper id and the inner hash.
+fn split_peppered_hash(hash: &str) -> Option<(&str, &str)> {
+    let rest = hash.strip_prefix(PEPPER_PREFIX)?;
+    let (id, _) = rest.split_once('$')?;
+    Some((id, &rest[id.len()..]))
+}
+
+impl<H: PasswordHasher> PasswordHasher for PepperedHasher<H> {
+    fn hash(&self, password: &str) -> Result<String, AuthError> {
+        check_password_length(password)?;
+        let inner = self.inner.hash(&apply_pepper(&self.pepper, password))?;
+        Ok(format!("{}{}{}", PEPPER_PREFIX, self.id, inner))
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<(), AuthError> {
+        // Hashes from before the pepper was enabled
+        let Some((id, inner)) = split_peppered_hash(hash) else {
+            return self.inner.verify(password, hash);
+        };
+        match self.pepper(id) {
+            Some(pepper) => self.inner.verify(&apply_pepper(pepper, password), inner),
+            None => {
+                tracing::warn!(pepper_id = %id, "Password hash uses an unknown pepper");
+                // Spend the same work as a real check, then fail like a wrong password
+                let _ = self.inner.verify(&apply_pepper(&self.pepper, password), inner);
+                Err(AuthError::InvalidCredentials)
+            }
+        }
+    }
+}
+
+impl<H: std::fmt::Debug> std::fmt::Debug for PepperedHasher<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let previous: Vec<&str> = self.previous.iter().map(|(id, _)| id.as_str()).collect();
+        f.debug_struct("PepperedHasher")
+            .field("inner", &self.inner)
+            .field("id", &self.id)
+            .field("previous", &previous)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Hash a password using Argon2id.
+///
+/// Thin wrapper over [`Argon2Hasher`].
+///
 /// # Arguments
 ///
 /// * `password` - The plaintext password to hash
@@ -38,32 +295,13 @@ use crate::error::AuthError;
 ///
 /// Returns `AuthError::PasswordValidationError` if hashing fails.
 pub fn hash_password(password: &str) -> Result<String, AuthError> {
-    // Validate password length
-    if password.is_empty() || password.len() > 128 {
-        return Err(AuthError::PasswordValidationError(
-            "Password must be between 1 and 128 characters".to_string(),
-        ));
-    }
-
-    // Generate a random salt
-    let salt = SaltString::generate(&mut OsRng);
-
-    // Create Argon2 instance with recommended parameters
-    let argon2 = Argon2::default();
-
-    // Hash the password
-    let password_hash = argon2
-        .hash_password(password.as_bytes(), &salt)
-        .map_err(|e| {
-            AuthError::PasswordValidationError(format!("Failed to hash password: {}", e))
-        })?
-        .to_string();
-
-    Ok(password_hash)
+    Argon2Hasher.hash(password)
 }
 
 /// Verify a password against a Argon2id hash.
 ///
+/// Thin wrapper over [`Argon2Hasher`].
+///
 /// # Arguments
 ///
 /// * `password` - The plaintext password to verify
@@ -85,18 +323,7 @@ pub fn hash_password(password: &str) -> Result<String, AuthError> {
 /// assert!(verify_password("wrong_password", &hash).is_err());
 /// ```
 pub fn verify_password(password: &str, hash: &str) -> Result<(), AuthError> {
-    // Parse the hash
-    let parsed_hash = PasswordHash::new(hash).map_err(|e| {
-        AuthError::PasswordValidationError(format!("Invalid password hash format: {}", e))
-    })?;
-
-    // Create Argon2 instance
-    let argon2 = Argon2::default();
-
-    // Verify the password
-    argon2
-        .verify_password(password.as_bytes(), &parsed_hash)
-        .map_err(|_| AuthError::InvalidCredentials)
+    Argon2Hasher.verify(password, hash)
 }
 
 #[cfg(test)]
@@ -151,4 +378,49 @@ mod tests {
         assert!(verify_password("password1", &hash1).is_ok());
         assert!(verify_password("password1", &hash2).is_ok());
     }
+
+    #[test]
+    fn test_peppered_hashes_verify_only_with_matching_pepper() {
+        let hasher = PepperedHasher::new(Argon2Hasher, "v1", "pepper-one").unwrap();
+        let other = PepperedHasher::new(Argon2Hasher, "v1", "pepper-two").unwrap();
+
+        let hash = hasher.hash("password1").unwrap();
+        assert!(hash.starts_with("$pepper$v1$argon2id$"));
+        assert!(hasher.verify("password1", &hash).is_ok());
+        assert!(matches!(hasher.verify("password2", &hash), Err(AuthError::InvalidCredentials)));
+        assert!(matches!(other.verify("password1", &hash), Err(AuthError::InvalidCredentials)));
+
+        // The inner hash is of the peppered password, not the password itself
+        assert!(verify_password("password1", &hash["$pepper$v1".len()..]).is_err());
+        assert!(hasher.hash("").is_err());
+        assert!(!format!("{:?}", hasher).contains("pepper-one"));
+    }
+
+    #[test]
+    fn test_peppered_hasher_verifies_unpeppered_hashes() {
+        let hasher = PepperedHasher::new(Argon2Hasher, "v1", "pepper-one").unwrap();
+        let legacy = hash_password("password1").unwrap();
+
+        assert!(hasher.verify("password1", &legacy).is_ok());
+        assert!(matches!(hasher.verify("password2", &legacy), Err(AuthError::InvalidCredentials)));
+        assert!(hasher.needs_rehash(&legacy));
+    }
+
+    #[test]
+    fn test_pepper_change_is_detectable() {
+        let old = PepperedHasher::new(Argon2Hasher, "v1", "pepper-one").unwrap();
+        let hash = old.hash("password1").unwrap();
+
+        let new = PepperedHasher::new(Argon2Hasher, "v2", "pepper-two").unwrap();
+        assert!(new.needs_rehash(&hash));
+        assert!(new.needs_rehash(&hash_password("password1").unwrap()));
+        assert!(matches!(new.verify("password1", &hash), Err(AuthError::InvalidCredentials)));
+
+        let new = new.with_previous_pepper("v1", "pepper-one").unwrap();
+        assert!(new.verify("password1", &hash).is_ok());
+        assert!(!new.needs_rehash(&new.hash("password1").unwrap()));
+
+        assert!(PepperedHasher::new(Argon2Hasher, "v$1", "pepper").is_err());
+        assert!(PepperedHasher::new(Argon2Hasher, "v1", "").is_err());
+    }
 }
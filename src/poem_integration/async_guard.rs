@@ -0,0 +1,99 @@
+//! Asynchronous authorization guards
+//!
+//! Guards that need to await a database or network lookup implement
+//! [`AsyncAuthGuard`] instead of [`AuthGuard`]. Every synchronous `AuthGuard`
+//! is also an `AsyncAuthGuard`, so authorization code can always use the
+//! async form.
+
+use async_trait::async_trait;
+
+use crate::auth::UserClaims;
+use crate::poem_integration::guards::AuthGuard;
+
+/// Trait for authorization guards that need async lookups
+///
+/// Use this for guards that must hit a database or remote service, such as
+/// checking live account status or a dynamic permission table.
+///
+/// Because sync guards implement this trait too, call it with fully-qualified
+/// syntax when both traits are in scope:
+/// `AsyncAuthGuard::check(&guard, &claims).await`.
+///
+/// # Example
+///
+/// ```ignore
+/// use poem_auth::AsyncAuthGuard;
+/// use async_trait::async_trait;
+///
+/// struct IsActiveAccount {
+///     db: Arc<dyn UserDatabase>,
+/// }
+///
+/// #[async_trait]
+/// impl AsyncAuthGuard for IsActiveAccount {
+///     async fn check(&self, claims: &UserClaims) -> bool {
+///         matches!(self.db.get_user(&claims.sub).await, Ok(user) if user.is_enabled())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait AsyncAuthGuard: Send + Sync {
+    /// Check if the claims satisfy this guard
+    async fn check(&self, claims: &UserClaims) -> bool;
+}
+
+#[async_trait]
+impl<T: AuthGuard + ?Sized> AsyncAuthGuard for T {
+    async fn check(&self, claims: &UserClaims) -> bool {
+        AuthGuard::check(self, claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poem_integration::guards::{HasGroup, Not};
+
+    /// Simulates a permission table that must be queried asynchronously
+    struct PermissionTableGuard {
+        allowed_users: Vec<String>,
+    }
+
+    impl PermissionTableGuard {
+        async fn lookup(&self, username: &str) -> bool {
+            tokio::task::yield_now().await;
+            self.allowed_users.iter().any(|u| u == username)
+        }
+    }
+
+    #[async_trait]
+    impl AsyncAuthGuard for PermissionTableGuard {
+        async fn check(&self, claims: &UserClaims) -> bool {
+            self.lookup(&claims.sub).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_guard_with_lookup() {
+        let guard = PermissionTableGuard {
+            allowed_users: vec!["alice".to_string()],
+        };
+
+        let alice = UserClaims::new("alice", "local", 1000, 0);
+        let bob = UserClaims::new("bob", "local", 1000, 0);
+
+        assert!(guard.check(&alice).await);
+        assert!(!guard.check(&bob).await);
+    }
+
+    #[tokio::test]
+    async fn test_sync_guard_is_async_guard() {
+        let claims = UserClaims::new("alice", "local", 1000, 0).with_groups(vec!["admin"]);
+
+        let guard = HasGroup("admin".to_string());
+        assert!(AsyncAuthGuard::check(&guard, &claims).await);
+
+        let guard: Box<dyn AsyncAuthGuard> = Box::new(Not(HasGroup("admin".to_string())));
+        assert!(!guard.check(&claims).await);
+    }
+}
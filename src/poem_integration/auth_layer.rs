@@ -0,0 +1,229 @@
+//! Route-level authentication middleware
+//!
+//! [`AuthLayer`] verifies the request's JWT once and stores the resulting
+//! `UserClaims` in the request extensions. Handlers (and inner middleware)
+//! read them back with the [`ClaimsExt`] extractor, or with the `UserClaims`
+//! extractor, which also prefers claims already placed by the layer.
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use poem::{
+    http::StatusCode, Endpoint, Error as PoemError, FromRequest, Middleware, Request,
+    RequestBody, Result,
+};
+
+use crate::auth::UserClaims;
+use crate::poem_integration::extractors::claims_from_request;
+use crate::poem_integration::{PoemAppState, TokenSource};
+
+/// Middleware that authenticates every request to the wrapped endpoint
+///
+/// Requests without a valid token are rejected with the same 401 challenge as
+/// the `UserClaims` extractor. By default the state comes from
+/// [`PoemAppState::for_request`]; `with_state` supplies one explicitly, and
+/// `with_token_source` overrides where the wrapped routes read the token.
+///
+/// # Example
+///
+/// ```ignore
+/// use poem::{get, handler, EndpointExt, Route};
+/// use poem_auth::poem_integration::{AuthLayer, ClaimsExt};
+///
+/// #[handler]
+/// async fn profile(ClaimsExt(claims): ClaimsExt) -> String {
+///     format!("Hello, {}!", claims.sub)
+/// }
+///
+/// let app = Route::new()
+///     .nest("/api", Route::new().at("/profile", get(profile)).with(AuthLayer::new()));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AuthLayer {
+    state: Option<Arc<PoemAppState>>,
+    token_source: Option<TokenSource>,
+}
+
+impl AuthLayer {
+    /// Create a layer that verifies tokens with the request's `PoemAppState`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a layer that verifies tokens with the given state.
+    pub fn with_state(state: PoemAppState) -> Self {
+        Self {
+            state: Some(Arc::new(state)),
+            token_source: None,
+        }
+    }
+
+    /// Read tokens from `source` instead of the state's token source.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let layer = AuthLayer::new().with_token_source(TokenSource::Cookie("session".to_string()));
+    /// ```
+    pub fn with_token_source(mut self, source: TokenSource) -> Self {
+        self.token_source = Some(source);
+        self
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for AuthLayer {
+    type Output = AuthLayerEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        AuthLayerEndpoint {
+            inner: ep,
+            state: self.state.clone(),
+            token_source: self.token_source.clone(),
+        }
+    }
+}
+
+/// Endpoint produced by [`AuthLayer`]
+#[derive(Debug)]
+pub struct AuthLayerEndpoint<E> {
+    inner: E,
+    state: Option<Arc<PoemAppState>>,
+    token_source: Option<TokenSource>,
+}
+
+impl<E: Endpoint> Endpoint for AuthLayerEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        if let Some(source) = &self.token_source {
+            req.extensions_mut().insert(source.clone());
+        }
+        let state = match &self.state {
+            Some(state) => state.as_ref(),
+            None => PoemAppState::for_request(&req)
+                .ok_or_else(|| PoemError::from_status(StatusCode::INTERNAL_SERVER_ERROR))?,
+        };
+
+        let claims = claims_from_request(state, &req)?;
+        req.extensions_mut().insert(claims);
+        self.inner.call(req).await
+    }
+}
+
+/// Extractor for claims already verified by [`AuthLayer`]
+///
+/// Reads the claims from the request extensions without touching the token
+/// again. Returns 500 Internal Server Error if the route isn't wrapped in
+/// `AuthLayer`, since that's a routing mistake rather than a client error.
+///
+/// # Example
+///
+/// ```ignore
+/// #[handler]
+/// async fn whoami(claims: ClaimsExt) -> String {
+///     claims.sub.clone()
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClaimsExt(pub UserClaims);
+
+impl Deref for ClaimsExt {
+    type Target = UserClaims;
+
+    fn deref(&self) -> &UserClaims {
+        &self.0
+    }
+}
+
+impl<'a> FromRequest<'a> for ClaimsExt {
+    async fn from_request(req: &'a Request, _body: &mut RequestBody) -> Result<Self> {
+        req.extensions()
+            .get::<UserClaims>()
+            .cloned()
+            .map(ClaimsExt)
+            .ok_or_else(|| {
+                tracing::error!("ClaimsExt used on a route without AuthLayer");
+                PoemError::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwt::JwtValidator;
+    use crate::middleware::RateLimit;
+    use crate::providers::LocalAuthProvider;
+    use poem::{handler, EndpointExt};
+
+    async fn test_state() -> PoemAppState {
+        let db = crate::db::SqliteUserDb::in_memory().await.unwrap();
+        PoemAppState {
+            provider: Arc::new(LocalAuthProvider::new(db)),
+            user_db: None,
+            jwt: Arc::new(JwtValidator::new("my-very-long-secret-key").unwrap()),
+            server_config: None,
+            token_source: TokenSource::default(),
+            denial_mode: Default::default(),
+            rate_limit: Arc::new(RateLimit::default_config()),
+        }
+    }
+
+    fn bearer(state: &PoemAppState) -> String {
+        let now = chrono::Utc::now().timestamp();
+        let claims = UserClaims::new("alice", "local", now + 3600, now);
+        format!("Bearer {}", state.jwt.generate_token(&claims).unwrap().token)
+    }
+
+    #[handler]
+    fn whoami(claims: ClaimsExt) -> String {
+        claims.sub.clone()
+    }
+
+    #[handler]
+    fn whoami_claims(claims: UserClaims) -> String {
+        claims.sub
+    }
+
+    #[tokio::test]
+    async fn test_layer_passes_claims_to_handler() {
+        let state = test_state().await;
+        let auth = bearer(&state);
+        let ep = whoami.with(AuthLayer::with_state(state));
+
+        let resp = ep
+            .get_response(Request::builder().header("Authorization", auth).finish())
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.into_body().into_string().await.unwrap(), "alice");
+    }
+
+    #[tokio::test]
+    async fn test_layer_rejects_missing_token() {
+        let ep = whoami.with(AuthLayer::with_state(test_state().await));
+
+        let resp = ep.get_response(Request::builder().finish()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_user_claims_extractor_reuses_layer_claims() {
+        // The global PoemAppState is never initialized here, so the handler
+        // can only succeed by reading the claims the layer stored.
+        let state = test_state().await;
+        let auth = bearer(&state);
+        let ep = whoami_claims.with(AuthLayer::with_state(state));
+
+        let resp = ep
+            .get_response(Request::builder().header("Authorization", auth).finish())
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.into_body().into_string().await.unwrap(), "alice");
+    }
+
+    #[tokio::test]
+    async fn test_claims_ext_without_layer() {
+        let resp = whoami.get_response(Request::builder().finish()).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
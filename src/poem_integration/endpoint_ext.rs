@@ -0,0 +1,206 @@
+//! Fluent route protection
+//!
+//! [`AuthEndpointExt`] adds `require_*` methods to every endpoint, so a route
+//! can be protected where it is built instead of with an attribute macro:
+//! `get(handler).require_group("admins")`. This also works for routes whose
+//! requirements are only known at runtime.
+
+use std::sync::Arc;
+
+use poem::{http::StatusCode, Endpoint, Error as PoemError, IntoEndpoint, Middleware, Request, Result};
+
+use crate::auth::UserClaims;
+use crate::poem_integration::async_guard::AsyncAuthGuard;
+use crate::poem_integration::extractors::claims_from_request;
+use crate::poem_integration::guards::{DenialMode, HasAllGroups, HasAnyGroup, HasGroup};
+use crate::poem_integration::PoemAppState;
+
+/// Reason reported when a custom guard denies a request
+const GUARD_DENIED: &str = "Forbidden: insufficient permissions";
+
+/// Middleware that only admits requests whose claims pass a guard
+///
+/// Claims already verified by [`AuthLayer`](crate::poem_integration::AuthLayer)
+/// are reused; otherwise the token is verified with
+/// [`PoemAppState::for_request`]'s state, and requests without a valid token
+/// get the same 401 challenge as the `UserClaims` extractor. Denied requests
+/// get the state's [`DenialMode`] response. Verified claims are left in the
+/// request extensions for the handler.
+///
+/// Usually applied through [`AuthEndpointExt`].
+///
+/// # Example
+///
+/// ```ignore
+/// let guard = RequireGuard::new(Or { first: HasGroup("admins".into()), second: HasGroup("ops".into()) });
+/// let app = Route::new().at("/deploy", post(deploy).with(guard));
+/// ```
+pub struct RequireGuard<G> {
+    guard: Arc<G>,
+    reason: String,
+    /// Requirement `mode` and names reported in 403 bodies, if known
+    requirement: Option<(&'static str, Vec<String>)>,
+}
+
+impl<G: AsyncAuthGuard> RequireGuard<G> {
+    /// Create middleware that checks `guard`.
+    pub fn new(guard: G) -> Self {
+        Self {
+            guard: Arc::new(guard),
+            reason: GUARD_DENIED.to_string(),
+            requirement: None,
+        }
+    }
+
+    /// Report `required` names and the match `mode` when denying, like the
+    /// `require_*` macros do.
+    fn with_requirement(mut self, reason: String, mode: &'static str, required: Vec<String>) -> Self {
+        self.reason = reason;
+        self.requirement = Some((mode, required));
+        self
+    }
+}
+
+impl<G> Clone for RequireGuard<G> {
+    fn clone(&self) -> Self {
+        Self {
+            guard: self.guard.clone(),
+            reason: self.reason.clone(),
+            requirement: self.requirement.clone(),
+        }
+    }
+}
+
+impl<G> std::fmt::Debug for RequireGuard<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequireGuard")
+            .field("reason", &self.reason)
+            .field("requirement", &self.requirement)
+            .finish()
+    }
+}
+
+impl<E: Endpoint, G: AsyncAuthGuard + 'static> Middleware<E> for RequireGuard<G> {
+    type Output = RequireGuardEndpoint<E, G>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RequireGuardEndpoint {
+            inner: ep,
+            guard: self.clone(),
+        }
+    }
+}
+
+/// Endpoint produced by [`RequireGuard`]
+#[derive(Debug)]
+pub struct RequireGuardEndpoint<E, G> {
+    inner: E,
+    guard: RequireGuard<G>,
+}
+
+impl<E: Endpoint, G: AsyncAuthGuard + 'static> Endpoint for RequireGuardEndpoint<E, G> {
+    type Output = E::Output;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let state = PoemAppState::for_request(&req);
+        let claims = match req.extensions().get::<UserClaims>() {
+            Some(claims) => claims.clone(),
+            None => {
+                let state = state
+                    .ok_or_else(|| PoemError::from_status(StatusCode::INTERNAL_SERVER_ERROR))?;
+                claims_from_request(state, &req)?
+            }
+        };
+
+        if !AsyncAuthGuard::check(self.guard.guard.as_ref(), &claims).await {
+            let mode = state.map(|state| state.denial_mode).unwrap_or_default();
+            return Err(self.deny(mode));
+        }
+
+        req.extensions_mut().insert(claims);
+        self.inner.call(req).await
+    }
+}
+
+impl<E, G> RequireGuardEndpoint<E, G> {
+    fn deny(&self, mode: DenialMode) -> PoemError {
+        let reason = &self.guard.reason;
+        match &self.guard.requirement {
+            Some((requirement_mode, required)) => {
+                let required: Vec<&str> = required.iter().map(String::as_str).collect();
+                mode.deny_requirement_error(reason, requirement_mode, &required)
+            }
+            None => mode.deny_error(reason),
+        }
+    }
+}
+
+/// Route protection methods for every Poem endpoint
+///
+/// A builder-style alternative to the `require_*` attribute macros. Import
+/// the trait and chain the requirement onto the endpoint.
+///
+/// # Example
+///
+/// ```ignore
+/// use poem::{get, Route};
+/// use poem_auth::poem_integration::AuthEndpointExt;
+///
+/// let app = Route::new()
+///     .at("/admin", get(admin_panel).require_group("admins"))
+///     .at("/reports", get(reports).require_any_group(vec!["finance", "auditors"]))
+///     .at("/deploy", get(deploy).require_guard(IsEnabled));
+/// ```
+pub trait AuthEndpointExt: IntoEndpoint {
+    /// Require a single group membership.
+    fn require_group<S: Into<String>>(self, group: S) -> RequireGuardEndpoint<Self::Endpoint, HasGroup>
+    where
+        Self: Sized,
+    {
+        let group = group.into();
+        let reason = format!("Forbidden: requires '{}' group", group);
+        let guard = RequireGuard::new(HasGroup(group.clone()))
+            .with_requirement(reason, "single", vec![group]);
+        guard.transform(self.into_endpoint())
+    }
+
+    /// Require membership in any of `groups`.
+    fn require_any_group<S: Into<String>>(
+        self,
+        groups: Vec<S>,
+    ) -> RequireGuardEndpoint<Self::Endpoint, HasAnyGroup>
+    where
+        Self: Sized,
+    {
+        let groups: Vec<String> = groups.into_iter().map(Into::into).collect();
+        let reason = format!("Forbidden: requires one of groups: {}", groups.join(", "));
+        let guard = RequireGuard::new(HasAnyGroup(groups.clone()))
+            .with_requirement(reason, "any", groups);
+        guard.transform(self.into_endpoint())
+    }
+
+    /// Require membership in all of `groups`.
+    fn require_all_groups<S: Into<String>>(
+        self,
+        groups: Vec<S>,
+    ) -> RequireGuardEndpoint<Self::Endpoint, HasAllGroups>
+    where
+        Self: Sized,
+    {
+        let groups: Vec<String> = groups.into_iter().map(Into::into).collect();
+        let reason = format!("Forbidden: requires all groups: {}", groups.join(", "));
+        let guard = RequireGuard::new(HasAllGroups(groups.clone()))
+            .with_requirement(reason, "all", groups);
+        guard.transform(self.into_endpoint())
+    }
+
+    /// Require any guard, sync or async.
+    fn require_guard<G: AsyncAuthGuard + 'static>(self, guard: G) -> RequireGuardEndpoint<Self::Endpoint, G>
+    where
+        Self: Sized,
+    {
+        RequireGuard::new(guard).transform(self.into_endpoint())
+    }
+}
+
+impl<T: IntoEndpoint> AuthEndpointExt for T {}
@@ -0,0 +1,166 @@
+//! HTTP responses for `AuthError`
+//!
+//! Implementing Poem's `ResponseError` lets handlers use `?` on auth
+//! operations: the error converts into a `poem::Error` whose response has a
+//! matching status and an [`ErrorResponse`] JSON body.
+
+use poem::{
+    http::{header, StatusCode},
+    error::ResponseError,
+    web::Json,
+    IntoResponse, Response,
+};
+
+use crate::api::types::ErrorResponse;
+use crate::error::AuthError;
+use crate::poem_integration::extractors::bearer_challenge;
+
+/// Maps each variant to a status; server-side failures (database, LDAP,
+/// configuration, secrets, token signing) are 500 with a generic message so
+/// internals aren't exposed. [`AuthError::Other`] carries request-level
+/// failures such as a rejected username or an operation the provider
+/// doesn't support, so it's a 400 with its message. Token errors carry the
+/// same `WWW-Authenticate` challenge as the `UserClaims` extractor, and
+/// [`AuthError::RateLimitExceeded`] sets `Retry-After`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[handler]
+/// async fn login(Json(req): Json<LoginRequest>) -> poem::Result<Json<LoginResponse>> {
+///     let claims = PoemAppState::get().provider.authenticate(&req.username, &req.password).await?;
+///     // ...
+/// }
+/// ```
+impl ResponseError for AuthError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AuthError::InvalidCredentials
+            | AuthError::InvalidToken
+            | AuthError::MalformedToken
+            | AuthError::InvalidSignature
+            | AuthError::TokenExpired
+            | AuthError::TokenNotYetValid
+            | AuthError::MasterAuthFailed => StatusCode::UNAUTHORIZED,
+            AuthError::UserDisabled => StatusCode::FORBIDDEN,
+            AuthError::UserNotFound => StatusCode::NOT_FOUND,
+            AuthError::ProviderNotFound(_)
+            | AuthError::PasswordValidationError(_)
+            | AuthError::Other(_) => StatusCode::BAD_REQUEST,
+            AuthError::RateLimitExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+            AuthError::LdapError(_)
+            | AuthError::DatabaseError(_)
+            | AuthError::ConfigError(_)
+            | AuthError::JwtError(_)
+            | AuthError::SecretsError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn as_response(&self) -> Response {
+        let status = self.status();
+        let mut response = (status, Json(self.response_body())).into_response();
+        if status == StatusCode::UNAUTHORIZED && self.is_token_error() {
+            if let Ok(challenge) = bearer_challenge(Some(self)).parse() {
+                response.headers_mut().insert(header::WWW_AUTHENTICATE, challenge);
+            }
+        }
+        if let AuthError::RateLimitExceeded(secs) = self {
+            response.headers_mut().insert(header::RETRY_AFTER, (*secs).into());
+        }
+        response
+    }
+}
+
+impl AuthError {
+    /// The [`ErrorResponse`] body sent for this error; server-side failures
+    /// are logged here and get a generic message.
+    pub(crate) fn response_body(&self) -> ErrorResponse {
+        match self {
+            AuthError::InvalidCredentials => ErrorResponse::invalid_credentials(),
+            AuthError::RateLimitExceeded(secs) => ErrorResponse::rate_limit_exceeded(*secs),
+            _ if self.status().is_server_error() => {
+                tracing::error!("Auth error in handler: {}", self);
+                ErrorResponse::new(self.kind(), "Internal server error")
+            }
+            _ => ErrorResponse::new(self.kind(), &self.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body_of(err: AuthError) -> (StatusCode, serde_json::Value) {
+        let response = poem::Error::from(err).into_response();
+        let status = response.status();
+        let body = response.into_body().into_string().await.unwrap();
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_credentials_is_401() {
+        let (status, body) = body_of(AuthError::InvalidCredentials).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "invalid_credentials");
+        assert_eq!(body["message"], "Username or password is incorrect");
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_hide_details() {
+        let (status, body) = body_of(AuthError::database("disk I/O error at /var/db")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"], "database_error");
+        assert_eq!(body["message"], "Internal server error");
+    }
+
+    #[tokio::test]
+    async fn test_signing_errors_are_500() {
+        let response = poem::Error::from(AuthError::jwt("Failed to encode token")).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(response.headers().get(header::WWW_AUTHENTICATE).is_none());
+        let body = response.into_body().into_string().await.unwrap();
+        assert!(!body.contains("encode"));
+    }
+
+    #[tokio::test]
+    async fn test_other_is_400_with_message() {
+        let (status, body) = body_of(AuthError::other("Username must not be empty")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "other");
+        assert_eq!(body["message"], "Authentication failed: Username must not be empty");
+    }
+
+    #[tokio::test]
+    async fn test_token_errors_carry_challenge() {
+        let response = poem::Error::from(AuthError::TokenExpired).into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers()[header::WWW_AUTHENTICATE]
+            .to_str()
+            .unwrap()
+            .contains("expired"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_sets_retry_after() {
+        let response = poem::Error::from(AuthError::RateLimitExceeded(12)).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "12");
+        let body = response.into_body().into_string().await.unwrap();
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["error"], "rate_limit_exceeded");
+    }
+
+    #[tokio::test]
+    async fn test_question_mark_in_handler() {
+        use poem::{handler, Endpoint, Request};
+
+        #[handler]
+        fn disabled() -> poem::Result<String> {
+            Err(AuthError::UserDisabled)?
+        }
+
+        let response = disabled.get_response(Request::builder().finish()).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
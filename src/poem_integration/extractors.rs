@@ -1,13 +1,203 @@
-/// Poem framework extractors for automatic user claims extraction
-///
-/// This module provides automatic extraction of JWT claims from HTTP requests,
-/// making it easy to access authenticated user information in Poem handlers
-/// without manual token parsing and validation.
+//! Poem framework extractors for automatic user claims extraction
+//!
+//! This module provides automatic extraction of JWT claims from HTTP requests,
+//! making it easy to access authenticated user information in Poem handlers
+//! without manual token parsing and validation.
+
+use std::collections::HashMap;
 
-use poem::{FromRequest, Request, RequestBody, http::StatusCode, Error as PoemError};
+use poem::{Endpoint, FromRequest, Middleware, Request, RequestBody, Response, http::{header, StatusCode}, Error as PoemError};
 use crate::auth::UserClaims;
+use crate::error::AuthError;
+use crate::jwt::JwtValidator;
 use crate::poem_integration::PoemAppState;
 
+/// Where the `UserClaims` extractor looks for the JWT.
+///
+/// Configured on [`PoemAppState`] via `with_token_source`. Defaults to the
+/// `Authorization: Bearer <token>` header.
+///
+/// A `TokenSource` is also a middleware: wrapping routes in one overrides the
+/// state's source for that subtree, so an API and a browser frontend can be
+/// served side by side:
+///
+/// ```ignore
+/// let app = Route::new()
+///     .nest("/api", api_routes)
+///     .nest("/app", app_routes.with(TokenSource::Cookie("session".to_string())));
+/// ```
+///
+/// # Example
+///
+/// ```ignore
+/// use poem_auth::poem_integration::TokenSource;
+///
+/// // Browser WebSocket clients can't set headers, so accept `?token=...` too
+/// let state = PoemAppState::new("users.db", "my-super-secret-key")
+///     .await?
+///     .with_token_source(TokenSource::Any(vec![
+///         TokenSource::Bearer,
+///         TokenSource::Query("token".to_string()),
+///     ]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TokenSource {
+    /// `Authorization: Bearer <token>` header (scheme is case-insensitive)
+    #[default]
+    Bearer,
+    /// Query string parameter with the given name (e.g. `?token=...`)
+    Query(String),
+    /// Cookie with the given name (e.g. `Cookie: session=...`)
+    ///
+    /// Browsers attach cookies to cross-site requests too, so routes that
+    /// change state and accept a cookie token are open to CSRF. Set the
+    /// cookie with `SameSite=Strict` (or `Lax`, keeping state changes off
+    /// `GET`), `Secure` and `HttpOnly`, or add CSRF tokens to those routes
+    /// (e.g. Poem's `Csrf` middleware). Rejections from cookie routes still
+    /// carry a `WWW-Authenticate: Bearer` challenge, which browsers ignore.
+    Cookie(String),
+    /// Header with the given name, for gateways that strip `Authorization`
+    /// and forward the token elsewhere (e.g. `X-Auth-Token: <token>`)
+    CustomHeader {
+        /// Header name (case-insensitive)
+        name: String,
+        /// Expect `Bearer <token>` instead of the raw token
+        bearer: bool,
+    },
+    /// Try each source in order and use the first token found
+    Any(Vec<TokenSource>),
+}
+
+impl TokenSource {
+    /// Extract the raw token from the request, if present.
+    ///
+    /// Returns `None` if this source doesn't carry a token.
+    pub fn extract(&self, req: &Request) -> Option<String> {
+        match self {
+            TokenSource::Bearer => req
+                .header("Authorization")
+                .and_then(|h| JwtValidator::extract_token(h).ok())
+                .map(|t| t.to_string()),
+            TokenSource::Query(name) => {
+                req.uri().query()?;
+                let params = req.params::<HashMap<String, String>>().ok()?;
+                params.get(name).filter(|t| !t.is_empty()).cloned()
+            }
+            TokenSource::Cookie(name) => req
+                .headers()
+                .get_all(header::COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(';'))
+                .filter_map(|pair| pair.trim().split_once('='))
+                .find(|(key, token)| key == name && !token.is_empty())
+                .map(|(_, token)| token.to_string()),
+            TokenSource::CustomHeader { name, bearer: true } => req
+                .header(name)
+                .and_then(|h| JwtValidator::extract_token(h).ok())
+                .map(|t| t.to_string()),
+            TokenSource::CustomHeader { name, bearer: false } => req
+                .header(name)
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(|t| t.to_string()),
+            TokenSource::Any(sources) => sources.iter().find_map(|source| source.extract(req)),
+        }
+    }
+
+    /// The source for `req`: a per-route override, if any, else the state's.
+    pub(crate) fn for_request<'a>(state: &'a PoemAppState, req: &'a Request) -> &'a TokenSource {
+        req.extensions()
+            .get::<TokenSource>()
+            .unwrap_or(&state.token_source)
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for TokenSource {
+    type Output = TokenSourceEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        TokenSourceEndpoint {
+            inner: ep,
+            source: self.clone(),
+        }
+    }
+}
+
+/// Endpoint produced by using a [`TokenSource`] as middleware
+///
+/// Makes the wrapped routes read tokens from its source instead of the
+/// `PoemAppState`'s.
+#[derive(Debug)]
+pub struct TokenSourceEndpoint<E> {
+    inner: E,
+    source: TokenSource,
+}
+
+impl<E: Endpoint> Endpoint for TokenSourceEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, mut req: Request) -> poem::Result<Self::Output> {
+        req.extensions_mut().insert(self.source.clone());
+        self.inner.call(req).await
+    }
+}
+
+/// Build a 401 Unauthorized error carrying an RFC 6750 `WWW-Authenticate` challenge.
+///
+/// With no error (the request carried no token) the challenge is a bare
+/// `Bearer`, as RFC 6750 §3.1 recommends. Otherwise it reports
+/// `error="invalid_token"` with a description that distinguishes expired,
+/// not-yet-valid and malformed tokens without exposing validation details.
+///
+/// # Example
+///
+/// ```ignore
+/// use poem_auth::poem_integration::extractors::unauthorized;
+///
+/// let claims = state.jwt.verify_token(token).map_err(|e| unauthorized(Some(&e)))?;
+/// ```
+pub fn unauthorized(error: Option<&AuthError>) -> PoemError {
+    PoemError::from_response(
+        Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header(header::WWW_AUTHENTICATE, bearer_challenge(error))
+            .finish(),
+    )
+}
+
+/// The `WWW-Authenticate` challenge for a missing (`None`) or rejected token.
+pub(crate) fn bearer_challenge(error: Option<&AuthError>) -> String {
+    match error {
+        None => "Bearer".to_string(),
+        Some(err) => {
+            let description = match err {
+                AuthError::TokenExpired => "The access token expired",
+                AuthError::TokenNotYetValid => "The access token is not yet valid",
+                _ => "The access token is malformed or invalid",
+            };
+            format!("Bearer error=\"invalid_token\", error_description=\"{}\"", description)
+        }
+    }
+}
+
+/// Extract and verify the claims for `req` using the given app state.
+pub(crate) fn claims_from_request(state: &PoemAppState, req: &Request) -> Result<UserClaims, PoemError> {
+    // Extract token from the route's or the state's source
+    let token = TokenSource::for_request(state, req)
+        .extract(req)
+        .ok_or_else(|| unauthorized(None))?;
+
+    // Verify and decode token
+    let claims = state.jwt.verify_token(&token).map_err(|e| unauthorized(Some(&e)))?;
+
+    // Let an enclosing AuditMiddleware record who made the request
+    #[cfg(feature = "middleware")]
+    crate::middleware::audit::VerifiedClaims::record(req, &claims);
+
+    Ok(claims)
+}
+
 /// Automatic JWT extractor for Poem handlers
 ///
 /// This implementation allows handlers to directly receive `UserClaims` as a parameter,
@@ -30,19 +220,24 @@ use crate::poem_integration::PoemAppState;
 ///
 /// # How it Works
 ///
-/// 1. Extracts Authorization header from request
+/// 1. Reuses claims already verified by [`AuthLayer`](super::AuthLayer), if any;
+///    otherwise looks up the token using the route's [`TokenSource`], or the
+///    state's (the Authorization header by default)
 /// 2. Checks for "Bearer <token>" format
 /// 3. Extracts JWT from Bearer token
-/// 4. Verifies and decodes using JwtValidator from global state
+/// 4. Verifies and decodes using the JwtValidator from [`PoemAppState::for_request`]
 /// 5. Returns claims or 401 Unauthorized error
 ///
 /// # Error Handling
 ///
-/// Returns 401 Unauthorized if:
-/// - No Authorization header present
+/// Returns 401 Unauthorized with a `WWW-Authenticate: Bearer` challenge
+/// (see [`unauthorized`]) if:
+/// - No token is present in the configured source
 /// - Header doesn't start with "Bearer "
 /// - Token is invalid or expired
-/// - JwtValidator is not initialized
+///
+/// Returns 500 Internal Server Error if no `PoemAppState` is attached to the
+/// request and the global state is not initialized.
 ///
 /// # Performance
 ///
@@ -50,35 +245,20 @@ use crate::poem_integration::PoemAppState;
 /// to avoid repeated cryptographic operations.
 impl<'a> FromRequest<'a> for UserClaims {
     async fn from_request(req: &'a Request, _body: &mut RequestBody) -> Result<Self, PoemError> {
-        // Try to get the app state (will panic if not initialized)
-        let state = match PoemAppState::try_get() {
+        // Claims placed by AuthLayer have already been verified
+        if let Some(claims) = req.extensions().get::<UserClaims>() {
+            return Ok(claims.clone());
+        }
+
+        // Prefer a state attached to the request, then the global one
+        let state = match PoemAppState::for_request(req) {
             Some(s) => s,
             None => {
                 return Err(PoemError::from_status(StatusCode::INTERNAL_SERVER_ERROR));
             }
         };
 
-        // Extract Authorization header
-        let auth_header = match req.header("Authorization") {
-            Some(h) => h,
-            None => {
-                return Err(PoemError::from_status(StatusCode::UNAUTHORIZED));
-            }
-        };
-
-        // Extract Bearer token
-        let token = match auth_header.strip_prefix("Bearer ") {
-            Some(t) => t,
-            None => {
-                return Err(PoemError::from_status(StatusCode::UNAUTHORIZED));
-            }
-        };
-
-        // Verify and decode token
-        match state.jwt.verify_token(token) {
-            Ok(claims) => Ok(claims),
-            Err(_) => Err(PoemError::from_status(StatusCode::UNAUTHORIZED)),
-        }
+        claims_from_request(state, req)
     }
 }
 
@@ -91,4 +271,233 @@ mod tests {
         // This is a compile-time test ensuring FromRequest is properly implemented
         // Runtime tests require setting up a full Poem app
     }
+
+    fn test_token(validator: &JwtValidator) -> String {
+        let now = chrono::Utc::now().timestamp();
+        let claims = UserClaims::new("alice", "local", now + 3600, now);
+        validator.generate_token(&claims).unwrap().token
+    }
+
+    #[test]
+    fn test_token_source_bearer() {
+        let validator = JwtValidator::new("my-very-long-secret-key").unwrap();
+        let token = test_token(&validator);
+        let req = Request::builder()
+            .header("Authorization", format!("Bearer {}", token))
+            .finish();
+
+        assert_eq!(TokenSource::Bearer.extract(&req), Some(token));
+    }
+
+    #[test]
+    fn test_token_source_bearer_case_insensitive() {
+        for scheme in ["Bearer", "bearer", "BEARER"] {
+            let req = Request::builder()
+                .header("Authorization", format!("{} abc.def.ghi", scheme))
+                .finish();
+            assert_eq!(TokenSource::Bearer.extract(&req), Some("abc.def.ghi".to_string()));
+        }
+
+        let req = Request::builder()
+            .header("Authorization", "Basic YWxpY2U6cGFzcw==")
+            .finish();
+        assert_eq!(TokenSource::Bearer.extract(&req), None);
+    }
+
+    #[test]
+    fn test_token_source_query_authenticates() {
+        let validator = JwtValidator::new("my-very-long-secret-key").unwrap();
+        let token = test_token(&validator);
+        let req = Request::builder()
+            .uri_str(format!("/ws?room=lobby&token={}", token))
+            .finish();
+
+        let extracted = TokenSource::Query("token".to_string()).extract(&req).unwrap();
+        let claims = validator.verify_token(&extracted).unwrap();
+        assert_eq!(claims.sub, "alice");
+    }
+
+    #[test]
+    fn test_token_source_cookie() {
+        let source = TokenSource::Cookie("session".to_string());
+        let req = Request::builder()
+            .header("Cookie", "theme=dark; session=abc.def.ghi")
+            .finish();
+        assert_eq!(source.extract(&req), Some("abc.def.ghi".to_string()));
+
+        let req = Request::builder()
+            .header("Cookie", "sessionid=abc; session=")
+            .header("Authorization", "Bearer abc.def.ghi")
+            .finish();
+        assert_eq!(source.extract(&req), None);
+    }
+
+    #[test]
+    fn test_token_source_custom_header() {
+        let validator = JwtValidator::new("my-very-long-secret-key").unwrap();
+        let token = test_token(&validator);
+        let raw = TokenSource::CustomHeader { name: "X-Auth-Token".to_string(), bearer: false };
+        let req = Request::builder()
+            .header("X-Auth-Token", &token)
+            .finish();
+
+        let extracted = raw.extract(&req).unwrap();
+        assert_eq!(validator.verify_token(&extracted).unwrap().sub, "alice");
+        // The default source still only reads Authorization
+        assert_eq!(TokenSource::Bearer.extract(&req), None);
+
+        let bearer = TokenSource::CustomHeader { name: "x-auth-token".to_string(), bearer: true };
+        assert_eq!(bearer.extract(&req), None);
+        let req = Request::builder()
+            .header("X-Auth-Token", format!("Bearer {}", token))
+            .finish();
+        assert_eq!(bearer.extract(&req), Some(token));
+    }
+
+    #[test]
+    fn test_token_source_bearer_ignores_query() {
+        let validator = JwtValidator::new("my-very-long-secret-key").unwrap();
+        let token = test_token(&validator);
+        let req = Request::builder()
+            .uri_str(format!("/ws?token={}", token))
+            .finish();
+
+        assert_eq!(TokenSource::Bearer.extract(&req), None);
+    }
+
+    async fn test_state() -> PoemAppState {
+        state_with_secret("my-very-long-secret-key").await
+    }
+
+    async fn state_with_secret(secret: &str) -> PoemAppState {
+        let db = crate::db::SqliteUserDb::in_memory().await.unwrap();
+        PoemAppState {
+            provider: std::sync::Arc::new(crate::providers::LocalAuthProvider::new(db)),
+            user_db: None,
+            jwt: std::sync::Arc::new(JwtValidator::new(secret).unwrap()),
+            server_config: None,
+            token_source: TokenSource::default(),
+            denial_mode: Default::default(),
+            rate_limit: std::sync::Arc::new(crate::middleware::RateLimit::default_config()),
+        }
+    }
+
+    fn challenge(err: PoemError) -> String {
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        response
+            .headers()
+            .get(header::WWW_AUTHENTICATE)
+            .expect("401 must carry WWW-Authenticate")
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_missing_token_gets_bare_bearer_challenge() {
+        let state = test_state().await;
+        let req = Request::builder().finish();
+
+        let err = claims_from_request(&state, &req).unwrap_err();
+        assert_eq!(challenge(err), "Bearer");
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_challenge() {
+        let state = test_state().await;
+        let now = chrono::Utc::now().timestamp();
+        let claims = UserClaims::new("alice", "local", now - 3600, now - 7200);
+        let token = state.jwt.generate_token(&claims).unwrap().token;
+        let req = Request::builder()
+            .header("Authorization", format!("Bearer {}", token))
+            .finish();
+
+        let value = challenge(claims_from_request(&state, &req).unwrap_err());
+        assert!(value.starts_with("Bearer error=\"invalid_token\""));
+        assert!(value.contains("expired"));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_token_challenge() {
+        let state = test_state().await;
+        let req = Request::builder()
+            .header("Authorization", "Bearer not-a-jwt")
+            .finish();
+
+        let value = challenge(claims_from_request(&state, &req).unwrap_err());
+        assert!(value.starts_with("Bearer error=\"invalid_token\""));
+        assert!(value.contains("malformed"));
+    }
+
+    #[tokio::test]
+    async fn test_valid_token_passes() {
+        let state = test_state().await;
+        let token = test_token(&state.jwt);
+        let req = Request::builder()
+            .header("Authorization", format!("Bearer {}", token))
+            .finish();
+
+        assert_eq!(claims_from_request(&state, &req).unwrap().sub, "alice");
+    }
+
+    #[poem::handler]
+    fn whoami(claims: UserClaims) -> String {
+        claims.sub
+    }
+
+    /// Call `whoami` with `state` attached to the route and `token` as bearer.
+    async fn call_with_state(state: PoemAppState, token: &str) -> Response {
+        use poem::{Endpoint, EndpointExt};
+
+        whoami
+            .data(state)
+            .get_response(
+                Request::builder()
+                    .header("Authorization", format!("Bearer {}", token))
+                    .finish(),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_request_state_first_instance() {
+        let state = state_with_secret("first-test-secret-key").await;
+        let other = state_with_secret("second-test-secret-key").await;
+        let token = test_token(&state.jwt);
+
+        let resp = call_with_state(state.clone(), &token).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.into_body().into_string().await.unwrap(), "alice");
+
+        let resp = call_with_state(state, &test_token(&other.jwt)).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_request_state_second_instance() {
+        // Runs alongside the test above with a different secret; neither
+        // touches the global singleton, so they can't interfere
+        let state = state_with_secret("second-test-secret-key").await;
+        let token = test_token(&state.jwt);
+
+        let resp = call_with_state(state, &token).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let other = state_with_secret("first-test-secret-key").await;
+        let resp = call_with_state(other, &token).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_token_source_any_falls_back() {
+        let req = Request::builder().uri_str("/ws?access_token=abc").finish();
+        let source = TokenSource::Any(vec![
+            TokenSource::Bearer,
+            TokenSource::Query("access_token".to_string()),
+        ]);
+
+        assert_eq!(source.extract(&req), Some("abc".to_string()));
+        assert_eq!(TokenSource::Query("token".to_string()).extract(&req), None);
+    }
 }
@@ -1,9 +1,12 @@
-/// Authorization guard trait and implementations
-///
-/// This module provides composable authorization guards for checking user permissions
-/// in a flexible, type-safe way. Guards can be combined using logical operators.
+//! Authorization guard trait and implementations
+//!
+//! This module provides composable authorization guards for checking user permissions
+//! in a flexible, type-safe way. Guards can be combined using logical operators.
+
+use poem::{http::StatusCode, web::Json, IntoResponse, Response};
 
 use crate::auth::UserClaims;
+use crate::poem_integration::PoemAppState;
 
 /// Trait for authorization guards
 ///
@@ -80,6 +83,61 @@ impl AuthGuard for HasAllGroups {
     }
 }
 
+/// Guard that requires ANY of the specified OAuth scopes (OR logic)
+///
+/// Scopes are read from the token's `scope` claim (see [`UserClaims::scopes`]).
+///
+/// # Example
+///
+/// ```ignore
+/// let guard = HasAnyScope(vec!["read:users".to_string(), "admin".to_string()]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct HasAnyScope(pub Vec<String>);
+
+impl AuthGuard for HasAnyScope {
+    fn check(&self, claims: &UserClaims) -> bool {
+        let scope_refs: Vec<&str> = self.0.iter().map(|s| s.as_str()).collect();
+        claims.has_any_scope(&scope_refs)
+    }
+}
+
+/// Guard that requires the user to have authenticated through a provider
+///
+/// Compares against `claims.provider`, so routes can demand a particular
+/// authentication method (e.g. LDAP rather than a local password).
+///
+/// # Example
+///
+/// ```ignore
+/// let guard = HasProvider("ldap".to_string());
+/// ```
+#[derive(Debug, Clone)]
+pub struct HasProvider(pub String);
+
+impl AuthGuard for HasProvider {
+    fn check(&self, claims: &UserClaims) -> bool {
+        claims.provider == self.0
+    }
+}
+
+/// Guard that requires ALL of the specified OAuth scopes (AND logic)
+///
+/// # Example
+///
+/// ```ignore
+/// let guard = HasAllScopes(vec!["read:users".to_string(), "write:users".to_string()]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct HasAllScopes(pub Vec<String>);
+
+impl AuthGuard for HasAllScopes {
+    fn check(&self, claims: &UserClaims) -> bool {
+        let scope_refs: Vec<&str> = self.0.iter().map(|s| s.as_str()).collect();
+        claims.has_all_scopes(&scope_refs)
+    }
+}
+
 /// Composite guard that requires BOTH guards to pass (AND logic)
 ///
 /// # Example
@@ -167,6 +225,95 @@ impl AuthGuard for IsEnabled {
     }
 }
 
+/// Response returned when an authorization guard denies a request
+///
+/// Configured on [`PoemAppState`] via `with_denial_mode` and used by the
+/// `require_*` macros. `NotFound404` hides whether the resource exists from
+/// unauthorized users; the denial reason is then omitted from the body too.
+///
+/// # Example
+///
+/// ```ignore
+/// let state = PoemAppState::new("users.db", "my-super-secret-key")
+///     .await?
+///     .with_denial_mode(DenialMode::NotFound404);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DenialMode {
+    /// 403 Forbidden with the denial reason (default)
+    #[default]
+    Forbidden403,
+    /// 404 Not Found with a generic body
+    NotFound404,
+}
+
+impl DenialMode {
+    /// The mode configured on the global `PoemAppState`, or the default if
+    /// the state isn't initialized.
+    pub fn current() -> Self {
+        PoemAppState::try_get()
+            .map(|state| state.denial_mode)
+            .unwrap_or_default()
+    }
+
+    /// The mode configured on the state serving `req` (see
+    /// [`PoemAppState::for_request`]), or the default if there is none.
+    pub fn for_request(req: &poem::Request) -> Self {
+        PoemAppState::for_request(req)
+            .map(|state| state.denial_mode)
+            .unwrap_or_default()
+    }
+
+    /// Build the denial response, including `reason` only in 403 mode.
+    pub fn deny(self, reason: &str) -> Response {
+        match self {
+            DenialMode::Forbidden403 => (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({ "error": reason })),
+            )
+                .into_response(),
+            DenialMode::NotFound404 => (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Not Found" })),
+            )
+                .into_response(),
+        }
+    }
+
+    /// Build the denial response for an unmet group or scope requirement.
+    ///
+    /// In 403 mode the body adds the `required` names and the match `mode`
+    /// (`single`, `any` or `all`) so clients can explain what's missing, e.g.
+    /// `{"error": "...", "required": ["admins", "moderators"], "mode": "any"}`.
+    /// 404 mode reveals nothing, exactly like [`DenialMode::deny`].
+    pub fn deny_requirement(self, reason: &str, mode: &str, required: &[&str]) -> Response {
+        match self {
+            DenialMode::Forbidden403 => (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({
+                    "error": reason,
+                    "required": required,
+                    "mode": mode,
+                })),
+            )
+                .into_response(),
+            DenialMode::NotFound404 => self.deny(reason),
+        }
+    }
+
+    /// [`DenialMode::deny_requirement`] as a `poem::Error`, for handlers returning `Result`.
+    pub fn deny_requirement_error(self, reason: &str, mode: &str, required: &[&str]) -> poem::Error {
+        poem::Error::from_response(self.deny_requirement(reason, mode, required))
+    }
+
+    /// Build the denial as a `poem::Error`, for handlers returning `Result`.
+    ///
+    /// The error carries the same response as [`DenialMode::deny`].
+    pub fn deny_error(self, reason: &str) -> poem::Error {
+        poem::Error::from_response(self.deny(reason))
+    }
+}
+
 /// Helper functions for creating guards
 pub mod builders {
     use super::*;
@@ -185,6 +332,11 @@ pub mod builders {
     pub fn require_all_groups<S: Into<String>>(groups: Vec<S>) -> HasAllGroups {
         HasAllGroups(groups.into_iter().map(|s| s.into()).collect())
     }
+
+    /// Create a guard requiring authentication through the given provider
+    pub fn require_provider<S: Into<String>>(provider: S) -> HasProvider {
+        HasProvider(provider.into())
+    }
 }
 
 #[cfg(test)]
@@ -200,6 +352,7 @@ mod tests {
             exp: 1000,
             iat: 0,
             jti: "123".to_string(),
+            nbf: None,
             extra: None,
         };
 
@@ -219,6 +372,7 @@ mod tests {
             exp: 1000,
             iat: 0,
             jti: "123".to_string(),
+            nbf: None,
             extra: None,
         };
 
@@ -229,6 +383,27 @@ mod tests {
         assert!(!guard.check(&claims));
     }
 
+    #[test]
+    fn test_has_provider_guard() {
+        let mut claims = UserClaims::new("user", "local", 1000, 0);
+
+        let guard = builders::require_provider("ldap");
+        assert!(!guard.check(&claims));
+
+        claims.provider = "ldap".to_string();
+        assert!(guard.check(&claims));
+    }
+
+    #[test]
+    fn test_scope_guards() {
+        let claims = UserClaims::new("user", "local", 1000, 0).with_scopes(vec!["read:users"]);
+
+        assert!(HasAnyScope(vec!["admin".to_string(), "read:users".to_string()]).check(&claims));
+        assert!(!HasAnyScope(vec!["admin".to_string()]).check(&claims));
+        assert!(HasAllScopes(vec!["read:users".to_string()]).check(&claims));
+        assert!(!HasAllScopes(vec!["read:users".to_string(), "admin".to_string()]).check(&claims));
+    }
+
     #[test]
     fn test_and_guard() {
         let claims = UserClaims {
@@ -238,19 +413,20 @@ mod tests {
             exp: 1000,
             iat: 0,
             jti: "123".to_string(),
+            nbf: None,
             extra: None,
         };
 
-        let guard = And(
-            HasGroup("admin".to_string()),
-            HasGroup("verified".to_string()),
-        );
+        let guard = And {
+            first: HasGroup("admin".to_string()),
+            second: HasGroup("verified".to_string()),
+        };
         assert!(guard.check(&claims));
 
-        let guard = And(
-            HasGroup("admin".to_string()),
-            HasGroup("banned".to_string()),
-        );
+        let guard = And {
+            first: HasGroup("admin".to_string()),
+            second: HasGroup("banned".to_string()),
+        };
         assert!(!guard.check(&claims));
     }
 
@@ -263,13 +439,14 @@ mod tests {
             exp: 1000,
             iat: 0,
             jti: "123".to_string(),
+            nbf: None,
             extra: None,
         };
 
-        let guard = Or(
-            HasGroup("admin".to_string()),
-            HasGroup("moderator".to_string()),
-        );
+        let guard = Or {
+            first: HasGroup("admin".to_string()),
+            second: HasGroup("moderator".to_string()),
+        };
         assert!(guard.check(&claims));
     }
 
@@ -282,6 +459,7 @@ mod tests {
             exp: 1000,
             iat: 0,
             jti: "123".to_string(),
+            nbf: None,
             extra: None,
         };
 
@@ -291,4 +469,54 @@ mod tests {
         let guard = Not(HasGroup("user".to_string()));
         assert!(!guard.check(&claims));
     }
+
+    fn denied_claims() -> UserClaims {
+        UserClaims::new("bob", "local", 1000, 0).with_groups(vec!["users"])
+    }
+
+    #[tokio::test]
+    async fn test_denial_mode_toggles_status() {
+        let claims = denied_claims();
+        let guard = HasGroup("admins".to_string());
+        assert!(!guard.check(&claims));
+
+        let response = DenialMode::Forbidden403.deny("Forbidden: requires 'admins' group");
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = response.into_body().into_string().await.unwrap();
+        assert!(body.contains("admins"));
+
+        // Same denied request, but existence of the resource is hidden
+        let response = DenialMode::NotFound404.deny("Forbidden: requires 'admins' group");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = response.into_body().into_string().await.unwrap();
+        assert!(!body.contains("admins"));
+    }
+
+    #[tokio::test]
+    async fn test_deny_requirement_body() {
+        let response = DenialMode::Forbidden403.deny_requirement("nope", "all", &["a", "b"]);
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_body().into_string().await.unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({"error": "nope", "required": ["a", "b"], "mode": "all"}));
+
+        // 404 mode doesn't reveal the requirement
+        let response = DenialMode::NotFound404.deny_requirement("nope", "all", &["a", "b"]);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(!response.into_body().into_string().await.unwrap().contains("required"));
+    }
+
+    #[tokio::test]
+    async fn test_deny_error_keeps_denial_response() {
+        let response = DenialMode::Forbidden403.deny_error("nope").into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(response.into_body().into_string().await.unwrap().contains("nope"));
+    }
+
+    #[test]
+    fn test_denial_mode_defaults_to_forbidden() {
+        assert_eq!(DenialMode::default(), DenialMode::Forbidden403);
+        // Without an initialized PoemAppState, the default applies
+        assert_eq!(DenialMode::current(), DenialMode::Forbidden403);
+    }
 }
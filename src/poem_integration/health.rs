@@ -0,0 +1,54 @@
+//! Readiness checks for the authentication stack
+//!
+//! Provides a composite health report over the auth provider, the user
+//! database and JWT signing, suitable for backing a Kubernetes readiness
+//! endpoint.
+
+use poem::{http::StatusCode, IntoResponse, Response, web::Json};
+use serde::Serialize;
+
+/// Result of [`PoemAppState::health_check`](crate::poem_integration::PoemAppState::health_check).
+///
+/// Each component is reported as a plain pass/fail so the report can be
+/// returned to unauthenticated probes without leaking error details; failures
+/// are logged server-side instead.
+///
+/// # Example
+///
+/// ```ignore
+/// use poem::{handler, IntoResponse};
+/// use poem_auth::poem_integration::PoemAppState;
+///
+/// #[handler]
+/// async fn ready() -> impl IntoResponse {
+///     // 200 when healthy, 503 otherwise
+///     PoemAppState::get().health_check().await
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HealthStatus {
+    /// The authentication provider passed `validate_config`
+    pub provider: bool,
+    /// The user database answered a ping
+    pub database: bool,
+    /// A throwaway token could be signed and verified
+    pub jwt: bool,
+}
+
+impl HealthStatus {
+    /// Check whether every component is healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.provider && self.database && self.jwt
+    }
+}
+
+impl IntoResponse for HealthStatus {
+    fn into_response(self) -> Response {
+        let status = if self.is_healthy() {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        (status, Json(self)).into_response()
+    }
+}
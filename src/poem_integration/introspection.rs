@@ -0,0 +1,85 @@
+//! Token introspection for resource servers without the signing key
+//!
+//! Mirrors OAuth 2.0 token introspection (RFC 7662): a trusted caller submits
+//! a token and learns whether it is active and, if so, whose it is.
+
+use crate::api::types::IntrospectionResponse;
+use crate::jwt::JwtValidator;
+use crate::middleware::MasterCredentials;
+
+/// Verify a submitted token on behalf of an authenticated service.
+///
+/// Requiring [`MasterCredentials`] keeps the endpoint from becoming a token
+/// oracle for anonymous callers. Take them as a handler parameter: the
+/// extractor validates HTTP Basic credentials with the route's
+/// [`MasterAuth`](crate::middleware::MasterAuth) data and rejects everyone
+/// else with 401. Any verification failure (bad signature, expired,
+/// malformed) yields `{"active": false}`.
+///
+/// # Example
+///
+/// ```ignore
+/// use poem::{handler, post, web::Json, EndpointExt, Route};
+/// use poem_auth::api::types::{IntrospectionRequest, IntrospectionResponse};
+/// use poem_auth::middleware::{MasterAuth, MasterCredentials};
+/// use poem_auth::poem_integration::{introspect, PoemAppState};
+///
+/// #[handler]
+/// async fn introspection(
+///     creds: MasterCredentials,
+///     Json(req): Json<IntrospectionRequest>,
+/// ) -> Json<IntrospectionResponse> {
+///     Json(introspect(&creds, &PoemAppState::get().jwt, &req.token))
+/// }
+///
+/// let app = Route::new()
+///     .at("/introspect", post(introspection))
+///     .data(MasterAuth::new("resource-server", &master_hash));
+/// ```
+pub fn introspect(
+    credentials: &MasterCredentials,
+    validator: &JwtValidator,
+    token: &str,
+) -> IntrospectionResponse {
+    match validator.verify_token(token) {
+        Ok(claims) => {
+            tracing::debug!(caller = %credentials.username, sub = %claims.sub, "Introspected active token");
+            IntrospectionResponse::active(&claims)
+        }
+        Err(e) => {
+            tracing::debug!(caller = %credentials.username, error = %e, "Introspected inactive token");
+            IntrospectionResponse::inactive()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::UserClaims;
+
+    const SECRET: &str = "introspection-test-secret-32-bytes!!";
+
+    #[test]
+    fn test_introspect_active_and_expired_tokens() {
+        let validator = JwtValidator::new(SECRET).unwrap();
+        let caller = MasterCredentials::new("resource-server".to_string());
+        let now = chrono::Utc::now().timestamp();
+
+        let claims = UserClaims::new("alice", "local", now + 3600, now).with_groups(vec!["users"]);
+        let token = validator.generate_token(&claims).unwrap();
+        let response = introspect(&caller, &validator, &token.token);
+        assert!(response.active);
+        assert_eq!(response.sub.as_deref(), Some("alice"));
+        assert_eq!(response.groups, Some(vec!["users".to_string()]));
+        assert_eq!(response.exp, Some(now + 3600));
+        assert_eq!(response.iat, Some(now));
+        assert_eq!(response.provider.as_deref(), Some("local"));
+
+        let expired = UserClaims::new("alice", "local", now - 3600, now - 7200);
+        let token = validator.generate_token(&expired).unwrap();
+        assert_eq!(introspect(&caller, &validator, &token.token), IntrospectionResponse::inactive());
+
+        assert!(!introspect(&caller, &validator, "not-a-token").active);
+    }
+}
@@ -1,15 +1,95 @@
 //! Simple helpers for implementing JWT login endpoints in Poem handlers
 //!
 //! This module provides convenient response constructors for creating login endpoints
-//! with minimal boilerplate.
+//! with minimal boilerplate, and [`login`], which runs the authenticate-then-sign
+//! flow and reports a typed [`LoginOutcome`].
 
 use poem::{Response, http::StatusCode, IntoResponse};
 use poem::web::Json;
 use serde_json::json;
 
 use crate::auth::UserClaims;
-use crate::api::types::{LoginResponse, UserClaimsResponse};
+use crate::api::types::{LoginRequest, LoginResponse};
+use crate::error::AuthError;
 use crate::jwt::Token;
+use crate::poem_integration::PoemAppState;
+
+/// Result of a login attempt made with [`login`].
+#[derive(Debug)]
+pub enum LoginOutcome {
+    /// The credentials were accepted and a token was signed.
+    Success {
+        /// Claims returned by the provider
+        claims: UserClaims,
+        /// The signed access token
+        token: Token,
+    },
+    /// The username or password is wrong, or the provider rejected them.
+    InvalidCredentials,
+    /// The password matched, but the account is disabled.
+    Disabled,
+    /// The provider's backend failed in a way a retry could fix, e.g. a
+    /// database or LDAP outage (see [`AuthError::is_retryable`]).
+    Unavailable(AuthError),
+    /// The provider accepted the credentials but the token couldn't be signed.
+    TokenError(AuthError),
+}
+
+/// Authenticate `req` with the state's provider and sign a token for it.
+///
+/// Retryable provider failures (e.g. a database error) are logged and
+/// reported as [`LoginOutcome::Unavailable`], so an outage doesn't look like
+/// a wrong password. Other failures except [`AuthError::UserDisabled`] are
+/// reported as [`LoginOutcome::InvalidCredentials`], so callers can't tell a
+/// missing user from a wrong password.
+/// [`LocalAuthProvider`](crate::providers::LocalAuthProvider) only reports a
+/// disabled account once the password matches, so `Disabled` doesn't reveal
+/// account status to callers without the password; custom providers should
+/// do the same.
+///
+/// # Example
+///
+/// ```ignore
+/// use poem::{handler, web::Json, Response};
+/// use poem_auth::api::types::LoginRequest;
+/// use poem_auth::poem_integration::{login, LoginOutcome, LoginResponseBuilder, PoemAppState};
+///
+/// #[handler]
+/// async fn login_handler(Json(req): Json<LoginRequest>) -> Response {
+///     match login(PoemAppState::get(), &req).await {
+///         LoginOutcome::Success { claims, token } => LoginResponseBuilder::success(&claims, &token),
+///         LoginOutcome::InvalidCredentials => LoginResponseBuilder::invalid_credentials(),
+///         LoginOutcome::Disabled => LoginResponseBuilder::user_disabled(&req.username),
+///         LoginOutcome::Unavailable(_) => LoginResponseBuilder::service_unavailable(),
+///         LoginOutcome::TokenError(_) => LoginResponseBuilder::token_generation_failed(),
+///     }
+/// }
+/// ```
+pub async fn login(state: &PoemAppState, req: &LoginRequest) -> LoginOutcome {
+    let claims = match state.provider.authenticate(&req.username, &req.password).await {
+        Ok(claims) => claims,
+        Err(AuthError::UserDisabled) => return LoginOutcome::Disabled,
+        Err(AuthError::InvalidCredentials | AuthError::UserNotFound) => {
+            return LoginOutcome::InvalidCredentials
+        }
+        Err(e) if e.is_retryable() => {
+            tracing::error!(error = %e, provider = %state.provider.name(), "Login backend unavailable");
+            return LoginOutcome::Unavailable(e);
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, provider = %state.provider.name(), "Login failed");
+            return LoginOutcome::InvalidCredentials;
+        }
+    };
+
+    match state.jwt.generate_token(&claims) {
+        Ok(token) => LoginOutcome::Success { claims, token },
+        Err(e) => {
+            tracing::error!(error = %e, sub = %claims.sub, "Failed to sign login token");
+            LoginOutcome::TokenError(e)
+        }
+    }
+}
 
 /// Helper for constructing JWT login responses with minimal boilerplate.
 ///
@@ -20,24 +100,23 @@ use crate::jwt::Token;
 ///
 /// ```ignore
 /// use poem_auth::{PoemAppState, LoginResponseBuilder};
+/// use poem_auth::poem_integration::{login, LoginOutcome};
 /// use poem::web::Json;
 /// use poem_auth::api::types::LoginRequest;
 /// use poem::{handler, Response};
 ///
 /// #[handler]
-/// async fn login(Json(req): Json<LoginRequest>) -> Response {
-///     let state = PoemAppState::get();
-///     match state.provider.authenticate(&req.username, &req.password).await {
-///         Ok(claims) => {
-///             match state.jwt.generate_token(&claims) {
-///                 Ok(token_data) => LoginResponseBuilder::success(&claims, &token_data),
-///                 Err(_) => LoginResponseBuilder::token_generation_failed(),
-///             }
-///         }
-///         Err(_) => LoginResponseBuilder::invalid_credentials(),
+/// async fn login_handler(Json(req): Json<LoginRequest>) -> Response {
+///     match login(PoemAppState::get(), &req).await {
+///         LoginOutcome::Success { claims, token } => LoginResponseBuilder::success(&claims, &token),
+///         LoginOutcome::InvalidCredentials => LoginResponseBuilder::invalid_credentials(),
+///         LoginOutcome::Disabled => LoginResponseBuilder::user_disabled(&req.username),
+///         LoginOutcome::Unavailable(_) => LoginResponseBuilder::service_unavailable(),
+///         LoginOutcome::TokenError(_) => LoginResponseBuilder::token_generation_failed(),
 ///     }
 /// }
 /// ```
+#[derive(Debug)]
 pub struct LoginResponseBuilder;
 
 impl LoginResponseBuilder {
@@ -54,15 +133,35 @@ impl LoginResponseBuilder {
     /// let response = LoginResponseBuilder::success(&claims, &token_data);
     /// ```
     pub fn success(claims: &UserClaims, token_data: &Token) -> Response {
-        let expires_in = claims.exp - claims.iat;
-        let login_response = LoginResponse {
+        Self::respond(claims, token_data, None)
+    }
+
+    /// Build a successful login response (HTTP 200) that includes a refresh token.
+    ///
+    /// Same as [`success`](Self::success), plus a `refresh_token` field.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let response = LoginResponseBuilder::success_with_refresh(&claims, &access, &refresh);
+    /// ```
+    pub fn success_with_refresh(claims: &UserClaims, access: &Token, refresh: &str) -> Response {
+        Self::respond(claims, access, Some(refresh.to_string()))
+    }
+
+    fn respond(claims: &UserClaims, token_data: &Token, refresh_token: Option<String>) -> Response {
+        (StatusCode::OK, Json(Self::body(claims, token_data, refresh_token))).into_response()
+    }
+
+    /// The [`LoginResponse`] body sent by [`success`](Self::success).
+    pub(crate) fn body(claims: &UserClaims, token_data: &Token, refresh_token: Option<String>) -> LoginResponse {
+        LoginResponse {
             token: token_data.token.clone(),
             token_type: "Bearer".to_string(),
-            expires_in,
-            claims: UserClaimsResponse::from_claims(claims.clone()),
-        };
-
-        (StatusCode::OK, Json(login_response)).into_response()
+            expires_in: claims.exp - claims.iat,
+            claims: claims.to_response(),
+            refresh_token,
+        }
     }
 
     /// Build a response for invalid credentials (returns 401).
@@ -89,6 +188,18 @@ impl LoginResponseBuilder {
             .into_response()
     }
 
+    /// Build a response for an unavailable authentication backend (returns 503).
+    pub fn service_unavailable() -> Response {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "service_unavailable",
+                "message": "Authentication service is temporarily unavailable"
+            })),
+        )
+            .into_response()
+    }
+
     /// Build a response for disabled user (returns 403).
     pub fn user_disabled(username: &str) -> Response {
         (
@@ -129,30 +240,169 @@ impl LoginResponseBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use crate::auth::AuthProvider;
+    use crate::db::{MemoryUserDb, UserDatabase, UserRecord};
+    use crate::providers::LocalAuthProvider;
+
+    const SECRET: &str = "login-helper-test-secret-32-bytes!!";
+
+    async fn local_state() -> PoemAppState {
+        let db = Arc::new(MemoryUserDb::new());
+        let hash = crate::password::hash_password("s3cret").unwrap();
+        db.create_user(UserRecord::new("alice", &hash).with_groups(vec!["users"])).await.unwrap();
+        db.create_user(UserRecord::new("bob", &hash).disable()).await.unwrap();
+        let provider = Arc::new(LocalAuthProvider::with_db(db.clone()));
+        PoemAppState::with_provider(provider, SECRET).unwrap().with_user_db(db)
+    }
+
+    fn request(username: &str, password: &str) -> LoginRequest {
+        LoginRequest {
+            username: username.to_string(),
+            password: password.to_string(),
+            provider: None,
+        }
+    }
+
+    /// Accepts everyone but hands back claims that can't be signed
+    #[derive(Debug)]
+    struct BrokenClaimsProvider;
+
+    #[async_trait]
+    impl AuthProvider for BrokenClaimsProvider {
+        async fn authenticate(&self, username: &str, _password: &str) -> Result<UserClaims, AuthError> {
+            Ok(UserClaims::new(username, "broken", 1000, 2000))
+        }
+
+        fn name(&self) -> &str {
+            "broken"
+        }
+    }
+
+    /// Fails every login the way a provider does during a database outage
+    #[derive(Debug)]
+    struct DownProvider;
+
+    #[async_trait]
+    impl AuthProvider for DownProvider {
+        async fn authenticate(&self, _username: &str, _password: &str) -> Result<UserClaims, AuthError> {
+            Err(AuthError::database("connection refused"))
+        }
+
+        fn name(&self) -> &str {
+            "down"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_success() {
+        let state = local_state().await;
+        match login(&state, &request("alice", "s3cret")).await {
+            LoginOutcome::Success { claims, token } => {
+                assert_eq!(claims.sub, "alice");
+                assert_eq!(claims.groups, vec!["users".to_string()]);
+                assert_eq!(state.jwt.verify_token(&token.token).unwrap().sub, "alice");
+            }
+            other => panic!("expected success, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_invalid_credentials() {
+        let state = local_state().await;
+        assert!(matches!(
+            login(&state, &request("alice", "wrong")).await,
+            LoginOutcome::InvalidCredentials
+        ));
+        assert!(matches!(
+            login(&state, &request("nobody", "s3cret")).await,
+            LoginOutcome::InvalidCredentials
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_login_disabled() {
+        let state = local_state().await;
+        assert!(matches!(
+            login(&state, &request("bob", "s3cret")).await,
+            LoginOutcome::Disabled
+        ));
+        // Only the right password learns that the account is disabled
+        assert!(matches!(
+            login(&state, &request("bob", "wrong")).await,
+            LoginOutcome::InvalidCredentials
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_login_token_error() {
+        let state = PoemAppState::with_provider(Arc::new(BrokenClaimsProvider), SECRET).unwrap();
+        assert!(matches!(
+            login(&state, &request("alice", "anything")).await,
+            LoginOutcome::TokenError(AuthError::JwtError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_login_backend_unavailable() {
+        let state = PoemAppState::with_provider(Arc::new(DownProvider), SECRET).unwrap();
+        assert!(matches!(
+            login(&state, &request("alice", "s3cret")).await,
+            LoginOutcome::Unavailable(AuthError::DatabaseError(_))
+        ));
+
+        let response = LoginResponseBuilder::service_unavailable();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body_json(response).await["error"], "service_unavailable");
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let body = response.into_body().into_string().await.unwrap();
+        serde_json::from_str(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_login_response_builder_refresh_token() {
+        let claims = UserClaims::new("alice", "local", 2000, 1000);
+        let access = Token {
+            token: "access123".to_string(),
+            expires_at: 2000,
+            ttl: 1000,
+        };
+
+        let json = body_json(LoginResponseBuilder::success(&claims, &access)).await;
+        assert_eq!(json["token"], "access123");
+        assert!(json.get("refresh_token").is_none());
+
+        let response = LoginResponseBuilder::success_with_refresh(&claims, &access, "refresh456");
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["token"], "access123");
+        assert_eq!(json["refresh_token"], "refresh456");
+    }
 
     #[test]
     fn test_login_response_builder_invalid_credentials() {
         let response = LoginResponseBuilder::invalid_credentials();
-        // We can't directly test Response, but this ensures the method compiles
-        // and returns a valid Response
-        assert!(true);
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
     #[test]
     fn test_login_response_builder_token_generation_failed() {
         let response = LoginResponseBuilder::token_generation_failed();
-        assert!(true);
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
 
     #[test]
     fn test_login_response_builder_user_disabled() {
         let response = LoginResponseBuilder::user_disabled("alice");
-        assert!(true);
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
     }
 
     #[test]
     fn test_login_response_builder_user_not_found() {
         let response = LoginResponseBuilder::user_not_found();
-        assert!(true);
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 }
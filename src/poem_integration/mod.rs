@@ -1,15 +1,26 @@
-/// Poem web framework integration module
-///
-/// Provides utilities for integrating poem_auth with the Poem web framework,
-/// including shared state management, automatic claim extraction, authorization guards,
-/// and login response builders for simplified endpoint implementation.
+//! Poem web framework integration module
+//!
+//! Provides utilities for integrating poem_auth with the Poem web framework,
+//! including shared state management, automatic claim extraction, authorization guards,
+//! and login response builders for simplified endpoint implementation.
 
 pub mod app_state;
+pub mod async_guard;
+pub mod auth_layer;
+pub mod endpoint_ext;
+mod error_response;
 pub mod extractors;
 pub mod guards;
+pub mod health;
+pub mod introspection;
 pub mod login_helper;
 
 pub use app_state::PoemAppState;
-pub use extractors::*;
-pub use guards::{AuthGuard, HasGroup, HasAnyGroup, HasAllGroups, And, Or, Not, IsEnabled};
-pub use login_helper::LoginResponseBuilder;
+pub use async_guard::AsyncAuthGuard;
+pub use auth_layer::{AuthLayer, ClaimsExt};
+pub use endpoint_ext::{AuthEndpointExt, RequireGuard};
+pub use extractors::TokenSource;
+pub use guards::{AuthGuard, DenialMode, HasGroup, HasAnyGroup, HasAllGroups, HasAnyScope, HasAllScopes, HasProvider, And, Or, Not, IsEnabled};
+pub use health::HealthStatus;
+pub use introspection::introspect;
+pub use login_helper::{login, LoginOutcome, LoginResponseBuilder};
@@ -26,6 +26,7 @@ use crate::error::AuthError;
 ///     group_filter: "(member={user_dn})".to_string(),
 ///     use_tls: false,
 ///     timeout_seconds: Some(10),
+///     username_suffix: None,
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +50,11 @@ pub struct LdapConfig {
 
     /// Connection timeout in seconds
     pub timeout_seconds: Option<u64>,
+
+    /// Only accept usernames ending with this suffix (e.g., "@corp.com")
+    /// when routing logins that don't name a provider. None accepts all.
+    #[serde(default)]
+    pub username_suffix: Option<String>,
 }
 
 impl LdapConfig {
@@ -92,6 +98,7 @@ impl LdapConfig {
 ///     group_filter: "(member={user_dn})".to_string(),
 ///     use_tls: false,
 ///     timeout_seconds: Some(10),
+///     username_suffix: None,
 /// };
 ///
 /// let provider = LdapAuthProvider::new(config)?;
@@ -176,6 +183,24 @@ impl AuthProvider for LdapAuthProvider {
         Ok(UserClaims::new(username, "ldap", expiration, now))
     }
 
+    async fn refresh_groups(&self, username: &str) -> Result<Vec<String>, AuthError> {
+        if username.is_empty() {
+            return Err(AuthError::UserNotFound);
+        }
+
+        // A real implementation would bind with a service account and run
+        // the group filter for this user, as `authenticate` does after the
+        // user bind. Like `authenticate`, the stub finds no groups.
+        let user_dn = self.format_user_dn(username);
+        let _filter = self
+            .config
+            .group_filter
+            .replace("{user_dn}", &user_dn)
+            .replace("{username}", username);
+
+        Ok(Vec::new())
+    }
+
     fn name(&self) -> &str {
         "ldap"
     }
@@ -193,6 +218,13 @@ impl AuthProvider for LdapAuthProvider {
             self.config.server, self.config.base_dn
         )
     }
+
+    fn supports_username(&self, username: &str) -> bool {
+        match &self.config.username_suffix {
+            Some(suffix) => username.ends_with(suffix.as_str()),
+            None => true,
+        }
+    }
 }
 
 #[cfg(not(feature = "ldap"))]
@@ -222,6 +254,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: Some(10),
+            username_suffix: None,
         };
 
         assert!(valid_config.validate().is_ok());
@@ -236,6 +269,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         assert!(config.validate().is_err());
@@ -250,6 +284,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         assert!(config.validate().is_err());
@@ -264,6 +299,7 @@ mod tests {
             group_filter: String::new(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         assert!(config.validate().is_err());
@@ -278,6 +314,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: Some(10),
+            username_suffix: None,
         };
 
         let provider = LdapAuthProvider::new(config);
@@ -293,6 +330,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         let provider = LdapAuthProvider::new(config);
@@ -308,6 +346,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         let provider = LdapAuthProvider::new(config).unwrap();
@@ -323,6 +362,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         let provider = LdapAuthProvider::new(config).unwrap();
@@ -340,6 +380,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         let provider = LdapAuthProvider::new(config).unwrap();
@@ -356,6 +397,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         let provider = LdapAuthProvider::new(config).unwrap();
@@ -372,6 +414,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         let provider = LdapAuthProvider::new(config).unwrap();
@@ -388,6 +431,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         let provider = LdapAuthProvider::new(config).unwrap();
@@ -404,6 +448,7 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: Some(30),
+            username_suffix: None,
         };
 
         let config2 = LdapConfig {
@@ -413,9 +458,27 @@ mod tests {
             group_filter: "(member={user_dn})".to_string(),
             use_tls: false,
             timeout_seconds: None,
+            username_suffix: None,
         };
 
         assert_eq!(config1.timeout(), Duration::from_secs(30));
         assert_eq!(config2.timeout(), Duration::from_secs(10));
     }
+
+    #[test]
+    fn test_supports_username_suffix() {
+        let config = LdapConfig {
+            server: "ldap://dc.example.com".to_string(),
+            base_dn: "DC=example,DC=com".to_string(),
+            bind_dn_template: None,
+            group_filter: "(member={user_dn})".to_string(),
+            use_tls: false,
+            timeout_seconds: None,
+            username_suffix: Some("@corp.com".to_string()),
+        };
+
+        let provider = LdapAuthProvider::new(config).unwrap();
+        assert!(provider.supports_username("alice@corp.com"));
+        assert!(!provider.supports_username("bob"));
+    }
 }
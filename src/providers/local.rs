@@ -6,10 +6,15 @@
 use async_trait::async_trait;
 use std::sync::Arc;
 
-use crate::auth::{AuthProvider, UserClaims};
+use crate::auth::{AuthProvider, ProviderDiagnostics, UserClaims};
 use crate::db::UserDatabase;
 use crate::error::AuthError;
-use crate::password;
+use crate::password::{Argon2Hasher, PasswordHasher};
+
+/// Argon2id hash (same parameters as `hash_password`) verified against when a
+/// user doesn't exist, so unknown usernames cost as much as wrong passwords.
+const DUMMY_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$QEBl7d2OpYrnz9KYaDVJkg$3/dFKXAtWw3OEwfGGC+lLpF2Ny05W05xpkLQIaiXLQ0";
 
 /// Authentication provider backed by a local user database.
 ///
@@ -19,6 +24,18 @@ use crate::password;
 /// 3. Checking that the user is enabled
 /// 4. Returning claims with the user's username and groups
 ///
+/// Unknown usernames still run an Argon2 verification against a dummy hash
+/// and fail with `AuthError::InvalidCredentials`, so a login can't reveal
+/// whether an account exists through either the error or the response time.
+/// Likewise `AuthError::UserDisabled` is only returned once the password
+/// matches.
+///
+/// Passwords are checked with [`Argon2Hasher`] unless another
+/// [`PasswordHasher`] is set with [`with_hasher`](Self::with_hasher).
+///
+/// Usernames are used exactly as given unless
+/// [`with_normalized_usernames`](Self::with_normalized_usernames) is enabled.
+///
 /// # Example
 ///
 /// ```ignore
@@ -34,6 +51,13 @@ use crate::password;
 #[derive(Debug)]
 pub struct LocalAuthProvider {
     db: Arc<dyn UserDatabase>,
+    hasher: Box<dyn PasswordHasher>,
+    /// Hash verified against for unknown users, produced by `hasher`
+    dummy_hash: String,
+    /// Trim usernames before lookup and use the result as `sub`
+    trim_usernames: bool,
+    /// Also lowercase usernames (only with `trim_usernames`)
+    lowercase_usernames: bool,
 }
 
 impl LocalAuthProvider {
@@ -43,34 +67,108 @@ impl LocalAuthProvider {
     ///
     /// * `db` - A user database implementation
     pub fn new<D: UserDatabase + 'static>(db: D) -> Self {
-        Self {
-            db: Arc::new(db),
-        }
+        Self::with_db(Arc::new(db))
     }
 
     /// Create a new provider with an Arc-wrapped database.
     pub fn with_db(db: Arc<dyn UserDatabase>) -> Self {
-        Self { db }
+        Self {
+            db,
+            hasher: Box::new(Argon2Hasher),
+            dummy_hash: DUMMY_PASSWORD_HASH.to_string(),
+            trim_usernames: false,
+            lowercase_usernames: false,
+        }
     }
-}
 
-#[async_trait]
-impl AuthProvider for LocalAuthProvider {
-    async fn authenticate(
+    /// Use `hasher` instead of Argon2 to verify passwords.
+    ///
+    /// Stored hashes must have been produced by the same algorithm. Hashes
+    /// one dummy password up front so unknown usernames still cost a real
+    /// verification.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let provider = LocalAuthProvider::new(db).with_hasher(Box::new(BcryptHasher));
+    /// ```
+    pub fn with_hasher(mut self, hasher: Box<dyn PasswordHasher>) -> Self {
+        self.dummy_hash = hasher.hash("poem-auth-dummy-password").unwrap_or_default();
+        self.hasher = hasher;
+        self
+    }
+
+    /// Normalize usernames before they are looked up and become `claims.sub`
+    /// (default: off).
+    ///
+    /// Surrounding whitespace is always trimmed; with `lowercase` the name is
+    /// also lowercased, so `"Alice "` and `"alice"` yield the same `sub`.
+    /// Only enable lowercasing if usernames are stored in lowercase, or
+    /// mixed-case accounts will no longer be found.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let provider = LocalAuthProvider::new(db).with_normalized_usernames(true);
+    /// let claims = provider.authenticate(" Alice", "password123").await?;
+    /// assert_eq!(claims.sub, "alice");
+    /// ```
+    pub fn with_normalized_usernames(mut self, lowercase: bool) -> Self {
+        self.trim_usernames = true;
+        self.lowercase_usernames = lowercase;
+        self
+    }
+
+    /// Apply the configured username normalization.
+    fn normalize_username<'a>(&self, username: &'a str) -> std::borrow::Cow<'a, str> {
+        if !self.trim_usernames {
+            return username.into();
+        }
+        let trimmed = username.trim();
+        if self.lowercase_usernames {
+            trimmed.to_lowercase().into()
+        } else {
+            trimmed.into()
+        }
+    }
+
+    /// Get the underlying user database.
+    pub fn db(&self) -> Arc<dyn UserDatabase> {
+        self.db.clone()
+    }
+
+    /// The actual credential check behind `authenticate`.
+    async fn check_credentials(
         &self,
         username: &str,
         password: &str,
     ) -> Result<UserClaims, AuthError> {
-        // Get user from database
-        let user = self.db.get_user(username).await?;
+        let username = self.normalize_username(username);
+        let username = username.as_ref();
 
-        // Check if user is enabled
-        if !user.enabled {
-            return Err(AuthError::UserDisabled);
+        // An empty username can never match, so skip the hashing work
+        if username.trim().is_empty() {
+            return Err(AuthError::InvalidCredentials);
         }
 
-        // Verify password hash
-        password::verify_password(password, &user.password_hash)?;
+        // Get user from database; a missing user fails like a wrong password
+        let user = match self.db.get_user(username).await {
+            Ok(user) => user,
+            Err(AuthError::UserNotFound) => {
+                let _ = self.hasher.verify(password, &self.dummy_hash);
+                return Err(AuthError::InvalidCredentials);
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Verify password hash first, so a wrong password on a disabled
+        // account fails like any other wrong password
+        self.hasher.verify(password, &user.password_hash)?;
+
+        // Check if user is enabled (soft-deleted users are not)
+        if !user.is_enabled() {
+            return Err(AuthError::UserDisabled);
+        }
 
         // Generate claims
         let now = chrono::Utc::now().timestamp();
@@ -79,15 +177,52 @@ impl AuthProvider for LocalAuthProvider {
         Ok(UserClaims::new(username, "local", expiration, now)
             .with_groups(user.groups))
     }
+}
+
+#[async_trait]
+impl AuthProvider for LocalAuthProvider {
+    async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<UserClaims, AuthError> {
+        let result = self.check_credentials(username, password).await;
+        crate::telemetry::record_authentication(self.name(), &result);
+        result
+    }
+
+    /// Reads the user's groups from the database.
+    ///
+    /// Fails with `AuthError::UserDisabled` for disabled or soft-deleted
+    /// users, so a token can't be re-issued for an account that was locked
+    /// mid-session.
+    async fn refresh_groups(&self, username: &str) -> Result<Vec<String>, AuthError> {
+        let user = self.db.get_user(&self.normalize_username(username)).await?;
+        if !user.is_enabled() {
+            return Err(AuthError::UserDisabled);
+        }
+        Ok(user.groups)
+    }
 
     fn name(&self) -> &str {
         "local"
     }
 
     async fn validate_config(&self) -> Result<(), AuthError> {
-        // Try to list users to validate database connectivity
-        self.db.list_users().await?;
-        Ok(())
+        // Validate database connectivity
+        self.db.ping().await
+    }
+
+    async fn diagnose(&self) -> ProviderDiagnostics {
+        let report = ProviderDiagnostics::new(self.name());
+        let report = match self.db.ping().await {
+            Ok(()) => report.with_check("database", true, "reachable"),
+            Err(e) => report.with_check("database", false, e.to_string()),
+        };
+        match self.db.count_users().await {
+            Ok(count) => report.with_check("user_count", true, count.to_string()),
+            Err(e) => report.with_check("user_count", false, e.to_string()),
+        }
     }
 
     fn info(&self) -> String {
@@ -100,17 +235,10 @@ mod tests {
     use super::*;
     use crate::db::sqlite::SqliteUserDb;
     use crate::password;
-    use tempfile::TempDir;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     async fn test_provider() -> Result<LocalAuthProvider, AuthError> {
-        let temp_dir = TempDir::new()
-            .map_err(|e| AuthError::database(e.to_string()))?;
-        let db_path = temp_dir.path().join("test.db");
-        let path = db_path.to_str()
-            .ok_or_else(|| AuthError::database("Invalid temp path".to_string()))?;
-
-        let db = SqliteUserDb::new(path).await?;
-        std::mem::forget(temp_dir);
+        let db = SqliteUserDb::in_memory().await?;
         let provider = LocalAuthProvider::new(db);
 
         // Create a test user
@@ -134,6 +262,17 @@ mod tests {
         assert!(claims.exp > claims.iat);
     }
 
+    #[tokio::test]
+    async fn test_diagnose_reports_user_count() {
+        let provider = test_provider().await.unwrap();
+        let report = provider.diagnose().await;
+
+        assert_eq!(report.provider, "local");
+        assert!(report.is_healthy());
+        assert!(report.check("database").unwrap().passed);
+        assert_eq!(report.check("user_count").unwrap().detail, "1");
+    }
+
     #[tokio::test]
     async fn test_authenticate_wrong_password() {
         let provider = test_provider().await.unwrap();
@@ -146,13 +285,86 @@ mod tests {
         assert!(provider.authenticate("nonexistent", "password").await.is_err());
     }
 
+    #[tokio::test]
+    async fn test_authenticate_empty_username() {
+        let provider = test_provider().await.unwrap();
+        for username in ["", "  "] {
+            assert!(matches!(
+                provider.authenticate(username, "password").await,
+                Err(AuthError::InvalidCredentials)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_user_and_wrong_password_are_indistinguishable() {
+        let provider = test_provider().await.unwrap();
+
+        let missing = provider.authenticate("nonexistent", "password").await;
+        let wrong = provider.authenticate("alice", "wrong_password").await;
+        assert!(matches!(missing, Err(AuthError::InvalidCredentials)));
+        assert!(matches!(wrong, Err(AuthError::InvalidCredentials)));
+
+        // Management paths still report the missing user
+        assert!(matches!(
+            provider.db.get_user("nonexistent").await,
+            Err(AuthError::UserNotFound)
+        ));
+    }
+
+    /// Hasher storing passwords as `plain:<password>` and counting verifications.
+    #[derive(Debug, Default)]
+    struct PlainHasher {
+        verifications: Arc<AtomicUsize>,
+    }
+
+    impl PasswordHasher for PlainHasher {
+        fn hash(&self, password: &str) -> Result<String, AuthError> {
+            Ok(format!("plain:{}", password))
+        }
+
+        fn verify(&self, password: &str, hash: &str) -> Result<(), AuthError> {
+            self.verifications.fetch_add(1, Ordering::SeqCst);
+            if hash == format!("plain:{}", password) {
+                Ok(())
+            } else {
+                Err(AuthError::InvalidCredentials)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_hasher() {
+        let db = SqliteUserDb::in_memory().await.unwrap();
+        db.create_user(crate::db::UserRecord::new("carol", "plain:s3cret")).await.unwrap();
+
+        let hasher = PlainHasher::default();
+        let verifications = hasher.verifications.clone();
+        let provider = LocalAuthProvider::new(db).with_hasher(Box::new(hasher));
+
+        let claims = provider.authenticate("carol", "s3cret").await.unwrap();
+        assert_eq!(claims.sub, "carol");
+        assert!(matches!(
+            provider.authenticate("carol", "wrong").await,
+            Err(AuthError::InvalidCredentials)
+        ));
+        // Unknown users are verified against the hasher's own dummy hash
+        assert!(matches!(
+            provider.authenticate("nobody", "s3cret").await,
+            Err(AuthError::InvalidCredentials)
+        ));
+        assert_eq!(verifications.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_dummy_hash_is_valid() {
+        let result = password::verify_password("not-the-password", DUMMY_PASSWORD_HASH);
+        assert!(matches!(result, Err(AuthError::InvalidCredentials)));
+    }
+
     #[tokio::test]
     async fn test_authenticate_disabled_user() {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-        let path = db_path.to_str().unwrap();
-        let db = SqliteUserDb::new(path).await.unwrap();
-        std::mem::forget(temp_dir);
+        let db = SqliteUserDb::in_memory().await.unwrap();
 
         let password_hash = password::hash_password("test123").unwrap();
         let user = crate::db::UserRecord::new("bob", &password_hash)
@@ -160,7 +372,21 @@ mod tests {
         db.create_user(user).await.unwrap();
 
         let provider = LocalAuthProvider::new(db);
-        assert!(provider.authenticate("bob", "test123").await.is_err());
+        assert!(matches!(provider.authenticate("bob", "test123").await, Err(AuthError::UserDisabled)));
+        // A wrong password doesn't reveal that the account is disabled
+        assert!(matches!(
+            provider.authenticate("bob", "wrong").await,
+            Err(AuthError::InvalidCredentials)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_soft_deleted_user() {
+        let provider = test_provider().await.unwrap();
+        provider.db.soft_delete_user("alice").await.unwrap();
+
+        let result = provider.authenticate("alice", "test123").await;
+        assert!(matches!(result, Err(AuthError::UserDisabled)));
     }
 
     #[tokio::test]
@@ -191,6 +417,55 @@ mod tests {
         assert!(!claims.has_group("nonexistent"));
     }
 
+    #[tokio::test]
+    async fn test_normalized_usernames() {
+        let provider = test_provider().await.unwrap();
+        // Raw by default: the padded name is a different (unknown) user
+        assert!(matches!(
+            provider.authenticate("alice ", "test123").await,
+            Err(AuthError::InvalidCredentials)
+        ));
+
+        let provider = provider.with_normalized_usernames(false);
+        let claims = provider.authenticate("  alice\t", "test123").await.unwrap();
+        assert_eq!(claims.sub, "alice");
+        assert!(provider.authenticate("Alice", "test123").await.is_err());
+
+        let provider = provider.with_normalized_usernames(true);
+        for username in ["alice", "Alice ", " ALICE", "aLiCe"] {
+            let claims = provider.authenticate(username, "test123").await.unwrap();
+            assert_eq!(claims.sub, "alice", "input {:?}", username);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_groups_reads_current_membership() {
+        let provider = test_provider().await.unwrap();
+        let claims = provider.authenticate("alice", "test123").await.unwrap();
+        assert_eq!(claims.groups, vec!["admins", "users"]);
+
+        provider
+            .db
+            .update_groups("alice", vec!["users".to_string(), "auditors".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            provider.refresh_groups("alice").await.unwrap(),
+            vec!["users", "auditors"]
+        );
+
+        let alice = provider.db.get_user("alice").await.unwrap();
+        provider.db.update_user(alice.disable()).await.unwrap();
+        assert!(matches!(
+            provider.refresh_groups("alice").await,
+            Err(AuthError::UserDisabled)
+        ));
+        assert!(matches!(
+            provider.refresh_groups("nobody").await,
+            Err(AuthError::UserNotFound)
+        ));
+    }
+
     #[tokio::test]
     async fn test_claims_expiration() {
         let provider = test_provider().await.unwrap();
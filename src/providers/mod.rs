@@ -3,11 +3,13 @@
 //! This module provides ready-to-use authentication implementations.
 
 pub mod local;
+pub mod registry;
 
 #[cfg(feature = "ldap")]
 pub mod ldap;
 
 pub use local::LocalAuthProvider;
+pub use registry::ProviderRegistry;
 
 #[cfg(feature = "ldap")]
 pub use ldap::{LdapAuthProvider, LdapConfig};
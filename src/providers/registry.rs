@@ -0,0 +1,303 @@
+//! Registry for routing logins across multiple authentication providers.
+//!
+//! When a login request names a provider, the registry dispatches to it
+//! directly. Otherwise it picks the first registered provider whose
+//! [`AuthProvider::supports_username`] accepts the username, so clients
+//! don't need to know which backend holds their account.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::types::LoginRequest;
+use crate::auth::{AuthProvider, UserClaims};
+use crate::config::AuthConfig;
+use crate::error::AuthError;
+
+/// Ordered collection of authentication providers.
+///
+/// Providers are consulted in registration order, so register the most
+/// specific providers (e.g. LDAP matching a domain suffix) before catch-all
+/// ones like the local database.
+///
+/// Each provider can get its own token lifetime (e.g. short for password
+/// logins, longer for LDAP); the registry then sets `exp` on the claims it
+/// returns to `iat` plus that lifetime.
+///
+/// # Example
+///
+/// ```ignore
+/// use poem_auth::providers::{LdapAuthProvider, LocalAuthProvider, ProviderRegistry};
+///
+/// let registry = ProviderRegistry::new()
+///     .register(LdapAuthProvider::new(ldap_config)?)
+///     .register(LocalAuthProvider::new(db));
+///
+/// // `alice@corp.com` goes to LDAP, `bob` falls through to the local DB
+/// let claims = registry.authenticate(&login_request).await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProviderRegistry {
+    providers: Vec<Arc<dyn AuthProvider>>,
+    expirations: HashMap<String, Duration>,
+}
+
+impl ProviderRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a provider to the end of the routing order.
+    pub fn register<P: AuthProvider + 'static>(self, provider: P) -> Self {
+        self.register_arc(Arc::new(provider))
+    }
+
+    /// Add an already shared provider to the end of the routing order.
+    pub fn register_arc(mut self, provider: Arc<dyn AuthProvider>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    /// Set the token lifetime for logins through the named provider.
+    ///
+    /// Overrides whatever `exp` the provider itself chose.
+    pub fn with_expiration(mut self, provider: &str, lifetime: Duration) -> Self {
+        self.expirations.insert(provider.to_string(), lifetime);
+        self
+    }
+
+    /// Set every registered provider's token lifetime from `config`.
+    ///
+    /// Uses each provider's `[providers.<name>] expiration_hours`, falling
+    /// back to `jwt.expiration_hours`. Call it after registering providers.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let registry = ProviderRegistry::new()
+    ///     .register(LdapAuthProvider::new(ldap_config)?)
+    ///     .register(LocalAuthProvider::new(db))
+    ///     .with_config_expirations(&config);
+    /// ```
+    pub fn with_config_expirations(mut self, config: &AuthConfig) -> Self {
+        for provider in &self.providers {
+            let name = provider.name().to_string();
+            let lifetime = config.expiration_for(&name);
+            self.expirations.insert(name, lifetime);
+        }
+        self
+    }
+
+    /// Token lifetime configured for the named provider, if any.
+    pub fn expiration(&self, provider: &str) -> Option<Duration> {
+        self.expirations.get(provider).copied()
+    }
+
+    /// Look up a provider by its [`AuthProvider::name`].
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn AuthProvider>> {
+        self.providers.iter().find(|p| p.name() == name)
+    }
+
+    /// Names of the registered providers, in routing order.
+    pub fn names(&self) -> Vec<&str> {
+        self.providers.iter().map(|p| p.name()).collect()
+    }
+
+    /// Number of registered providers.
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    /// Check whether no providers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Pick the provider for a login.
+    ///
+    /// An explicit `provider` name wins; otherwise the first provider whose
+    /// `supports_username` accepts `username` is chosen.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::ProviderNotFound` if the named provider isn't
+    /// registered or no provider accepts the username.
+    pub fn resolve(
+        &self,
+        provider: Option<&str>,
+        username: &str,
+    ) -> Result<&Arc<dyn AuthProvider>, AuthError> {
+        match provider {
+            Some(name) => self
+                .get(name)
+                .ok_or_else(|| AuthError::ProviderNotFound(name.to_string())),
+            None => self
+                .providers
+                .iter()
+                .find(|p| p.supports_username(username))
+                .ok_or_else(|| {
+                    AuthError::ProviderNotFound(format!("no provider accepts '{}'", username))
+                }),
+        }
+    }
+
+    /// Authenticate a login request against the resolved provider.
+    ///
+    /// If the provider has a configured lifetime, the returned claims expire
+    /// that long after their `iat`.
+    pub async fn authenticate(&self, request: &LoginRequest) -> Result<UserClaims, AuthError> {
+        let provider = self.resolve(request.provider.as_deref(), &request.username)?;
+        let mut claims = provider.authenticate(&request.username, &request.password).await?;
+        if let Some(lifetime) = self.expiration(provider.name()) {
+            claims.exp = claims.iat + lifetime.as_secs() as i64;
+        }
+        Ok(claims)
+    }
+
+    /// Run the logout hook of the provider that issued `claims`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::ProviderNotFound` if `claims.provider` isn't
+    /// registered, or whatever the provider's `logout` returns.
+    pub async fn logout(&self, claims: &UserClaims) -> Result<(), AuthError> {
+        let provider = self
+            .get(&claims.provider)
+            .ok_or_else(|| AuthError::ProviderNotFound(claims.provider.clone()))?;
+        provider.logout(claims).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    /// Provider that only accepts usernames ending in a domain suffix.
+    #[derive(Debug)]
+    struct DomainProvider {
+        suffix: &'static str,
+    }
+
+    #[async_trait]
+    impl AuthProvider for DomainProvider {
+        async fn authenticate(&self, username: &str, _password: &str) -> Result<UserClaims, AuthError> {
+            Ok(UserClaims::new(username, "corp", 0, 0))
+        }
+
+        fn name(&self) -> &str {
+            "corp"
+        }
+
+        fn supports_username(&self, username: &str) -> bool {
+            username.ends_with(self.suffix)
+        }
+    }
+
+    /// Catch-all provider using the default `supports_username`.
+    #[derive(Debug)]
+    struct FallbackProvider;
+
+    #[async_trait]
+    impl AuthProvider for FallbackProvider {
+        async fn authenticate(&self, username: &str, _password: &str) -> Result<UserClaims, AuthError> {
+            Ok(UserClaims::new(username, "local", 0, 0))
+        }
+
+        fn name(&self) -> &str {
+            "local"
+        }
+    }
+
+    fn login(username: &str, provider: Option<&str>) -> LoginRequest {
+        LoginRequest {
+            username: username.to_string(),
+            password: "password".to_string(),
+            provider: provider.map(str::to_string),
+        }
+    }
+
+    fn registry() -> ProviderRegistry {
+        ProviderRegistry::new()
+            .register(DomainProvider { suffix: "@corp.com" })
+            .register(FallbackProvider)
+    }
+
+    #[tokio::test]
+    async fn test_routes_by_username_pattern() {
+        let registry = registry();
+
+        let claims = registry.authenticate(&login("alice@corp.com", None)).await.unwrap();
+        assert_eq!(claims.provider, "corp");
+
+        let claims = registry.authenticate(&login("bob", None)).await.unwrap();
+        assert_eq!(claims.provider, "local");
+    }
+
+    #[tokio::test]
+    async fn test_explicit_provider_overrides_routing() {
+        let registry = registry();
+
+        let claims = registry
+            .authenticate(&login("alice@corp.com", Some("local")))
+            .await
+            .unwrap();
+        assert_eq!(claims.provider, "local");
+
+        let result = registry.authenticate(&login("alice", Some("oauth2"))).await;
+        assert!(matches!(result, Err(AuthError::ProviderNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_logout_dispatches_by_claims_provider() {
+        let registry = registry();
+        let claims = registry.authenticate(&login("bob", None)).await.unwrap();
+        assert!(registry.logout(&claims).await.is_ok());
+
+        let foreign = UserClaims::new("bob", "oauth2", 0, 0);
+        assert!(matches!(
+            registry.logout(&foreign).await,
+            Err(AuthError::ProviderNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_per_provider_expiration_from_config() {
+        let config: AuthConfig = toml::from_str(
+            r#"
+            users = []
+
+            [database]
+            path = "users.db"
+
+            [jwt]
+            secret = "my-super-secret-key"
+            expiration_hours = 1
+
+            [providers.corp]
+            expiration_hours = 12
+            "#,
+        )
+        .unwrap();
+        let registry = registry().with_config_expirations(&config);
+
+        let corp = registry.authenticate(&login("alice@corp.com", None)).await.unwrap();
+        assert_eq!(corp.exp - corp.iat, 12 * 3600);
+
+        // No override for "local", so jwt.expiration_hours applies
+        let local = registry.authenticate(&login("bob", None)).await.unwrap();
+        assert_eq!(local.exp - local.iat, 3600);
+    }
+
+    #[test]
+    fn test_no_provider_accepts_username() {
+        let registry = ProviderRegistry::new().register(DomainProvider { suffix: "@corp.com" });
+        assert!(matches!(
+            registry.resolve(None, "bob"),
+            Err(AuthError::ProviderNotFound(_))
+        ));
+        assert_eq!(registry.names(), vec!["corp"]);
+        assert!(ProviderRegistry::new().is_empty());
+    }
+}
@@ -1,15 +1,19 @@
-/// Quick-start initialization for poem_auth
-///
-/// Provides a single function to initialize the entire authentication system
-/// from a TOML configuration file, handling database creation, user setup,
-/// and component initialization.
+//! Quick-start initialization for poem_auth
+//!
+//! Provides a single function to initialize the entire authentication system
+//! from a TOML configuration file, handling database creation, user setup,
+//! and component initialization.
+
+use std::fmt;
 
+use crate::auth::{AuthProvider, ProviderDiagnostics};
 use crate::config::AuthConfig;
 use crate::db::sqlite::SqliteUserDb;
 use crate::db::{UserDatabase, UserRecord};
-use crate::password::hash_password;
+use crate::error::AuthError;
 use crate::providers::LocalAuthProvider;
 use crate::jwt::JwtValidator;
+use crate::middleware::RateLimit;
 use crate::poem_integration::PoemAppState;
 
 /// Initialize authentication system from configuration file
@@ -22,13 +26,18 @@ use crate::poem_integration::PoemAppState;
 /// 5. Initializes LocalAuthProvider and JwtValidator
 /// 6. Sets up global PoemAppState
 ///
+/// Progress is reported through `tracing` events rather than stdout, and
+/// the returned [`InitSummary`] describes what was set up.
+///
 /// # Arguments
 ///
 /// * `config_path` - Path to TOML configuration file
 ///
 /// # Errors
 ///
-/// Returns error if any step fails (file not found, invalid config, database error, etc.)
+/// Returns `AuthError::ConfigError` if the configuration can't be loaded or
+/// is invalid, or if `PoemAppState` was already initialized. Database and
+/// JWT setup failures are returned as their own `AuthError` variants.
 ///
 /// # Example
 ///
@@ -54,27 +63,29 @@ use crate::poem_integration::PoemAppState;
 /// ```
 pub async fn initialize_from_config(
     config_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<InitSummary, AuthError> {
     // Load and validate config
     let config = AuthConfig::from_file(config_path)?;
-    config.validate()?;
+    config.validate().map_err(AuthError::config)?;
 
-    println!("=== Initializing Authentication System ===\n");
+    tracing::info!("Initializing authentication system");
 
     // Initialize database
-    println!("Step 1: Initialize database at '{}'", config.database.path);
     let db = SqliteUserDb::new(&config.database.path).await?;
-    println!("✓ Database initialized\n");
+    tracing::info!(path = %config.database.path, "Database initialized");
 
     // Create users from config
-    println!("Step 2: Create users from configuration");
+    let hasher = config.password.hasher()?;
+    let mut users_created = Vec::new();
+    let mut users_existing = Vec::new();
     for user_config in &config.users {
         match db.get_user(&user_config.username).await {
             Ok(_) => {
-                println!("  - {} (already exists)", user_config.username);
+                tracing::debug!(username = %user_config.username, "User already exists");
+                users_existing.push(user_config.username.clone());
             }
             Err(_) => {
-                let hash = hash_password(&user_config.password)?;
+                let hash = hasher.hash(&user_config.password)?;
                 let mut user = UserRecord::new(&user_config.username, &hash);
 
                 if !user_config.groups.is_empty() {
@@ -86,92 +97,147 @@ pub async fn initialize_from_config(
                 }
 
                 db.create_user(user).await?;
-                println!(
-                    "  ✓ Created: {} with groups {:?}",
-                    user_config.username, user_config.groups
+                tracing::info!(
+                    username = %user_config.username,
+                    groups = ?user_config.groups,
+                    "Created user from configuration"
                 );
+                users_created.push(user_config.username.clone());
             }
         }
     }
-    println!();
 
     // Create auth components
-    println!("Step 3: Create authentication components");
-    let provider = std::sync::Arc::new(LocalAuthProvider::new(db));
+    let db: std::sync::Arc<dyn UserDatabase> = std::sync::Arc::new(db);
+    let provider = std::sync::Arc::new(LocalAuthProvider::with_db(db.clone()).with_hasher(hasher));
     let jwt = std::sync::Arc::new(JwtValidator::new(&config.jwt.secret)?);
-    println!("✓ LocalAuthProvider created");
-    println!("✓ JwtValidator created\n");
+    let diagnostics = provider.diagnose().await;
+    if diagnostics.is_healthy() {
+        tracing::info!("{}", diagnostics);
+    } else {
+        tracing::warn!("{}", diagnostics);
+    }
 
     // Initialize global state
     let app_state = PoemAppState {
         provider,
+        user_db: Some(db),
         jwt,
         server_config: config.server.clone(),
+        token_source: Default::default(),
+        denial_mode: Default::default(),
+        rate_limit: std::sync::Arc::new(RateLimit::try_new(config.rate_limit.clone())?),
     };
-    app_state.init().map_err(|_| {
-        Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Failed to initialize PoemAppState - already initialized"
-        )) as Box<dyn std::error::Error>
-    })?;
-
-    // Print summary
-    println!("✅ Authentication system initialized successfully!");
-    println!("\nConfiguration:");
-    println!("  Database: {}", config.database.path);
-    println!(
-        "  JWT Secret: {}...{}",
-        &config.jwt.secret[..8],
-        &config.jwt.secret[config.jwt.secret.len() - 4..]
-    );
-    println!("  Token Expiration: {} hours", config.jwt.expiration_hours);
-    println!("  Users: {}", config.users.len());
-
-    if let Some(server) = &config.server {
-        let protocol = if server.tls.as_ref().map(|t| t.enabled).unwrap_or(false) {
-            "https"
-        } else {
-            "http"
-        };
-        println!(
-            "\nServer: {}://{}:{}",
-            protocol, server.host, server.port
-        );
+    app_state
+        .init()
+        .map_err(|_| AuthError::config("Failed to initialize PoemAppState - already initialized"))?;
 
-        if let Some(tls) = &server.tls {
-            if tls.enabled {
-                println!("  🔒 TLS: Enabled");
-                println!("      Certificate: {}", tls.certificate);
-                println!("      Key: {}", tls.key);
-                if let Some(ca) = &tls.ca_chain {
-                    println!("      CA Chain: {}", ca);
-                }
-            }
+    let summary = InitSummary {
+        database_path: config.database.path.clone(),
+        token_expiration_hours: config.jwt.expiration_hours,
+        users_created,
+        users_existing,
+        server_url: config.server.as_ref().map(|server| {
+            let protocol = if server.tls.as_ref().map(|t| t.enabled).unwrap_or(false) {
+                "https"
+            } else {
+                "http"
+            };
+            format!("{}://{}:{}", protocol, server.host, server.port)
+        }),
+        diagnostics,
+    };
+    tracing::info!("Authentication system initialized");
+
+    Ok(summary)
+}
+
+/// What [`initialize_from_config`] set up.
+///
+/// Initialization doesn't print anything itself; log or print this (its
+/// `Display` output is a short multi-line report) or ignore it. It never
+/// contains the JWT secret.
+///
+/// # Example
+///
+/// ```ignore
+/// let summary = initialize_from_config("auth.toml").await?;
+/// println!("{}", summary);
+/// ```
+#[derive(Debug, Clone)]
+pub struct InitSummary {
+    /// Path of the SQLite database
+    pub database_path: String,
+    /// Configured token lifetime in hours
+    pub token_expiration_hours: u32,
+    /// Configured users that were created by this run
+    pub users_created: Vec<String>,
+    /// Configured users that already existed
+    pub users_existing: Vec<String>,
+    /// Base URL of the configured server, if any
+    pub server_url: Option<String>,
+    /// Startup diagnostics of the local provider
+    pub diagnostics: ProviderDiagnostics,
+}
+
+impl fmt::Display for InitSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Database: {}", self.database_path)?;
+        writeln!(f, "Token Expiration: {} hours", self.token_expiration_hours)?;
+        writeln!(
+            f,
+            "Users: {} created, {} existing",
+            self.users_created.len(),
+            self.users_existing.len()
+        )?;
+        if let Some(url) = &self.server_url {
+            writeln!(f, "Server: {}", url)?;
         }
+        write!(f, "{}", self.diagnostics)
     }
-    println!();
-
-    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use std::process::Command;
     use tempfile::TempDir;
 
-    #[tokio::test]
-    async fn test_initialize_from_config_file() {
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("auth.toml");
+    /// Set when the test binary re-runs a single test in a child process.
+    const CHILD_ENV: &str = "POEM_AUTH_QUICK_START_CHILD";
+
+    const SECRET: &str = "my-super-secret-key-should-be-at-least-16-chars";
+
+    /// Check whether this is the child process started by `run_in_child`.
+    fn in_child() -> bool {
+        std::env::var_os(CHILD_ENV).is_some()
+    }
+
+    /// Re-run one test of this binary in a fresh process.
+    ///
+    /// Needed for tests that must call `initialize_from_config`, which sets
+    /// process-global state, or that inspect the real stdout.
+    fn run_in_child(test: &str) -> std::process::Output {
+        Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", test, "--nocapture"])
+            .env(CHILD_ENV, "1")
+            .output()
+            .unwrap()
+    }
 
-        let config_content = r#"
+    fn write_config(dir: &Path, secret: &str) -> String {
+        let config_path = dir.join("auth.toml");
+        let config_content = format!(
+            r#"
 [database]
-path = "test.db"
+path = "{}"
 auto_create = true
 
 [jwt]
-secret = "my-super-secret-key-should-be-at-least-16-chars"
+secret = "{}"
 expiration_hours = 24
 
 [[users]]
@@ -179,13 +245,94 @@ username = "alice"
 password = "password123"
 groups = ["users"]
 enabled = true
-"#;
+"#,
+            dir.join("test.db").display(),
+            secret
+        );
 
         fs::write(&config_path, config_content).unwrap();
+        config_path.to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_initialize_from_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = write_config(temp_dir.path(), SECRET);
 
         // Note: This test would need cleanup of global state
         // For now, we just test that it doesn't panic
-        let result = initialize_from_config(config_path.to_str().unwrap()).await;
-        assert!(result.is_ok());
+        let summary = initialize_from_config(&config_path).await.unwrap();
+        assert_eq!(summary.users_created, vec!["alice"]);
+        assert!(summary.users_existing.is_empty());
+        assert_eq!(summary.diagnostics.check("user_count").unwrap().detail, "1");
+        assert!(!summary.to_string().contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_initialize_writes_nothing_to_stdout() {
+        // libtest captures stdout in-process, so run this test again in a
+        // child process and inspect what it really wrote
+        if in_child() {
+            let temp_dir = TempDir::new().unwrap();
+            let config_path = write_config(temp_dir.path(), SECRET);
+            print!("<begin>");
+            std::io::stdout().flush().unwrap();
+            initialize_from_config(&config_path).await.unwrap();
+            print!("<end>");
+            std::io::stdout().flush().unwrap();
+            return;
+        }
+
+        let output = run_in_child("quick_start::tests::test_initialize_writes_nothing_to_stdout");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(output.status.success(), "child failed: {}", stdout);
+        assert!(stdout.contains("<begin><end>"), "unexpected stdout: {}", stdout);
+    }
+
+    #[tokio::test]
+    async fn test_initialize_with_multibyte_secret() {
+        if in_child() {
+            let secret = "secrété-très-longue-clé-🔑";
+            // Byte 8 falls inside the second "é"
+            assert!(!secret.is_char_boundary(8));
+
+            let temp_dir = TempDir::new().unwrap();
+            let config_path = write_config(temp_dir.path(), secret);
+            let summary = initialize_from_config(&config_path).await.unwrap();
+            assert_eq!(summary.users_created, vec!["alice"]);
+            return;
+        }
+
+        let output = run_in_child("quick_start::tests::test_initialize_with_multibyte_secret");
+        assert!(
+            output.status.success(),
+            "initialization failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_initialize_hashes_with_configured_pepper() {
+        if in_child() {
+            let temp_dir = TempDir::new().unwrap();
+            let config_path = write_config(temp_dir.path(), SECRET);
+            let mut file = fs::OpenOptions::new().append(true).open(&config_path).unwrap();
+            writeln!(file, "\n[password]\npepper = \"server-side-pepper\"\npepper_id = \"v1\"").unwrap();
+
+            initialize_from_config(&config_path).await.unwrap();
+            let state = PoemAppState::get();
+            let user = state.user_db.as_ref().unwrap().get_user("alice").await.unwrap();
+            assert!(user.password_hash.starts_with("$pepper$v1$"));
+            assert!(state.provider.authenticate("alice", "password123").await.is_ok());
+            return;
+        }
+
+        let output = run_in_child("quick_start::tests::test_initialize_hashes_with_configured_pepper");
+        assert!(
+            output.status.success(),
+            "initialization failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
     }
 }
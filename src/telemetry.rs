@@ -0,0 +1,131 @@
+//! Metrics hooks for authentication outcomes.
+//!
+//! With the `metrics` feature enabled, these hooks emit through the
+//! [`metrics`](https://docs.rs/metrics) facade, so whatever exporter the
+//! application installs (e.g. Prometheus) picks them up. Without the feature
+//! they are empty and compile away.
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `auth_success_total` | counter | `provider` |
+//! | `auth_failure_total` | counter | `provider`, `reason` |
+//! | `auth_token_verify_seconds` | histogram | `outcome` (`ok` / `error`) |
+//! | `rate_limit_rejected_total` | counter | `limit` (`general` / `auth`) |
+
+use crate::error::AuthError;
+
+/// Counter of successful provider authentications.
+pub const AUTH_SUCCESS_TOTAL: &str = "auth_success_total";
+/// Counter of failed provider authentications, labelled by `reason`.
+pub const AUTH_FAILURE_TOTAL: &str = "auth_failure_total";
+/// Histogram of JWT verification latency in seconds.
+pub const AUTH_TOKEN_VERIFY_SECONDS: &str = "auth_token_verify_seconds";
+/// Counter of requests rejected by the rate limiter.
+pub const RATE_LIMIT_REJECTED_TOTAL: &str = "rate_limit_rejected_total";
+
+/// Record the outcome of a provider's `authenticate` call.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+#[inline]
+pub(crate) fn record_authentication<T>(provider: &str, result: &Result<T, AuthError>) {
+    #[cfg(feature = "metrics")]
+    match result {
+        Ok(_) => {
+            metrics::counter!(AUTH_SUCCESS_TOTAL, "provider" => provider.to_string()).increment(1)
+        }
+        Err(err) => metrics::counter!(
+            AUTH_FAILURE_TOTAL,
+            "provider" => provider.to_string(),
+            "reason" => err.kind()
+        )
+        .increment(1),
+    }
+}
+
+/// Run a token verification, recording its latency.
+#[inline]
+pub(crate) fn time_verification<T>(verify: impl FnOnce() -> Result<T, AuthError>) -> Result<T, AuthError> {
+    #[cfg(feature = "metrics")]
+    {
+        let started = std::time::Instant::now();
+        let result = verify();
+        let outcome = if result.is_ok() { "ok" } else { "error" };
+        metrics::histogram!(AUTH_TOKEN_VERIFY_SECONDS, "outcome" => outcome)
+            .record(started.elapsed().as_secs_f64());
+        result
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    verify()
+}
+
+/// Record a request rejected by the `general` or `auth` rate limit.
+#[cfg(feature = "rate-limit")]
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+#[inline]
+pub(crate) fn record_rate_limited(limit: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(RATE_LIMIT_REJECTED_TOTAL, "limit" => limit).increment(1);
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use crate::auth::AuthProvider;
+    use crate::db::{MemoryUserDb, UserDatabase, UserRecord};
+    use crate::providers::LocalAuthProvider;
+    use metrics::{Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    /// Recorder that only tracks the auth outcome counters.
+    #[derive(Default)]
+    struct TestRecorder {
+        success: Arc<AtomicU64>,
+        failure: Arc<AtomicU64>,
+    }
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            match key.name() {
+                AUTH_SUCCESS_TOTAL => Counter::from_arc(self.success.clone()),
+                AUTH_FAILURE_TOTAL => Counter::from_arc(self.failure.clone()),
+                _ => Counter::noop(),
+            }
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn test_successful_login_increments_counter() {
+        // The local recorder is thread-local, so drive the login on this thread
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let provider = rt.block_on(async {
+            let db = MemoryUserDb::new();
+            let hash = crate::password::hash_password("password123").unwrap();
+            db.create_user(UserRecord::new("alice", &hash)).await.unwrap();
+            LocalAuthProvider::new(db)
+        });
+
+        let recorder = TestRecorder::default();
+        metrics::with_local_recorder(&recorder, || {
+            rt.block_on(async {
+                provider.authenticate("alice", "password123").await.unwrap();
+                provider.authenticate("alice", "wrong").await.unwrap_err();
+            })
+        });
+
+        assert_eq!(recorder.success.load(Ordering::Relaxed), 1);
+        assert_eq!(recorder.failure.load(Ordering::Relaxed), 1);
+    }
+}
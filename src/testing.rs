@@ -0,0 +1,86 @@
+//! Helpers for testing auth-protected handlers.
+//!
+//! Available when the `testing` feature is enabled. Enable it for your
+//! tests only:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! poem_auth = { version = "0.1", features = ["testing"] }
+//! ```
+//!
+//! [`test_state`] installs a global [`PoemAppState`] backed by an in-memory
+//! user database, and [`test_token`] signs a token with it, so handlers that
+//! extract `UserClaims` (including the `require_*` macros) can be called
+//! without a database file or a configured secret.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use poem::{Endpoint, Request};
+//! use poem_auth::testing::test_token;
+//!
+//! #[tokio::test]
+//! async fn admin_panel_allows_admins() {
+//!     let req = Request::builder()
+//!         .header("Authorization", test_token("alice", vec!["admins"]))
+//!         .finish();
+//!     let resp = admin_panel.get_response(req).await;
+//!     assert_eq!(resp.status(), 200);
+//! }
+//! ```
+
+use std::sync::Arc;
+
+use crate::auth::UserClaims;
+use crate::db::MemoryUserDb;
+use crate::poem_integration::PoemAppState;
+use crate::providers::LocalAuthProvider;
+
+/// Lifetime of tokens issued by [`test_token`], in seconds.
+const TEST_TOKEN_TTL: i64 = 3600;
+
+/// Get the global app state, initializing a test one if needed.
+///
+/// The first call installs a state with an empty [`MemoryUserDb`], a
+/// [`LocalAuthProvider`] over it and a random JWT secret. If the state was
+/// already initialized (by the application or an earlier call), that state
+/// is returned unchanged.
+///
+/// # Panics
+///
+/// Panics if the test state can't be built.
+pub fn test_state() -> &'static PoemAppState {
+    PoemAppState::get_or_init(|| {
+        let db = Arc::new(MemoryUserDb::new());
+        let provider = Arc::new(LocalAuthProvider::with_db(db.clone()));
+        let secret = hex::encode(rand::random::<[u8; 32]>());
+        PoemAppState::with_provider(provider, &secret)
+            .expect("test JWT secret is valid")
+            .with_user_db(db)
+    })
+}
+
+/// Build an `Authorization` header value for `sub` in `groups`.
+///
+/// The token is signed by [`test_state`]'s validator, comes from the
+/// `local` provider and expires in an hour.
+///
+/// # Panics
+///
+/// Panics if the token can't be signed (e.g. `sub` is empty).
+///
+/// # Example
+///
+/// ```ignore
+/// let header = test_token("alice", vec!["admins"]);
+/// assert!(header.starts_with("Bearer "));
+/// ```
+pub fn test_token<S: Into<String>>(sub: &str, groups: Vec<S>) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = UserClaims::new(sub, "local", now + TEST_TOKEN_TTL, now).with_groups(groups);
+    let token = test_state()
+        .jwt
+        .generate_token(&claims)
+        .expect("test token can be signed");
+    format!("Bearer {}", token.token)
+}
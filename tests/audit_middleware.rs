@@ -0,0 +1,72 @@
+//! Recording requests with `AuditMiddleware`.
+
+#![cfg(all(feature = "middleware", feature = "sqlite"))]
+
+use std::sync::Arc;
+
+use futures_util::TryStreamExt;
+use poem::http::StatusCode;
+use poem::{get, handler, Endpoint, EndpointExt, Request, Route};
+use poem_auth::providers::LocalAuthProvider;
+use poem_auth::{
+    AuditEvent, AuditFilter, AuditMiddleware, AuthEndpointExt, PoemAppState, SqliteUserDb,
+    UserClaims,
+};
+
+#[handler]
+fn whoami(claims: UserClaims) -> String {
+    claims.sub
+}
+
+async fn events(db: &SqliteUserDb) -> Vec<AuditEvent> {
+    db.export_events(&AuditFilter::new().with_event_type("http_request"))
+        .try_collect()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_requests_are_audited() {
+    let db = Arc::new(SqliteUserDb::in_memory().await.unwrap());
+    let provider = Arc::new(LocalAuthProvider::with_db(db.clone()));
+    let state = PoemAppState::with_provider(provider, "audit-middleware-test-key-0123456789").unwrap();
+    let now = chrono::Utc::now().timestamp();
+    let claims = UserClaims::new("alice", "local", now + 3600, now).with_groups(vec!["users"]);
+    let token = state.jwt.generate_token(&claims).unwrap().token;
+
+    let app = Route::new()
+        .at("/me", get(whoami))
+        .at("/admin", get(whoami).require_group("admins"))
+        .with(AuditMiddleware::new(db.clone()))
+        .data(state);
+    let request = |uri: &str| {
+        Request::builder()
+            .uri(uri.parse().unwrap())
+            .header("Authorization", format!("Bearer {}", token))
+            .finish()
+    };
+
+    assert_eq!(app.get_response(request("/me")).await.status(), StatusCode::OK);
+    assert_eq!(app.get_response(request("/admin")).await.status(), StatusCode::FORBIDDEN);
+    let anonymous = Request::builder().uri("/me".parse().unwrap()).finish();
+    assert_eq!(app.get_response(anonymous).await.status(), StatusCode::UNAUTHORIZED);
+
+    let events = events(&db).await;
+    assert_eq!(events.len(), 3);
+    let details: Vec<serde_json::Value> = events
+        .iter()
+        .map(|event| serde_json::from_str(event.details.as_deref().unwrap()).unwrap())
+        .collect();
+
+    assert_eq!(events[0].username.as_deref(), Some("alice"));
+    assert_eq!(events[0].provider, "local");
+    assert_eq!(details[0], serde_json::json!({"method": "GET", "path": "/me", "status": 200}));
+
+    assert_eq!(events[1].username.as_deref(), Some("alice"));
+    assert_eq!(details[1]["path"], "/admin");
+    assert_eq!(details[1]["status"], 403);
+
+    assert_eq!(events[2].username, None);
+    assert_eq!(events[2].provider, "anonymous");
+    assert_eq!(details[2]["status"], 401);
+}
@@ -0,0 +1,52 @@
+//! End-to-end tests for the `poem_auth_cli` binary.
+
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+use poem_auth::{hash_password, SqliteUserDb, UserDatabase, UserRecord};
+
+fn cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_poem_auth_cli"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+async fn seeded_db(dir: &tempfile::TempDir) -> String {
+    let path = dir.path().join("users.db").to_str().unwrap().to_string();
+    let db = SqliteUserDb::new(&path).await.unwrap();
+    let hash = hash_password("password123").unwrap();
+    db.create_user(UserRecord::new("alice", &hash).with_groups(vec!["admins", "users"]))
+        .await
+        .unwrap();
+    db.create_user(UserRecord::new("bob", &hash).disable()).await.unwrap();
+    path
+}
+
+#[tokio::test]
+async fn test_show_user() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = seeded_db(&dir).await;
+
+    let output = cli(&["show-user", "--db", &db, "alice"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("User: alice"));
+    assert!(stdout.contains("Enabled: Yes"));
+    assert!(stdout.contains("admins, users"));
+    assert!(stdout.contains("Created: "));
+    assert!(!stdout.contains("$argon2"));
+
+    let output = cli(&["show-user", "--db", &db, "bob"]);
+    assert!(String::from_utf8(output.stdout).unwrap().contains("Enabled: No"));
+}
+
+#[tokio::test]
+async fn test_show_missing_user_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = seeded_db(&dir).await;
+
+    let output = cli(&["show-user", "--db", &db, "nobody"]);
+    assert!(!output.status.success());
+}
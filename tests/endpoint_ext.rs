@@ -0,0 +1,76 @@
+//! Protecting routes with `AuthEndpointExt`.
+
+use std::sync::Arc;
+
+use poem::http::StatusCode;
+use poem::{get, handler, Endpoint, EndpointExt, Request, Route};
+use poem_auth::{AuthEndpointExt, AuthGuard, MemoryUserDb, PoemAppState, UserClaims};
+use poem_auth::providers::LocalAuthProvider;
+
+#[handler]
+fn whoami(claims: UserClaims) -> String {
+    claims.sub
+}
+
+/// Guard decided at runtime: only subjects on an allowlist.
+struct Allowlisted(Vec<String>);
+
+impl AuthGuard for Allowlisted {
+    fn check(&self, claims: &UserClaims) -> bool {
+        self.0.contains(&claims.sub)
+    }
+}
+
+fn state() -> PoemAppState {
+    let provider = Arc::new(LocalAuthProvider::new(MemoryUserDb::new()));
+    PoemAppState::with_provider(provider, "endpoint-ext-test-key-0123456789abcdef").unwrap()
+}
+
+fn request(state: &PoemAppState, uri: &str, sub: &str, groups: Vec<&str>) -> Request {
+    let now = chrono::Utc::now().timestamp();
+    let claims = UserClaims::new(sub, "local", now + 3600, now).with_groups(groups);
+    let token = state.jwt.generate_token(&claims).unwrap().token;
+    Request::builder()
+        .uri(uri.parse().unwrap())
+        .header("Authorization", format!("Bearer {}", token))
+        .finish()
+}
+
+#[tokio::test]
+async fn test_extension_methods_protect_routes() {
+    let state = state();
+    let app = Route::new()
+        .at("/admin", get(whoami).require_group("admins"))
+        .at("/staff", get(whoami).require_any_group(vec!["admins", "support"]))
+        .at("/ops", get(whoami).require_all_groups(vec!["ops", "oncall"]))
+        .at("/vip", get(whoami).require_guard(Allowlisted(vec!["carol".to_string()])))
+        .data(state.clone());
+
+    let resp = app.get_response(request(&state, "/admin", "alice", vec!["admins"])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "alice");
+
+    let resp = app.get_response(request(&state, "/admin", "bob", vec!["support"])).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let body: serde_json::Value = resp.into_body().into_json().await.unwrap();
+    assert_eq!(body["required"], serde_json::json!(["admins"]));
+    assert_eq!(body["mode"], "single");
+
+    let resp = app.get_response(request(&state, "/staff", "bob", vec!["support"])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = app.get_response(request(&state, "/ops", "dan", vec!["ops"])).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let resp = app.get_response(request(&state, "/ops", "dan", vec!["ops", "oncall"])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = app.get_response(request(&state, "/vip", "carol", vec![])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = app.get_response(request(&state, "/vip", "alice", vec!["admins"])).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = app
+        .get_response(Request::builder().uri("/admin".parse().unwrap()).finish())
+        .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,246 @@
+//! Runtime behaviour of the authorization macros.
+
+#![cfg(feature = "macros")]
+
+use std::sync::Arc;
+
+use poem::http::StatusCode;
+use poem::web::Json;
+use poem::{handler, Endpoint, EndpointExt, IntoResponse, Request, Response};
+use poem_auth::providers::LocalAuthProvider;
+use poem_auth::{
+    require_all_scopes, require_any_groups, require_any_scope, require_group, require_provider,
+    DenialMode, MemoryUserDb, PoemAppState, UserClaims,
+};
+
+#[require_any_scope("read:users", "admin")]
+#[handler]
+async fn list_users(claims: UserClaims) -> Response {
+    format!("users for {}", claims.sub).into_response()
+}
+
+#[require_all_scopes("read:users", "write:users")]
+#[handler]
+fn edit_user(claims: UserClaims) -> Response {
+    format!("edited by {}", claims.sub).into_response()
+}
+
+#[require_group("admins")]
+#[handler]
+async fn admin_json(claims: UserClaims) -> poem::Result<Json<serde_json::Value>> {
+    Ok(Json(serde_json::json!({ "admin": claims.sub })))
+}
+
+#[require_group("admins")]
+#[handler]
+fn admin_text(claims: UserClaims) -> Result<String, poem::Error> {
+    Ok(format!("admin {}", claims.sub))
+}
+
+#[require_any_groups("admins", "moderators", "support")]
+#[handler]
+async fn moderation(claims: UserClaims) -> Response {
+    format!("moderated by {}", claims.sub).into_response()
+}
+
+#[require_group("admins")]
+#[handler]
+async fn qualified_admin(claims: poem_auth::UserClaims) -> Response {
+    format!("admin {}", claims.sub).into_response()
+}
+
+/// Borrowed claims, e.g. a helper called by a handler that already has them.
+#[require_group("admins")]
+async fn borrowed_admin(claims: &UserClaims) -> Response {
+    format!("admin {}", claims.sub).into_response()
+}
+
+#[require_any_groups("admins", "support")]
+fn borrowed_support(claims: &UserClaims) -> Result<String, poem::Error> {
+    Ok(format!("support {}", claims.sub))
+}
+
+#[require_provider("ldap")]
+#[handler]
+async fn ldap_only(claims: UserClaims) -> Response {
+    format!("hello {} from {}", claims.sub, claims.provider).into_response()
+}
+
+/// Stacked requirements must all pass, checked from the top down.
+#[require_group("admins")]
+#[require_group("verified")]
+#[handler]
+async fn verified_admin(claims: UserClaims) -> Response {
+    format!("verified admin {}", claims.sub).into_response()
+}
+
+#[require_any_groups("admins", "support")]
+#[poem_auth::require_all_scopes("read:users")]
+#[handler]
+fn support_reader(claims: UserClaims) -> Result<String, poem::Error> {
+    Ok(format!("reader {}", claims.sub))
+}
+
+fn claims() -> UserClaims {
+    let now = chrono::Utc::now().timestamp();
+    UserClaims::new("alice", "local", now + 3600, now)
+}
+
+/// Request carrying pre-verified claims, as `AuthLayer` would leave them.
+fn request_with_scopes(scopes: Vec<&str>) -> Request {
+    Request::builder().extension(claims().with_scopes(scopes)).finish()
+}
+
+fn request_with_groups(groups: Vec<&str>) -> Request {
+    Request::builder().extension(claims().with_groups(groups)).finish()
+}
+
+async fn json_body(resp: Response) -> serde_json::Value {
+    serde_json::from_str(&resp.into_body().into_string().await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_any_groups_denial_lists_every_required_group() {
+    let resp = moderation.get_response(request_with_groups(vec!["users"])).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let body = json_body(resp).await;
+    assert_eq!(body["required"], serde_json::json!(["admins", "moderators", "support"]));
+    assert_eq!(body["mode"], "any");
+    assert_eq!(body["error"], "Forbidden: requires one of groups: admins, moderators, support");
+}
+
+#[tokio::test]
+async fn test_qualified_and_borrowed_claims_parameters() {
+    let resp = qualified_admin.get_response(request_with_groups(vec!["admins"])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = qualified_admin.get_response(request_with_groups(vec!["users"])).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let admin = claims().with_groups(vec!["admins"]);
+    let user = claims().with_groups(vec!["users"]);
+    assert_eq!(borrowed_admin(&admin).await.status(), StatusCode::OK);
+    assert_eq!(borrowed_admin(&user).await.status(), StatusCode::FORBIDDEN);
+
+    assert_eq!(borrowed_support(&admin).unwrap(), "support alice");
+    let err = borrowed_support(&user).unwrap_err();
+    assert_eq!(err.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_provider_requirement() {
+    let now = chrono::Utc::now().timestamp();
+    let ldap = UserClaims::new("alice", "ldap", now + 3600, now);
+    let resp = ldap_only.get_response(Request::builder().extension(ldap).finish()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "hello alice from ldap");
+
+    let resp = ldap_only.get_response(Request::builder().extension(claims()).finish()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let body = json_body(resp).await;
+    assert_eq!(body["error"], "Forbidden: requires authentication via 'ldap'");
+    assert_eq!(body["required"], serde_json::json!(["ldap"]));
+}
+
+#[tokio::test]
+async fn test_stacked_macros_combine_with_and() {
+    let resp = verified_admin
+        .get_response(request_with_groups(vec!["verified", "admins"]))
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "verified admin alice");
+
+    // The topmost failing requirement is reported
+    for (groups, missing) in [
+        (vec!["verified"], "admins"),
+        (vec!["admins"], "verified"),
+        (vec![], "admins"),
+    ] {
+        let resp = verified_admin.get_response(request_with_groups(groups)).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert_eq!(json_body(resp).await["required"], serde_json::json!([missing]));
+    }
+
+    let support = claims().with_groups(vec!["support"]);
+    let request = |claims: UserClaims| Request::builder().extension(claims).finish();
+    let resp = support_reader
+        .get_response(request(support.clone().with_scopes(vec!["read:users"])))
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = support_reader.get_response(request(support)).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let body = json_body(resp).await;
+    assert_eq!(body["required"], serde_json::json!(["read:users"]));
+    assert_eq!(body["mode"], "all");
+}
+
+#[tokio::test]
+async fn test_denial_mode_comes_from_request_state() {
+    let provider = Arc::new(LocalAuthProvider::new(MemoryUserDb::new()));
+    let state = PoemAppState::with_provider(provider, "macros-test-key-0123456789abcdef")
+        .unwrap()
+        .with_denial_mode(DenialMode::NotFound404);
+    let now = chrono::Utc::now().timestamp();
+    let claims = UserClaims::new("bob", "local", now + 3600, now).with_groups(vec!["users"]);
+    let token = state.jwt.generate_token(&claims).unwrap().token;
+
+    for endpoint in [admin_json.data(state.clone()).boxed(), moderation.data(state.clone()).boxed()] {
+        let req = Request::builder()
+            .header("Authorization", format!("Bearer {}", token))
+            .finish();
+        let resp = endpoint.get_response(req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(json_body(resp).await, serde_json::json!({ "error": "Not Found" }));
+    }
+}
+
+#[tokio::test]
+async fn test_result_handler_allows_member() {
+    let resp = admin_json.get_response(request_with_groups(vec!["admins"])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), r#"{"admin":"alice"}"#);
+
+    let resp = admin_text.get_response(request_with_groups(vec!["admins"])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_result_handler_denies_with_denial_response() {
+    for resp in [
+        admin_json.get_response(request_with_groups(vec!["users"])).await,
+        admin_text.get_response(request_with_groups(vec!["users"])).await,
+    ] {
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let body = json_body(resp).await;
+        assert_eq!(body["error"], "Forbidden: requires 'admins' group");
+        assert_eq!(body["required"], serde_json::json!(["admins"]));
+        assert_eq!(body["mode"], "single");
+    }
+}
+
+#[tokio::test]
+async fn test_any_scope_allows_matching_scope() {
+    let resp = list_users.get_response(request_with_scopes(vec!["admin"])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "users for alice");
+}
+
+#[tokio::test]
+async fn test_any_scope_denies_without_match() {
+    let resp = list_users.get_response(request_with_scopes(vec!["write:users"])).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let body = resp.into_body().into_string().await.unwrap();
+    assert!(body.contains("requires one of scopes: read:users, admin"));
+}
+
+#[tokio::test]
+async fn test_all_scopes_requires_every_scope() {
+    let resp = edit_user
+        .get_response(request_with_scopes(vec!["read:users", "write:users"]))
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = edit_user.get_response(request_with_scopes(vec!["read:users"])).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,44 @@
+//! Testing a protected handler with the `testing` harness.
+
+#![cfg(all(feature = "testing", feature = "macros"))]
+
+use poem::http::StatusCode;
+use poem::{handler, Endpoint, IntoResponse, Request, Response};
+use poem_auth::testing::{test_state, test_token};
+use poem_auth::{require_group, PoemAppState, UserClaims};
+
+#[require_group("admins")]
+#[handler]
+async fn admin_panel(claims: UserClaims) -> Response {
+    format!("welcome {}", claims.sub).into_response()
+}
+
+fn with_token(header: String) -> Request {
+    Request::builder().header("Authorization", header).finish()
+}
+
+#[tokio::test]
+async fn test_harness_token_reaches_protected_handler() {
+    let resp = admin_panel
+        .get_response(with_token(test_token("alice", vec!["admins"])))
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "welcome alice");
+
+    let resp = admin_panel
+        .get_response(with_token(test_token("bob", vec!["users"])))
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = admin_panel.get_response(Request::builder().finish()).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[test]
+fn test_token_verifies_against_test_state() {
+    let header = test_token("alice", vec!["admins"]);
+    let claims = test_state().jwt.verify_from_header(&header).unwrap();
+    assert_eq!(claims.sub, "alice");
+    assert_eq!(claims.groups, vec!["admins".to_string()]);
+    assert!(std::ptr::eq(test_state(), PoemAppState::get()));
+}
@@ -0,0 +1,72 @@
+//! Routes reading tokens from different sources in one app.
+
+use std::sync::Arc;
+
+use poem::http::StatusCode;
+use poem::{get, handler, Endpoint, EndpointExt, Request, Route};
+use poem_auth::providers::LocalAuthProvider;
+use poem_auth::{AuthEndpointExt, AuthLayer, ClaimsExt, MemoryUserDb, PoemAppState, TokenSource, UserClaims};
+
+#[handler]
+fn whoami(claims: UserClaims) -> String {
+    claims.sub
+}
+
+#[handler]
+fn layered(ClaimsExt(claims): ClaimsExt) -> String {
+    claims.sub
+}
+
+fn state() -> PoemAppState {
+    let provider = Arc::new(LocalAuthProvider::new(MemoryUserDb::new()));
+    PoemAppState::with_provider(provider, "token-source-test-key-0123456789abcdef").unwrap()
+}
+
+fn token(state: &PoemAppState, sub: &str) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = UserClaims::new(sub, "local", now + 3600, now).with_groups(vec!["users"]);
+    state.jwt.generate_token(&claims).unwrap().token
+}
+
+fn bearer(uri: &str, token: &str) -> Request {
+    Request::builder()
+        .uri(uri.parse().unwrap())
+        .header("Authorization", format!("Bearer {}", token))
+        .finish()
+}
+
+fn cookie(uri: &str, token: &str) -> Request {
+    Request::builder()
+        .uri(uri.parse().unwrap())
+        .header("Cookie", format!("session={}", token))
+        .finish()
+}
+
+#[tokio::test]
+async fn test_routes_override_token_source() {
+    let state = state();
+    let session = TokenSource::Cookie("session".to_string());
+    let app = Route::new()
+        .at("/api/me", get(whoami))
+        .at("/app/me", get(whoami).with(session.clone()))
+        .at("/app/admin", get(whoami).require_group("users").with(session.clone()))
+        .at("/app/layered", get(layered).with(AuthLayer::new().with_token_source(session)))
+        .data(state.clone());
+    let alice = token(&state, "alice");
+
+    // The API keeps the state's bearer source
+    let resp = app.get_response(bearer("/api/me", &alice)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "alice");
+    let resp = app.get_response(cookie("/api/me", &alice)).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Browser routes only read the session cookie
+    for uri in ["/app/me", "/app/admin", "/app/layered"] {
+        let resp = app.get_response(cookie(uri, &alice)).await;
+        assert_eq!(resp.status(), StatusCode::OK, "{}", uri);
+        assert_eq!(resp.into_body().into_string().await.unwrap(), "alice");
+        let resp = app.get_response(bearer(uri, &alice)).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+    }
+}
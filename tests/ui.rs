@@ -0,0 +1,10 @@
+//! Compile-time behaviour of the authorization macros.
+
+#![cfg(feature = "macros")]
+
+#[test]
+fn test_macro_ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/pass/*.rs");
+    t.compile_fail("tests/ui/fail/*.rs");
+}
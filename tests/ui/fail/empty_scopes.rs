@@ -0,0 +1,7 @@
+#[poem_auth::require_any_scope()]
+#[poem::handler]
+async fn nothing_required(claims: poem_auth::UserClaims) -> String {
+    claims.sub
+}
+
+fn main() {}
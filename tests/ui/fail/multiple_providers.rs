@@ -0,0 +1,7 @@
+#[poem_auth::require_provider("ldap", "local")]
+#[poem::handler]
+async fn either_provider(claims: poem_auth::UserClaims) -> String {
+    claims.sub
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[poem_auth::require_group("admins")]
+#[poem_auth::require_group()]
+#[poem::handler]
+async fn incomplete(claims: poem_auth::UserClaims) -> String {
+    claims.sub
+}
+
+fn main() {}
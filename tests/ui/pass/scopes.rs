@@ -0,0 +1,19 @@
+use poem::{handler, IntoResponse, Response};
+use poem_auth::{require_all_scopes, require_any_scope, UserClaims};
+
+#[require_any_scope("read:users", "admin")]
+#[handler]
+async fn read_users(claims: UserClaims) -> Response {
+    format!("users for {}", claims.sub).into_response()
+}
+
+#[require_all_scopes("read:users", "write:users")]
+#[poem_auth::require_group("admins")]
+#[handler]
+async fn edit_users(claims: UserClaims) -> poem::Result<String> {
+    Ok(claims.sub.clone())
+}
+
+fn main() {
+    let _ = (read_users, edit_users);
+}